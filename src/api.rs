@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Stable library facade for embedding cgrep in other Rust tools.
+//!
+//! The CLI commands print; everything here returns typed results instead.
+//!
+//! ```no_run
+//! use cgrep::api::{IndexOptions, Indexer, SearchRequest, Searcher};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! Indexer::build(&IndexOptions::new("/path/to/repo"))?;
+//! let searcher = Searcher::open("/path/to/repo")?;
+//! let response = searcher.search(&SearchRequest::new("parse_config"))?;
+//! for result in &response.results {
+//!     println!("{}:{:?}", result.path, result.line);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::output::OutputFormat;
+use crate::query::index_filter::open_index;
+use crate::query::search::{self, IndexMode, SearchOptions};
+use crate::query::{callers, definition, dependents, references, symbols};
+use crate::utils::{get_root_with_index, INDEX_DIR};
+
+pub use crate::hybrid::SearchMode;
+pub use crate::indexer::index::{
+    BuildStats, EmbeddingIndexStats, EmbeddingsMode, IndexOptions, IndexSummary,
+};
+pub use crate::parser::references::ReferenceKind;
+pub use crate::query::callers::CallerResult;
+pub use crate::query::definition::DefinitionResult;
+pub use crate::query::dependents::DependentResult;
+pub use crate::query::references::ReferenceResult;
+pub use crate::query::scope::ContextScope;
+pub use crate::query::search::SearchResult;
+pub use crate::query::symbols::{SymbolFilters, SymbolResult};
+pub use crate::session::AlreadySent;
+
+/// Default result limit when neither the request nor the config sets one
+const DEFAULT_MAX_RESULTS: usize = 20;
+
+/// Builds and updates the index for a project root
+pub struct Indexer;
+
+impl Indexer {
+    /// Build or incrementally update the index, honoring `.cgreprc.toml`
+    pub fn build(options: &IndexOptions) -> Result<IndexSummary> {
+        crate::indexer::index::build(options)
+    }
+}
+
+/// A search query; unset fields fall back to the project config
+#[derive(Debug, Clone, Default)]
+pub struct SearchRequest {
+    pub query: String,
+    /// Restrict the search to this path, relative to the searcher root
+    pub path: Option<PathBuf>,
+    pub max_results: Option<usize>,
+    /// Cap the returned snippets and context at this many estimated tokens
+    pub max_tokens: Option<usize>,
+    /// Lines of context before and after each match
+    pub context: usize,
+    /// Return the enclosing symbol (or its signature) instead of `context` lines
+    pub scope: ContextScope,
+    /// Defaults to the config's `search.default_mode`, then keyword
+    pub mode: Option<SearchMode>,
+    pub file_type: Option<String>,
+    pub glob: Option<String>,
+    pub exclude: Option<String>,
+    /// Only files changed since this git revision
+    pub changed: Option<String>,
+    pub fuzzy: bool,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Scan files instead of using the index
+    pub no_index: bool,
+    /// Agent session: hits it already received move to `already_sent`
+    pub session: Option<String>,
+}
+
+impl SearchRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Results of [`Searcher::search`]
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    /// Hits the request's session already received
+    pub already_sent: Vec<AlreadySent>,
+    pub files_with_matches: usize,
+    pub total_matches: usize,
+    /// Mode the search actually ran in
+    pub mode: SearchMode,
+    /// False when the search fell back to scanning files
+    pub used_index: bool,
+    pub elapsed: Duration,
+}
+
+/// Query handle for one project root, keeping its index open between calls
+pub struct Searcher {
+    root: PathBuf,
+    index_root: PathBuf,
+    config: Config,
+}
+
+impl Searcher {
+    /// Open `root`, using the nearest index at or above it when one exists
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Cannot open {}", root.display()))?;
+        let index_root = get_root_with_index(root.clone());
+        let index_path = index_root.join(INDEX_DIR);
+        if index_path.exists() {
+            open_index(&index_path)?;
+        }
+        let config = Config::load_for_dir(&index_root);
+        Ok(Self {
+            root,
+            index_root,
+            config,
+        })
+    }
+
+    /// Directory searches are scoped to
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding the index (the root itself or an ancestor)
+    pub fn index_root(&self) -> &Path {
+        &self.index_root
+    }
+
+    /// Full-text, semantic or hybrid search
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResponse> {
+        let scope = match &request.path {
+            Some(path) => self.root.join(path),
+            None => self.root.clone(),
+        };
+        let options = SearchOptions {
+            query: request.query.clone(),
+            path: Some(scope.display().to_string()),
+            max_results: request
+                .max_results
+                .or(self.config.max_results)
+                .unwrap_or(DEFAULT_MAX_RESULTS),
+            max_tokens: request.max_tokens,
+            context: request.context,
+            scope: request.scope,
+            file_type: request.file_type.clone(),
+            glob: request.glob.clone(),
+            exclude: request.exclude.clone(),
+            changed: request.changed.clone(),
+            fuzzy: request.fuzzy,
+            no_index: request.no_index,
+            regex: request.regex,
+            case_sensitive: request.case_sensitive,
+            search_mode: request
+                .mode
+                .or(self.config.search.default_mode.map(Into::into)),
+            cache_ttl: Some(self.config.cache.ttl_ms()),
+            session: request.session.clone(),
+            ..SearchOptions::default()
+        };
+        let report = search::execute_with_config(&options, OutputFormat::Json, &self.config)?;
+        Ok(SearchResponse {
+            used_index: report.outcome.mode == IndexMode::Index,
+            files_with_matches: report.outcome.files_with_matches,
+            total_matches: report.outcome.total_matches,
+            results: report.outcome.results,
+            already_sent: report.already_sent,
+            mode: report.search_mode,
+            elapsed: report.elapsed,
+        })
+    }
+
+    /// Symbols whose name contains `name`
+    pub fn symbols(&self, name: &str, filters: &SymbolFilters<'_>) -> Result<Vec<SymbolResult>> {
+        let (results, _) = symbols::find(&self.index_root, &self.config, name, filters)?;
+        Ok(results)
+    }
+
+    /// Definitions of `name`; qualified names (`Foo::build`) narrow the match
+    pub fn definition(&self, name: &str) -> Result<Vec<DefinitionResult>> {
+        definition::find(&self.index_root, name)
+    }
+
+    /// Occurrences of `name`, optionally limited to some reference kinds
+    pub fn references(
+        &self,
+        name: &str,
+        kinds: &[ReferenceKind],
+        max_results: usize,
+    ) -> Result<Vec<ReferenceResult>> {
+        references::find(&self.index_root, name, max_results, None, kinds, false)
+    }
+
+    /// Call sites of `function`
+    pub fn callers(&self, function: &str) -> Result<Vec<CallerResult>> {
+        callers::find(&self.index_root, function)
+    }
+
+    /// Files that import `file` (relative to the searcher root)
+    pub fn dependents(&self, file: impl AsRef<Path>) -> Result<Vec<DependentResult>> {
+        let file = self.root.join(file);
+        dependents::find(&self.index_root, &file.display().to_string())
+    }
+}
@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Batch query mode.
+//!
+//! `cgrep batch` reads one JSON request per line from stdin and writes one
+//! JSON response per line to stdout, in request order, as each finishes:
+//!
+//! ```text
+//! {"id": 1, "command": "search", "args": {"query": "parse_config", "limit": 5}}
+//! {"id": 2, "command": "agent locate", "args": {"query": "config loading"}}
+//! ```
+//!
+//! Commands are the query subcommands of the CLI: the MCP tools (see
+//! [`McpServer`]) plus `callgraph`, `deps`, `map` and `outline`. Arguments
+//! take the tool names or the CLI long flags (`max-results`, `type`, `kind`),
+//! with paths relative to the repository root; `agent locate` and
+//! `agent_locate` are equivalent. The index, config and query embedding
+//! provider are loaded once for the whole batch.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::mcp::McpServer;
+use cgrep::query::index_filter::preload_index;
+use cgrep::utils::get_root_with_index;
+
+/// CLI flags spelled differently from the tool argument they set:
+/// (command, flag, argument)
+const CLI_ARGUMENTS: &[(&str, &str, &str)] = &[
+    ("search", "type", "file_type"),
+    ("search", "max_results", "limit"),
+    ("agent_locate", "max_results", "limit"),
+    ("agent_expand", "id", "ids"),
+    ("symbols", "type", "kind"),
+    ("references", "kind", "kinds"),
+    ("references", "max_results", "limit"),
+];
+
+/// Rename CLI flag spellings to tool arguments. List arguments also take the
+/// CLI's comma-separated form (`"kind": "read,write"`).
+fn tool_arguments(command: &str, args: Map<String, Value>) -> Map<String, Value> {
+    let mut renamed = Map::new();
+    for (key, value) in args {
+        let key = key.replace('-', "_");
+        let key = CLI_ARGUMENTS
+            .iter()
+            .find(|(cmd, flag, _)| *cmd == command && *flag == key)
+            .map(|(_, _, arg)| arg.to_string())
+            .unwrap_or(key);
+        let value = match value {
+            Value::String(list) if matches!(key.as_str(), "ids" | "kinds" | "files") => list
+                .split(',')
+                .map(|item| Value::String(item.trim().to_string()))
+                .collect(),
+            value => value,
+        };
+        renamed.entry(key).or_insert(value);
+    }
+    renamed
+}
+
+/// One parsed batch line.
+struct BatchRequest {
+    id: Value,
+    command: String,
+    args: Map<String, Value>,
+}
+
+fn parse_request(line: &str) -> std::result::Result<BatchRequest, (Value, anyhow::Error)> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|err| (Value::Null, anyhow!("invalid JSON: {}", err)))?;
+    let Value::Object(mut object) = value else {
+        return Err((Value::Null, anyhow!("request must be a JSON object")));
+    };
+    let id = object.remove("id").unwrap_or(Value::Null);
+    let parsed = (|| {
+        let command = match object.remove("command") {
+            Some(Value::String(command)) => command,
+            Some(_) => bail!("`command` must be a string"),
+            None => bail!("missing `command`"),
+        };
+        let args = match object.remove("args") {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(args)) => args,
+            Some(_) => bail!("`args` must be an object"),
+        };
+        // Mirror the CLI spelling of subcommands: `agent locate` -> `agent_locate`
+        let command = command.split_whitespace().collect::<Vec<_>>().join("_");
+        let args = tool_arguments(&command, args);
+        Ok((command, args))
+    })();
+    match parsed {
+        Ok((command, args)) => Ok(BatchRequest { id, command, args }),
+        Err(err) => Err((id, err)),
+    }
+}
+
+/// Answer every request in `input`, writing one response line each.
+///
+/// Returns the number of failed requests.
+pub fn serve<R: BufRead, W: Write>(server: &McpServer, input: R, mut output: W) -> Result<usize> {
+    let mut failures = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let start = Instant::now();
+        let response = match parse_request(&line) {
+            Ok(request) => match server.run_command(&request.command, &request.args) {
+                Ok(result) => json!({
+                    "id": request.id,
+                    "ok": true,
+                    "meta": {
+                        "command": request.command,
+                        "elapsed_ms": start.elapsed().as_millis() as u64,
+                    },
+                    "result": result,
+                }),
+                Err(err) => {
+                    failures += 1;
+                    json!({
+                        "id": request.id,
+                        "ok": false,
+                        "meta": {"command": request.command},
+                        "error": format!("{:#}", err),
+                    })
+                }
+            },
+            Err((id, err)) => {
+                failures += 1;
+                json!({"id": id, "ok": false, "error": format!("{:#}", err)})
+            }
+        };
+        serde_json::to_writer(&mut output, &response)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    Ok(failures)
+}
+
+/// Run `cgrep batch` on stdin/stdout
+pub fn run(path: Option<&str>) -> Result<()> {
+    let start = match path {
+        Some(path) => PathBuf::from(path).canonicalize()?,
+        None => std::env::current_dir()?,
+    };
+    let root = get_root_with_index(start);
+    preload_index(&root);
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let failures = serve(&McpServer::new(root), stdin.lock(), stdout.lock())?;
+    if failures > 0 {
+        eprintln!("{} batch request(s) failed", failures);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgrep::api::{IndexOptions, Indexer};
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    #[test]
+    fn answers_each_line_in_order() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn batch_target() {}\n\nfn caller() {\n    batch_target();\n}\n",
+        )
+        .expect("write");
+        let server = McpServer::new(dir.path().to_path_buf());
+        let input = [
+            r#"{"id": 1, "command": "search", "args": {"query": "batch_target"}}"#,
+            "",
+            r#"{"id": "def", "command": "definition", "args": {"name": "batch_target"}}"#,
+            r#"{"id": 3, "command": "agent locate", "args": {"query": "batch_target"}}"#,
+            r#"{"id": 4, "command": "index"}"#,
+            r#"{"id": 5, "command": "callers"}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        let failures = serve(&server, Cursor::new(input), &mut output).expect("serve");
+        let responses: Vec<Value> = String::from_utf8(output)
+            .expect("utf8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+
+        assert_eq!(responses.len(), 6);
+        assert_eq!(failures, 3);
+
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["ok"], true);
+        assert_eq!(responses[0]["meta"]["command"], "search");
+        assert_eq!(responses[0]["result"]["results"][0]["path"], "lib.rs");
+
+        assert_eq!(responses[1]["id"], "def");
+        assert_eq!(responses[1]["result"]["results"][0]["line"], 1);
+
+        assert_eq!(responses[2]["meta"]["command"], "agent_locate");
+        assert!(responses[2]["result"]["meta"].is_object());
+
+        assert_eq!(responses[3]["ok"], false);
+        assert!(responses[3]["error"]
+            .as_str()
+            .expect("error")
+            .contains("Unknown command"));
+        assert!(responses[4]["error"]
+            .as_str()
+            .expect("error")
+            .contains("function"));
+        assert_eq!(responses[5]["id"], Value::Null);
+        assert_eq!(responses[5]["ok"], false);
+    }
+
+    #[test]
+    fn dispatches_cli_query_commands_with_cli_flags() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("src")).expect("mkdir");
+        std::fs::write(
+            dir.path().join("src/store.rs"),
+            "pub fn load() -> u32 {\n    1\n}\n",
+        )
+        .expect("write store");
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "mod store;\n\nfn main() {\n    store::load();\n}\n",
+        )
+        .expect("write main");
+        Indexer::build(&IndexOptions::new(dir.path())).expect("index");
+        let server = McpServer::new(dir.path().to_path_buf());
+        let input = [
+            r#"{"id": 1, "command": "callgraph", "args": {"function": "load", "max-nodes": 10}}"#,
+            r#"{"id": 2, "command": "deps", "args": {"file": "src/store.rs", "reverse": true}}"#,
+            r#"{"id": 3, "command": "map", "args": {"path": "src", "budget": 200}}"#,
+            r#"{"id": 4, "command": "outline", "args": {"files": "src/store.rs"}}"#,
+            r#"{"id": 5, "command": "search", "args": {"query": "load", "type": "rust", "max-results": 1}}"#,
+            r#"{"id": 6, "command": "references", "args": {"name": "load", "kind": "definition"}}"#,
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        let failures = serve(&server, Cursor::new(input), &mut output).expect("serve");
+        let responses: Vec<Value> = String::from_utf8(output)
+            .expect("utf8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(failures, 0, "{:?}", responses);
+
+        assert_eq!(responses[0]["result"]["nodes"], json!(["load", "main"]));
+        assert_eq!(responses[1]["result"]["results"][0]["path"], "src/main.rs");
+        assert_eq!(responses[2]["result"]["meta"]["path"], "src");
+        assert_eq!(
+            responses[3]["result"]["results"][0]["symbols"][0]["name"],
+            "load"
+        );
+        assert_eq!(
+            responses[4]["result"]["results"]
+                .as_array()
+                .expect("results")
+                .len(),
+            1
+        );
+        let references = responses[5]["result"]["results"]
+            .as_array()
+            .expect("references");
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0]["path"], "src/store.rs");
+    }
+}
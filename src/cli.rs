@@ -5,6 +5,12 @@
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
 
+pub use cgrep::output::OutputFormat;
+use cgrep::parser::references::ReferenceKind;
+pub use cgrep::query::callgraph::CallDirection;
+pub use cgrep::query::graph::GraphFormat;
+pub use cgrep::query::scope::ContextScope;
+
 /// cgrep - Local semantic code search tool
 ///
 /// A high-performance search tool combining AST analysis with BM25 ranking.
@@ -25,15 +31,6 @@ pub struct Cli {
     pub command: Commands,
 }
 
-/// Output format for results
-#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
-pub enum OutputFormat {
-    Text,
-    Json,
-    /// Structured JSON for AI agents (`meta` + `results`)
-    Json2,
-}
-
 /// Search mode for queries
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
 pub enum CliSearchMode {
@@ -55,6 +52,33 @@ pub enum CliBudgetPreset {
     Off,
 }
 
+/// Occurrence kind filter for references
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CliReferenceKind {
+    Definition,
+    Read,
+    Write,
+    Import,
+    Comment,
+    String,
+    /// Matches in files without a supported grammar
+    Text,
+}
+
+impl From<CliReferenceKind> for ReferenceKind {
+    fn from(kind: CliReferenceKind) -> Self {
+        match kind {
+            CliReferenceKind::Definition => ReferenceKind::Definition,
+            CliReferenceKind::Read => ReferenceKind::Read,
+            CliReferenceKind::Write => ReferenceKind::Write,
+            CliReferenceKind::Import => ReferenceKind::Import,
+            CliReferenceKind::Comment => ReferenceKind::Comment,
+            CliReferenceKind::String => ReferenceKind::String,
+            CliReferenceKind::Text => ReferenceKind::Text,
+        }
+    }
+}
+
 /// Agent provider for install/uninstall commands
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum AgentProvider {
@@ -64,11 +88,43 @@ pub enum AgentProvider {
     Opencode,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum DaemonCommands {
+    /// Serve queries for an index root in the foreground
+    Start {
+        /// Index root to serve (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Do not watch files for changes
+        #[arg(long)]
+        no_watch: bool,
+
+        /// Watcher debounce interval in seconds
+        #[arg(long, default_value = "2")]
+        debounce: u64,
+    },
+    /// Stop the daemon for an index root
+    Stop {
+        /// Index root (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+    /// Show whether a daemon is running for an index root
+    Status {
+        /// Index root (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum AgentCommands {
     /// Stage 1: locate candidate code regions with minimal payload
     Locate {
-        /// Search query (natural language or keywords)
+        /// Search query (natural language or keywords; `lang:`, `path:`, `kind:`, `sym:`,
+        /// "phrases", `-term` and `OR` filter and combine terms)
+        #[arg(allow_hyphen_values = true)]
         query: String,
 
         /// Path to search in (defaults to current directory)
@@ -90,6 +146,14 @@ pub enum AgentCommands {
         /// Output budget preset (default: balanced)
         #[arg(long, value_enum)]
         budget: Option<CliBudgetPreset>,
+
+        /// Cap the payload at N estimated tokens (replaces the preset's total cap)
+        #[arg(long)]
+        max_tokens: Option<usize>,
+
+        /// Agent session ID: hits it already received come back as references
+        #[arg(long)]
+        session: Option<String>,
     },
 
     /// Stage 2: expand selected locate result IDs into richer context
@@ -105,6 +169,40 @@ pub enum AgentCommands {
         /// Context lines to return around each ID match
         #[arg(short = 'C', long)]
         context: Option<usize>,
+
+        /// Return the enclosing symbol (or its signature) instead of -C lines
+        #[arg(long, value_enum)]
+        scope: Option<ContextScope>,
+
+        /// Agent session ID: windows it already received come back as references
+        #[arg(long)]
+        session: Option<String>,
+    },
+
+    /// Locate, then expand the best hits into one budgeted payload
+    Context {
+        /// Task or question (natural language or keywords)
+        task: String,
+
+        /// Path to search in (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Limit search to files changed since revision (default: HEAD)
+        #[arg(long, num_args = 0..=1, default_missing_value = "HEAD")]
+        changed: Option<String>,
+
+        /// Maximum number of candidate hits to consider
+        #[arg(short = 'm', long = "limit")]
+        limit: Option<usize>,
+
+        /// Search mode: keyword, semantic, or hybrid
+        #[arg(long, value_enum)]
+        mode: Option<CliSearchMode>,
+
+        /// Token budget for the returned context (default: 4000)
+        #[arg(long)]
+        budget: Option<usize>,
     },
 
     /// Install cgrep instructions for an AI agent provider
@@ -125,8 +223,9 @@ pub enum Commands {
     /// Full-text search with BM25 ranking
     #[command(alias = "s")]
     Search {
-        /// Search query (natural language or keywords)
-        #[arg(required_unless_present = "help_advanced")]
+        /// Search query (natural language or keywords; `lang:`, `path:`, `kind:`, `sym:`,
+        /// "phrases", `-term` and `OR` filter and combine terms)
+        #[arg(required_unless_present = "help_advanced", allow_hyphen_values = true)]
         query: Option<String>,
 
         /// Path to search in (defaults to current directory)
@@ -146,6 +245,10 @@ pub enum Commands {
         #[arg(short = 'C', long, help_heading = "Core")]
         context: Option<usize>,
 
+        /// Show the enclosing symbol (or its signature) instead of -C lines
+        #[arg(long, value_enum, help_heading = "Core")]
+        scope: Option<ContextScope>,
+
         /// Filter by file type/language (e.g., rust, ts, python)
         #[arg(short = 't', long = "type", help_heading = "Core")]
         file_type: Option<String>,
@@ -171,6 +274,14 @@ pub enum Commands {
         #[arg(long, value_enum, help_heading = "Core")]
         budget: Option<CliBudgetPreset>,
 
+        /// Cap the payload at N estimated tokens (replaces the preset's total cap)
+        #[arg(long, help_heading = "Core")]
+        max_tokens: Option<usize>,
+
+        /// Agent session ID: hits it already received come back as references
+        #[arg(long, help_heading = "Core")]
+        session: Option<String>,
+
         /// Use a preset profile (human, agent, fast)
         #[arg(long, help_heading = "Core")]
         profile: Option<String>,
@@ -320,6 +431,28 @@ pub enum Commands {
         function: String,
     },
 
+    /// Build a transitive call graph rooted at a function
+    Callgraph {
+        /// Function name at the root of the graph
+        function: String,
+
+        /// Maximum traversal depth
+        #[arg(short, long, default_value_t = 3)]
+        depth: usize,
+
+        /// Traversal direction: up (callers) or down (callees)
+        #[arg(long, value_enum, default_value_t = CallDirection::Up)]
+        direction: CallDirection,
+
+        /// Stop expanding once this many nodes are in the graph
+        #[arg(long, default_value_t = 200)]
+        max_nodes: usize,
+
+        /// Render the graph as DOT or Mermaid instead of text/json
+        #[arg(long, value_enum)]
+        graph: Option<GraphFormat>,
+    },
+
     /// Find all references to a symbol
     #[command(alias = "refs")]
     References {
@@ -342,15 +475,61 @@ pub enum Commands {
         /// Limit references to files changed since revision (default: HEAD)
         #[arg(long, num_args = 0..=1, default_missing_value = "HEAD")]
         changed: Option<String>,
+
+        /// Only show occurrences of these kinds (repeatable or comma-separated)
+        #[arg(short = 'k', long = "kind", value_enum, value_delimiter = ',')]
+        kinds: Vec<CliReferenceKind>,
+
+        /// Skip occurrences inside comments and string literals
+        #[arg(long)]
+        exclude_comments: bool,
     },
 
     /// Find files that depend on a given file
-    #[command(alias = "deps")]
     Dependents {
         /// File path to find dependents for
         file: String,
     },
 
+    /// Query the file dependency graph recorded by the index
+    Deps {
+        /// File to query (omit to inspect the whole graph)
+        file: Option<String>,
+
+        /// Show files that import FILE instead of files it imports
+        #[arg(short, long)]
+        reverse: bool,
+
+        /// Follow imports transitively instead of direct ones only
+        #[arg(short, long)]
+        transitive: bool,
+
+        /// Render the graph as DOT or Mermaid instead of text/json
+        #[arg(long, value_enum)]
+        graph: Option<GraphFormat>,
+    },
+
+    /// Ranked skeleton of the most referenced symbols, for orientation
+    Map {
+        /// Directory or file to map (defaults to the whole repository)
+        path: Option<String>,
+
+        /// Token budget for the map (default: 1024)
+        #[arg(long)]
+        budget: Option<usize>,
+
+        /// Render the map as Markdown instead of text/json
+        #[arg(long)]
+        markdown: bool,
+    },
+
+    /// Nested symbol tree of files, with kinds, line ranges and signatures
+    Outline {
+        /// Files to outline
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
     /// Build or rebuild the search index
     Index {
         /// Path to index (defaults to current directory)
@@ -389,6 +568,33 @@ pub enum Commands {
         debounce: u64,
     },
 
+    /// Resident query daemon that keeps the index warm
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommands,
+    },
+
+    /// Answer newline-delimited JSON requests from stdin, one response per line
+    Batch {
+        /// Repository to query (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+
+    /// Serve cgrep tools over the Model Context Protocol (stdio)
+    Mcp {
+        /// Repository to serve (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+
+    /// Serve code navigation over the Language Server Protocol (stdio)
+    Lsp {
+        /// Workspace to serve when the client sends no root (defaults to current directory)
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+
     /// Install cgrep for Claude Code
     #[command(name = "install-claude-code", hide = true)]
     InstallClaudeCode,
@@ -8,6 +8,9 @@ use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::embedding::DEFAULT_NPROBE;
+use crate::hybrid::FusionMethod;
+
 /// Output format for results (mirrored from cli for library use)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -47,6 +50,8 @@ pub enum EmbeddingProviderType {
     Dummy,
     /// Command provider (external process).
     Command,
+    /// HTTP provider speaking the OpenAI-compatible `/embeddings` API.
+    Http,
 }
 
 /// Search configuration
@@ -61,6 +66,10 @@ pub struct SearchConfig {
     pub weight_text: Option<f32>,
     /// Weight for vector/semantic scoring in hybrid mode (0.0-1.0)
     pub weight_vector: Option<f32>,
+    /// How hybrid mode merges keyword and vector candidates (weighted, rrf)
+    pub fusion: Option<FusionMethod>,
+    /// IVF clusters scanned per semantic query (more is slower but finds more)
+    pub nprobe: Option<usize>,
 }
 
 impl SearchConfig {
@@ -83,6 +92,16 @@ impl SearchConfig {
     pub fn weight_vector(&self) -> f32 {
         self.weight_vector.unwrap_or(0.3)
     }
+
+    /// Get hybrid fusion method (defaults to Weighted)
+    pub fn fusion(&self) -> FusionMethod {
+        self.fusion.unwrap_or_default()
+    }
+
+    /// Get IVF clusters scanned per query (defaults to 8)
+    pub fn nprobe(&self) -> usize {
+        self.nprobe.unwrap_or(DEFAULT_NPROBE).max(1)
+    }
 }
 
 /// Embedding configuration
@@ -101,6 +120,20 @@ pub struct EmbeddingConfig {
     pub model: Option<String>,
     /// Command to execute for command provider
     pub command: Option<String>,
+    /// Base URL of the HTTP provider; requests go to `{base_url}/embeddings`
+    pub base_url: Option<String>,
+    /// Environment variable holding a bearer token for the HTTP provider
+    pub api_key_env: Option<String>,
+    /// Extra HTTP headers; `${VAR}` in values is read from the environment
+    pub headers: Option<HashMap<String, String>>,
+    /// HTTP request timeout in milliseconds
+    pub timeout_ms: Option<u64>,
+    /// Retries for failed HTTP requests (connection errors, 429 and 5xx)
+    pub max_retries: Option<u32>,
+    /// Initial retry delay in milliseconds, doubled on every retry
+    pub retry_backoff_ms: Option<u64>,
+    /// Concurrent HTTP requests per embedding batch
+    pub concurrency: Option<usize>,
     /// Number of lines per chunk
     pub chunk_lines: Option<usize>,
     /// Number of overlap lines between chunks
@@ -150,6 +183,31 @@ impl EmbeddingConfig {
         self.command.as_deref().unwrap_or("embedder")
     }
 
+    /// Get HTTP base URL (if configured)
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// Get HTTP request timeout (defaults to 30000ms)
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(30_000)
+    }
+
+    /// Get HTTP retry count (defaults to 3)
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(3)
+    }
+
+    /// Get initial HTTP retry delay (defaults to 500ms)
+    pub fn retry_backoff_ms(&self) -> u64 {
+        self.retry_backoff_ms.unwrap_or(500)
+    }
+
+    /// Get concurrent HTTP requests (defaults to 4)
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(4).max(1)
+    }
+
     /// Get chunk lines (defaults to 80)
     pub fn chunk_lines(&self) -> usize {
         self.chunk_lines.unwrap_or(80)
@@ -201,6 +259,8 @@ pub struct IndexConfig {
     pub exclude_paths: Vec<String>,
     /// Maximum file size in bytes to index (default: 1MB)
     pub max_file_size: Option<u64>,
+    /// Stem identifier sub-words in new indexes (default: false)
+    pub stemming: Option<bool>,
 }
 
 impl IndexConfig {
@@ -213,6 +273,11 @@ impl IndexConfig {
     pub fn max_file_size(&self) -> u64 {
         self.max_file_size.unwrap_or(1024 * 1024)
     }
+
+    /// Whether to stem identifier sub-words (default: false)
+    pub fn stemming(&self) -> bool {
+        self.stemming.unwrap_or(false)
+    }
 }
 
 /// Cache configuration
@@ -223,6 +288,8 @@ pub struct CacheConfig {
     pub enabled: Option<bool>,
     /// Cache TTL in milliseconds
     pub ttl_ms: Option<u64>,
+    /// How long `--session` deliveries are remembered, in milliseconds
+    pub session_ttl_ms: Option<u64>,
 }
 
 impl CacheConfig {
@@ -235,6 +302,12 @@ impl CacheConfig {
     pub fn ttl_ms(&self) -> u64 {
         self.ttl_ms.unwrap_or(600_000)
     }
+
+    /// Get session TTL in milliseconds (defaults to 1800000 = 30 minutes)
+    pub fn session_ttl_ms(&self) -> u64 {
+        self.session_ttl_ms
+            .unwrap_or(crate::session::SessionStore::DEFAULT_TTL_MS)
+    }
 }
 
 /// Profile configuration for different usage modes
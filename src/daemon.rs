@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Resident query daemon.
+//!
+//! `cgrep daemon start` keeps the tantivy reader, the query embedding provider
+//! and a file watcher alive for one index root and answers requests on a Unix
+//! socket at `.cgrep/daemon.sock`. `cgrep search` (and `agent locate`) look
+//! for that socket and hand the query to the daemon, falling back to running
+//! in-process when no daemon answers. Set `CGREP_NO_DAEMON=1` to bypass it.
+//!
+//! The wire format is one JSON object per line in each direction.
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::query::search::{SearchOptions, SearchReport};
+use crate::utils::{find_index_root, get_root_with_index, INDEX_DIR};
+
+/// Socket file inside the index directory.
+pub const SOCKET_FILE: &str = "daemon.sock";
+
+/// Environment variable that disables routing to a running daemon.
+const NO_DAEMON_ENV: &str = "CGREP_NO_DAEMON";
+
+/// How long the CLI waits for a daemon answer before searching in-process.
+#[cfg(unix)]
+const CLIENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "lowercase")]
+enum Request {
+    Status,
+    Search {
+        options: Box<SearchOptions>,
+        format: OutputFormat,
+    },
+    Shutdown,
+}
+
+/// A request stamped with the client version; daemons only serve their own.
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    version: String,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Response {
+    Ok(Value),
+    Error(String),
+}
+
+/// Daemon state reported by `cgrep daemon status`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub version: String,
+    pub pid: u32,
+    pub root: String,
+    pub uptime_secs: u64,
+    pub requests: u64,
+    pub watching: bool,
+}
+
+fn socket_path(root: &Path) -> PathBuf {
+    root.join(INDEX_DIR).join(SOCKET_FILE)
+}
+
+/// Send one request to the daemon serving `root`.
+///
+/// Returns `Ok(None)` when no daemon is listening (including stale sockets).
+#[cfg(unix)]
+fn send(root: &Path, request: Request) -> Result<Option<Value>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let path = socket_path(root);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let Ok(mut stream) = UnixStream::connect(&path) else {
+        return Ok(None);
+    };
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let envelope = Envelope {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        request,
+    };
+    serde_json::to_writer(&mut stream, &envelope)?;
+    stream.write_all(b"\n")?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match serde_json::from_str::<Response>(&line)? {
+        Response::Ok(value) => Ok(Some(value)),
+        Response::Error(message) => bail!(message),
+    }
+}
+
+#[cfg(not(unix))]
+fn send(_root: &Path, _request: Request) -> Result<Option<Value>> {
+    Ok(None)
+}
+
+/// Run a search through the daemon for the search root's index, if one is up.
+///
+/// Any failure yields `None` so the caller searches in-process and reports
+/// errors itself.
+pub fn search(options: &SearchOptions, format: OutputFormat) -> Option<SearchReport> {
+    if std::env::var_os(NO_DAEMON_ENV).is_some() {
+        return None;
+    }
+    let cwd = std::env::current_dir().ok()?;
+    let search_root = match options.path.as_deref() {
+        Some(path) => cwd.join(path),
+        None => cwd,
+    };
+    let index_root = find_index_root(&search_root)?.root;
+
+    // The daemon has its own working directory; send an absolute scope
+    let mut options = Box::new(options.clone());
+    options.path = Some(search_root.display().to_string());
+    let value = send(&index_root, Request::Search { options, format }).ok()??;
+    serde_json::from_value(value).ok()
+}
+
+#[cfg(unix)]
+mod server {
+    use super::*;
+    use crate::indexer::watch::Watcher;
+    use crate::query::index_filter::preload_index;
+    use crate::query::search;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    struct State {
+        root: PathBuf,
+        started: Instant,
+        requests: AtomicU64,
+        watching: bool,
+        stopping: AtomicBool,
+    }
+
+    impl State {
+        fn dispatch(&self, envelope: Envelope) -> Response {
+            if envelope.version != env!("CARGO_PKG_VERSION") {
+                return Response::Error(format!(
+                    "daemon version {} does not match client {}",
+                    env!("CARGO_PKG_VERSION"),
+                    envelope.version
+                ));
+            }
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let result = match envelope.request {
+                Request::Status => serde_json::to_value(self.status()).map_err(Into::into),
+                Request::Search { options, format } => search::execute(&options, format)
+                    .and_then(|report| Ok(serde_json::to_value(report)?)),
+                Request::Shutdown => {
+                    self.stopping.store(true, Ordering::SeqCst);
+                    Ok(Value::Null)
+                }
+            };
+            match result {
+                Ok(value) => Response::Ok(value),
+                Err(err) => Response::Error(format!("{:#}", err)),
+            }
+        }
+
+        fn status(&self) -> DaemonStatus {
+            DaemonStatus {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                pid: std::process::id(),
+                root: self.root.display().to_string(),
+                uptime_secs: self.started.elapsed().as_secs(),
+                requests: self.requests.load(Ordering::Relaxed),
+                watching: self.watching,
+            }
+        }
+    }
+
+    fn handle_connection(stream: UnixStream, state: &State) -> Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Envelope>(&line) {
+                Ok(envelope) => state.dispatch(envelope),
+                Err(err) => Response::Error(format!("invalid request: {}", err)),
+            };
+            serde_json::to_writer(&mut writer, &response)?;
+            writer.write_all(b"\n")?;
+            if state.stopping.load(Ordering::SeqCst) {
+                // Wake the accept loop so it sees the shutdown
+                let _ = UnixStream::connect(socket_path(&state.root));
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Serve requests for `root` until a shutdown request arrives.
+    pub fn serve(root: PathBuf, watch_debounce: Option<u64>) -> Result<()> {
+        let socket = socket_path(&root);
+        if socket.exists() {
+            if UnixStream::connect(&socket).is_ok() {
+                bail!("A daemon is already running for {}", root.display());
+            }
+            // Left behind by a daemon that did not shut down cleanly
+            std::fs::remove_file(&socket)?;
+        }
+
+        let listener = UnixListener::bind(&socket)?;
+        std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+        preload_index(&root);
+
+        if let Some(debounce) = watch_debounce {
+            let watch_root = root.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = Watcher::with_debounce(&watch_root, debounce).watch() {
+                    eprintln!("{} Watcher stopped: {:#}", "✗".red(), err);
+                }
+            });
+        }
+
+        let state = Arc::new(State {
+            root: root.clone(),
+            started: Instant::now(),
+            requests: AtomicU64::new(0),
+            watching: watch_debounce.is_some(),
+            stopping: AtomicBool::new(false),
+        });
+        println!(
+            "{} cgrep daemon serving {} (pid {})",
+            "✓".green(),
+            root.display(),
+            std::process::id()
+        );
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("{} Connection failed: {}", "✗".red(), err);
+                    continue;
+                }
+            };
+            let connection_state = Arc::clone(&state);
+            std::thread::spawn(move || {
+                if let Err(err) = handle_connection(stream, &connection_state) {
+                    eprintln!("{} Request failed: {:#}", "✗".red(), err);
+                }
+            });
+            if state.stopping.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+
+        let _ = std::fs::remove_file(&socket);
+        println!("{} cgrep daemon stopped", "✓".green());
+        Ok(())
+    }
+}
+
+fn daemon_root(path: Option<&str>) -> Result<PathBuf> {
+    let start = match path {
+        Some(path) => PathBuf::from(path).canonicalize()?,
+        None => std::env::current_dir()?,
+    };
+    Ok(get_root_with_index(start))
+}
+
+/// Run `cgrep daemon start` in the foreground
+pub fn run_start(path: Option<&str>, watch_debounce: Option<u64>) -> Result<()> {
+    let root = daemon_root(path)?;
+    if !root.join(INDEX_DIR).exists() {
+        bail!(
+            "No index found at {}\n\nSuggestion: Run 'cgrep index' before starting the daemon.",
+            root.display()
+        );
+    }
+    #[cfg(unix)]
+    {
+        server::serve(root, watch_debounce)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = watch_debounce;
+        bail!("cgrep daemon requires Unix domain sockets, which this platform lacks")
+    }
+}
+
+/// Run `cgrep daemon stop`
+pub fn run_stop(path: Option<&str>) -> Result<()> {
+    let root = daemon_root(path)?;
+    match send(&root, Request::Shutdown)? {
+        Some(_) => println!("{} Stopped daemon for {}", "✓".green(), root.display()),
+        None => println!("{} No daemon running for {}", "✗".red(), root.display()),
+    }
+    Ok(())
+}
+
+/// Run `cgrep daemon status`
+pub fn run_status(path: Option<&str>, format: OutputFormat, compact: bool) -> Result<()> {
+    let root = daemon_root(path)?;
+    let status: Option<DaemonStatus> = send(&root, Request::Status)?
+        .map(serde_json::from_value)
+        .transpose()?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Json2 => {
+            print_json(
+                &serde_json::json!({ "running": status.is_some(), "daemon": status }),
+                compact,
+            )?;
+        }
+        OutputFormat::Text => match status {
+            Some(status) => {
+                println!(
+                    "{} Daemon running for {} (pid {}, v{})",
+                    "✓".green(),
+                    status.root.cyan(),
+                    status.pid,
+                    status.version
+                );
+                println!(
+                    "  uptime {}s | {} requests | watcher {}",
+                    status.uptime_secs,
+                    status.requests,
+                    if status.watching { "on" } else { "off" }
+                );
+            }
+            None => println!("{} No daemon running for {}", "✗".red(), root.display()),
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Inverted-file (IVF) index for approximate nearest-neighbour search.
+//!
+//! Embeddings are partitioned into clusters with spherical k-means. A query
+//! only scores the vectors in its closest clusters instead of every stored
+//! embedding. [`EmbeddingStorage`](super::EmbeddingStorage) persists the
+//! centroids and the cluster of each symbol, so the index lives on disk next
+//! to the vectors and new symbols are assigned as they are written.
+
+use rayon::prelude::*;
+
+/// Below this many embeddings an exact scan is fast enough; no index is built.
+pub const MIN_ANN_VECTORS: usize = 1024;
+
+/// Default number of clusters scanned per query.
+pub const DEFAULT_NPROBE: usize = 8;
+
+/// Upper bound on the number of clusters, which bounds assignment cost.
+const MAX_LISTS: usize = 1024;
+
+/// Training sample size per cluster; larger repositories are subsampled.
+pub const TRAINING_POINTS_PER_LIST: usize = 64;
+
+const TRAIN_ITERATIONS: usize = 10;
+
+/// Cluster centroids of an IVF index (unit length).
+#[derive(Debug, Clone)]
+pub struct IvfIndex {
+    centroids: Vec<Vec<f32>>,
+}
+
+impl IvfIndex {
+    /// Rebuild an index from stored centroids.
+    pub fn from_centroids(centroids: Vec<Vec<f32>>) -> Self {
+        Self { centroids }
+    }
+
+    /// Number of clusters to use for `count` vectors (about `sqrt(count)`).
+    pub fn suggested_lists(count: usize) -> usize {
+        ((count as f64).sqrt().round() as usize).clamp(1, MAX_LISTS)
+    }
+
+    /// Train `lists` centroids on `sample` with spherical k-means.
+    ///
+    /// Vectors whose dimension differs from the first one are ignored.
+    /// Returns `None` when there is nothing to train on.
+    pub fn train(sample: &[Vec<f32>], lists: usize) -> Option<Self> {
+        let dimension = sample.first()?.len();
+        let points: Vec<Vec<f32>> = sample
+            .iter()
+            .filter(|v| v.len() == dimension && dimension > 0)
+            .map(|v| normalized(v))
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+
+        // Seed with evenly spaced sample points so training is deterministic.
+        let lists = lists.clamp(1, points.len());
+        let step = points.len() / lists;
+        let mut centroids: Vec<Vec<f32>> = (0..lists).map(|i| points[i * step].clone()).collect();
+
+        for _ in 0..TRAIN_ITERATIONS {
+            let assignments: Vec<(usize, f32)> = points
+                .par_iter()
+                .map(|point| nearest(&centroids, point))
+                .collect();
+
+            let mut sums = vec![vec![0.0f32; dimension]; lists];
+            let mut counts = vec![0usize; lists];
+            for (point, (list, _)) in points.iter().zip(&assignments) {
+                counts[*list] += 1;
+                for (sum, value) in sums[*list].iter_mut().zip(point) {
+                    *sum += value;
+                }
+            }
+
+            // Reseed empty clusters with the points that fit their cluster worst.
+            let mut worst: Vec<usize> = (0..points.len()).collect();
+            worst.sort_by(|a, b| assignments[*a].1.total_cmp(&assignments[*b].1));
+            let mut worst = worst.into_iter();
+
+            for (list, sum) in sums.into_iter().enumerate() {
+                if counts[list] > 0 {
+                    centroids[list] = normalized(&sum);
+                } else if let Some(point) = worst.next() {
+                    centroids[list] = points[point].clone();
+                }
+            }
+        }
+
+        Some(Self { centroids })
+    }
+
+    pub fn centroids(&self) -> &[Vec<f32>] {
+        &self.centroids
+    }
+
+    pub fn list_count(&self) -> usize {
+        self.centroids.len()
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.centroids.first().map_or(0, Vec::len)
+    }
+
+    /// Cluster a vector belongs to, or `None` if its dimension does not match.
+    pub fn assign(&self, vector: &[f32]) -> Option<usize> {
+        if vector.len() != self.dimension() || self.centroids.is_empty() {
+            return None;
+        }
+        Some(nearest(&self.centroids, &normalized(vector)).0)
+    }
+
+    /// The `nprobe` clusters closest to `query`, best first.
+    pub fn probe(&self, query: &[f32], nprobe: usize) -> Vec<usize> {
+        if query.len() != self.dimension() {
+            return Vec::new();
+        }
+        let query = normalized(query);
+        let mut scored: Vec<(usize, f32)> = self
+            .centroids
+            .iter()
+            .enumerate()
+            .map(|(list, centroid)| (list, dot(centroid, &query)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored
+            .into_iter()
+            .take(nprobe.max(1))
+            .map(|(list, _)| list)
+            .collect()
+    }
+}
+
+/// Closest centroid to a unit-length point and its similarity.
+fn nearest(centroids: &[Vec<f32>], point: &[f32]) -> (usize, f32) {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(list, centroid)| (list, dot(centroid, point)))
+        .fold((0, f32::NEG_INFINITY), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let magnitude = dot(vector, vector).sqrt();
+    if magnitude == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / magnitude).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train_separates_clusters() {
+        let mut sample = Vec::new();
+        for i in 0..20 {
+            let jitter = i as f32 * 0.01;
+            sample.push(vec![1.0, jitter, 0.0]);
+            sample.push(vec![0.0, jitter, 1.0]);
+        }
+
+        let index = IvfIndex::train(&sample, 2).unwrap();
+        assert_eq!(index.list_count(), 2);
+        assert_eq!(index.dimension(), 3);
+
+        let x = index.assign(&[2.0, 0.0, 0.0]).unwrap();
+        let z = index.assign(&[0.0, 0.0, 3.0]).unwrap();
+        assert_ne!(x, z);
+        assert_eq!(index.probe(&[1.0, 0.1, 0.0], 1), vec![x]);
+        assert!(index.assign(&[1.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn suggested_lists_grow_with_sqrt() {
+        assert_eq!(IvfIndex::suggested_lists(0), 1);
+        assert_eq!(IvfIndex::suggested_lists(10_000), 100);
+        assert_eq!(IvfIndex::suggested_lists(50_000_000), MAX_LISTS);
+        assert!(IvfIndex::train(&[], 4).is_none());
+    }
+}
@@ -5,13 +5,16 @@
 //! This module provides storage and retrieval of embedding vectors for symbols,
 //! enabling semantic similarity search on top of the BM25 text search.
 
+pub mod ann;
 pub mod chunker;
 pub mod provider;
 pub mod storage;
 
+pub use ann::{IvfIndex, DEFAULT_NPROBE, MIN_ANN_VECTORS};
 pub use chunker::{ChunkConfig, EmbeddingChunker, TextChunk};
 pub use provider::{
     CommandProvider, DummyProvider, EmbeddingProvider, EmbeddingProviderConfig, FastEmbedder,
+    HttpProvider, HttpProviderConfig,
 };
 pub use storage::{
     EmbeddingStorage, SimilarityResult, SymbolEmbedding, SymbolEmbeddingInput,
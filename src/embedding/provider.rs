@@ -2,7 +2,8 @@
 
 //! Embedding provider interface and implementations.
 //!
-//! This module provides a fastembed-based provider optimized for CPU throughput.
+//! This module provides a fastembed-based provider optimized for CPU throughput,
+//! plus command and HTTP providers for external embedding services.
 
 use anyhow::{bail, Context, Result};
 #[cfg(not(all(target_os = "macos", target_arch = "x86_64")))]
@@ -12,12 +13,18 @@ use std::borrow::Cow;
 use std::env;
 use std::io::Write;
 use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::EmbeddingConfig;
 
 const DEFAULT_FASTEMBED_MODEL: &str = "minilm";
 const DEFAULT_FASTEMBED_BATCH_SIZE: usize = 4;
 const MAX_FASTEMBED_BATCH_SIZE: usize = 1024;
 const DEFAULT_FASTEMBED_MAX_CHARS: usize = 2000;
 const DEFAULT_COMMAND_BATCH_SIZE: usize = 64;
+const DEFAULT_HTTP_BATCH_SIZE: usize = 32;
+/// Upper bound on a server-requested `Retry-After` delay.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
 
 #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
 #[derive(Debug, Clone)]
@@ -285,18 +292,7 @@ impl CommandProvider {
             .as_array()
             .ok_or_else(|| anyhow::anyhow!("Embeddings output must be a JSON array"))?
             .iter()
-            .map(|row| {
-                row.as_array()
-                    .ok_or_else(|| anyhow::anyhow!("Embedding row must be an array"))?
-                    .iter()
-                    .map(|value| {
-                        value
-                            .as_f64()
-                            .ok_or_else(|| anyhow::anyhow!("Embedding value must be a number"))
-                            .map(|v| v as f32)
-                    })
-                    .collect::<Result<Vec<f32>>>()
-            })
+            .map(parse_vector)
             .collect::<Result<Vec<Vec<f32>>>>()?;
 
         Ok(vectors)
@@ -320,6 +316,208 @@ impl EmbeddingProvider for CommandProvider {
     }
 }
 
+/// Settings for [`HttpProvider`].
+#[derive(Debug, Clone)]
+pub struct HttpProviderConfig {
+    /// Base URL; requests are posted to `{base_url}/embeddings`
+    pub base_url: String,
+    pub model: String,
+    /// Headers sent with every request (already resolved)
+    pub headers: Vec<(String, String)>,
+    pub timeout: Duration,
+    /// Retries after the first attempt for connection errors, 429 and 5xx
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every further retry
+    pub retry_backoff: Duration,
+    /// Texts per request
+    pub batch_size: usize,
+    /// Requests in flight at once
+    pub concurrency: usize,
+}
+
+impl HttpProviderConfig {
+    /// Builds the provider settings from `[embeddings]`.
+    ///
+    /// Fails if `base_url` is missing or a referenced environment variable
+    /// (`api_key_env` or `${VAR}` in a header value) is not set.
+    pub fn from_embedding_config(config: &EmbeddingConfig) -> Result<Self> {
+        Self::from_embedding_config_with(config, |name| env::var(name).ok())
+    }
+
+    /// Like [`Self::from_embedding_config`], resolving variables through `lookup`.
+    pub fn from_embedding_config_with(
+        config: &EmbeddingConfig,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let base_url = config
+            .base_url()
+            .context("HTTP embedding provider requires [embeddings].base_url")?
+            .to_string();
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        if let Some(name) = config.api_key_env.as_deref() {
+            let token = lookup(name).with_context(|| {
+                format!("Environment variable {} (api_key_env) is not set", name)
+            })?;
+            headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        if let Some(configured) = &config.headers {
+            let mut configured: Vec<(&String, &String)> = configured.iter().collect();
+            configured.sort();
+            for (name, value) in configured {
+                headers.push((name.clone(), expand_env_vars(value, &lookup)?));
+            }
+        }
+
+        Ok(Self {
+            base_url,
+            model: config.model().to_string(),
+            headers,
+            timeout: Duration::from_millis(config.timeout_ms()),
+            max_retries: config.max_retries(),
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms()),
+            batch_size: config
+                .batch_size()
+                .unwrap_or(DEFAULT_HTTP_BATCH_SIZE)
+                .max(1),
+            concurrency: config.concurrency(),
+        })
+    }
+}
+
+/// HTTP provider for OpenAI-compatible embedding endpoints (OpenAI, Ollama,
+/// llama.cpp, vLLM, text-embeddings-inference, ...).
+///
+/// Sends `{"model": ..., "input": [...]}` and accepts either the OpenAI
+/// `{"data": [{"embedding": [...], "index": 0}]}` response or a bare
+/// `{"embeddings": [[...]]}` one.
+pub struct HttpProvider {
+    agent: ureq::Agent,
+    endpoint: String,
+    config: HttpProviderConfig,
+}
+
+impl HttpProvider {
+    pub fn new(config: HttpProviderConfig) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(config.timeout).build();
+        let endpoint = format!("{}/embeddings", config.base_url.trim_end_matches('/'));
+        Self {
+            agent,
+            endpoint,
+            config,
+        }
+    }
+
+    pub fn from_embedding_config(config: &EmbeddingConfig) -> Result<Self> {
+        HttpProviderConfig::from_embedding_config(config).map(Self::new)
+    }
+
+    /// Embeds one request's worth of texts, retrying transient failures.
+    fn post_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = serde_json::json!({
+            "model": self.config.model,
+            "input": texts,
+        })
+        .to_string();
+
+        let mut attempt = 0u32;
+        loop {
+            let mut request = self
+                .agent
+                .post(&self.endpoint)
+                .set("Content-Type", "application/json");
+            for (name, value) in &self.config.headers {
+                request = request.set(name, value);
+            }
+
+            let backoff = self.config.retry_backoff * 2u32.saturating_pow(attempt);
+            let retry_after = match request.send_string(&body) {
+                Ok(response) => {
+                    let text = response
+                        .into_string()
+                        .context("Failed to read embedding response")?;
+                    return parse_http_embeddings(&text, texts.len());
+                }
+                Err(ureq::Error::Status(status, response))
+                    if (status == 429 || status >= 500) && attempt < self.config.max_retries =>
+                {
+                    response
+                        .header("Retry-After")
+                        .and_then(|value| value.trim().parse::<u64>().ok())
+                        .map(|secs| Duration::from_secs(secs).min(MAX_RETRY_AFTER))
+                        .unwrap_or(backoff)
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    let detail = response.into_string().unwrap_or_default();
+                    bail!(
+                        "Embedding endpoint {} returned HTTP {}: {}",
+                        self.endpoint,
+                        status,
+                        truncate_to_chars(detail.trim(), 200)
+                    );
+                }
+                Err(ureq::Error::Transport(err)) if attempt < self.config.max_retries => {
+                    tracing::debug!("embedding request failed, retrying: {}", err);
+                    backoff
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Embedding request to {} failed", self.endpoint))
+                }
+            };
+
+            std::thread::sleep(retry_after);
+            attempt += 1;
+        }
+    }
+}
+
+impl EmbeddingProvider for HttpProvider {
+    fn model_id(&self) -> &str {
+        &self.config.model
+    }
+
+    /// One batch from the indexer fills every concurrent request.
+    fn batch_size(&self) -> usize {
+        self.config.batch_size * self.config.concurrency
+    }
+
+    fn embed_texts(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let requests: Vec<&[String]> = texts.chunks(self.config.batch_size).collect();
+        let mut vectors = Vec::with_capacity(texts.len());
+        for wave in requests.chunks(self.config.concurrency) {
+            if let [single] = wave {
+                vectors.extend(self.post_batch(single)?);
+                continue;
+            }
+            let this = &*self;
+            let results: Vec<Result<Vec<Vec<f32>>>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = wave
+                    .iter()
+                    .map(|batch| scope.spawn(move || this.post_batch(batch)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|_| Err(anyhow::anyhow!("Embedding request panicked")))
+                    })
+                    .collect()
+            });
+            for result in results {
+                vectors.extend(result?);
+            }
+        }
+
+        Ok(vectors)
+    }
+}
+
 /// Dummy provider that returns zero vectors (for testing/fallback).
 pub struct DummyProvider {
     model: String,
@@ -354,6 +552,80 @@ impl EmbeddingProvider for DummyProvider {
     }
 }
 
+/// Parses a JSON array of numbers into an embedding vector.
+fn parse_vector(row: &Value) -> Result<Vec<f32>> {
+    row.as_array()
+        .ok_or_else(|| anyhow::anyhow!("Embedding row must be an array"))?
+        .iter()
+        .map(|value| {
+            value
+                .as_f64()
+                .ok_or_else(|| anyhow::anyhow!("Embedding value must be a number"))
+                .map(|v| v as f32)
+        })
+        .collect()
+}
+
+/// Parses an `/embeddings` response body, restoring input order by `index`.
+fn parse_http_embeddings(body: &str, expected: usize) -> Result<Vec<Vec<f32>>> {
+    let parsed: Value =
+        serde_json::from_str(body).context("Failed to parse embedding response as JSON")?;
+
+    let vectors = if let Some(data) = parsed.get("data").and_then(Value::as_array) {
+        let mut rows: Vec<(u64, Vec<f32>)> = data
+            .iter()
+            .enumerate()
+            .map(|(position, item)| {
+                let index = item
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(position as u64);
+                let embedding = item.get("embedding").ok_or_else(|| {
+                    anyhow::anyhow!("Embedding response item missing 'embedding'")
+                })?;
+                Ok((index, parse_vector(embedding)?))
+            })
+            .collect::<Result<_>>()?;
+        rows.sort_by_key(|(index, _)| *index);
+        rows.into_iter().map(|(_, vector)| vector).collect()
+    } else if let Some(embeddings) = parsed.get("embeddings").and_then(Value::as_array) {
+        embeddings
+            .iter()
+            .map(parse_vector)
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        bail!("Embedding response missing 'data' or 'embeddings' field");
+    };
+
+    if vectors.len() != expected {
+        bail!(
+            "Embedding endpoint returned {} vectors for {} inputs",
+            vectors.len(),
+            expected
+        );
+    }
+    Ok(vectors)
+}
+
+/// Replaces `${VAR}` references with the values `lookup` resolves.
+fn expand_env_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let resolved =
+            lookup(name).with_context(|| format!("Environment variable {} is not set", name))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&resolved);
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
 fn truncate_texts<'a>(texts: &'a [String], max_chars: usize) -> Vec<Cow<'a, str>> {
     texts
         .iter()
@@ -465,6 +737,9 @@ fn normalize_max_chars(max_chars: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
 
     struct FixedSizeProvider {
         size: usize,
@@ -569,4 +844,176 @@ mod tests {
             Cow::<str>::Owned("가나다".to_string())
         );
     }
+
+    /// Minimal HTTP server answering `count` requests with `respond(request_body)`;
+    /// joins to the raw requests it received.
+    fn mock_server<F>(count: usize, respond: F) -> (String, JoinHandle<Vec<String>>)
+    where
+        F: Fn(&str) -> (u16, String) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for _ in 0..count {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+
+                let (status, response) = respond(&body);
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+                requests.push(head + &body);
+            }
+            requests
+        });
+        (base_url, handle)
+    }
+
+    fn http_config(base_url: String) -> HttpProviderConfig {
+        HttpProviderConfig {
+            base_url,
+            model: "test-model".to_string(),
+            headers: Vec::new(),
+            timeout: Duration::from_secs(5),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            batch_size: 8,
+            concurrency: 1,
+        }
+    }
+
+    #[test]
+    fn test_http_provider_openai_response() {
+        // Items arrive out of order and are restored by `index`.
+        let (base_url, server) = mock_server(1, |_| {
+            (
+                200,
+                r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#
+                    .to_string(),
+            )
+        });
+        let env = |name: &str| match name {
+            "CGREP_TEST_HTTP_TOKEN" => Some("secret".to_string()),
+            "CGREP_TEST_HTTP_TEAM" => Some("search".to_string()),
+            _ => None,
+        };
+        let config = EmbeddingConfig {
+            base_url: Some(base_url),
+            model: Some("nomic-embed-text".to_string()),
+            api_key_env: Some("CGREP_TEST_HTTP_TOKEN".to_string()),
+            headers: Some(
+                [(
+                    "X-Team".to_string(),
+                    "team-${CGREP_TEST_HTTP_TEAM}".to_string(),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let mut provider = HttpProvider::new(
+            HttpProviderConfig::from_embedding_config_with(&config, env).unwrap(),
+        );
+
+        let vectors = provider
+            .embed_texts(&["first".to_string(), "second".to_string()])
+            .unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let request = server.join().unwrap().remove(0);
+        assert!(request.starts_with("POST /v1/embeddings "));
+        assert!(request.contains("Authorization: Bearer secret"));
+        assert!(request.contains("X-Team: team-search"));
+        assert!(request.ends_with(r#"{"input":["first","second"],"model":"nomic-embed-text"}"#));
+
+        let missing = EmbeddingConfig {
+            base_url: Some("http://localhost".to_string()),
+            api_key_env: Some("CGREP_TEST_HTTP_UNSET".to_string()),
+            ..Default::default()
+        };
+        assert!(HttpProviderConfig::from_embedding_config_with(&missing, env).is_err());
+    }
+
+    #[test]
+    fn test_http_provider_retries_transient_errors() {
+        let attempts = std::sync::atomic::AtomicUsize::new(0);
+        let (base_url, server) = mock_server(3, move |_| {
+            match attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => (503, "busy".to_string()),
+                1 => (429, "slow down".to_string()),
+                _ => (200, r#"{"embeddings":[[0.5,0.5]]}"#.to_string()),
+            }
+        });
+        let mut provider = HttpProvider::new(http_config(base_url));
+        let vector = provider.embed_one("text").unwrap();
+        assert_eq!(vector, vec![0.5, 0.5]);
+        assert_eq!(server.join().unwrap().len(), 3);
+
+        // Client errors are not retried.
+        let (base_url, server) = mock_server(1, |_| (400, "bad model".to_string()));
+        let mut provider = HttpProvider::new(http_config(base_url));
+        let err = provider.embed_one("text").unwrap_err().to_string();
+        assert!(
+            err.contains("HTTP 400") && err.contains("bad model"),
+            "{}",
+            err
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_provider_concurrent_batches_keep_order() {
+        // Each text embeds to its length, so order mismatches would show.
+        let (base_url, server) = mock_server(3, |body| {
+            let request: Value = serde_json::from_str(body).unwrap();
+            let data: Vec<Value> = request["input"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .map(|(index, text)| {
+                    serde_json::json!({"index": index, "embedding": [text.as_str().unwrap().len()]})
+                })
+                .collect();
+            (200, serde_json::json!({ "data": data }).to_string())
+        });
+        let mut provider = HttpProvider::new(HttpProviderConfig {
+            batch_size: 2,
+            concurrency: 2,
+            ..http_config(base_url)
+        });
+        assert_eq!(provider.batch_size(), 4);
+
+        let texts: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let vectors = provider.embed_texts(&texts).unwrap();
+        let lengths: Vec<f32> = vectors.into_iter().map(|v| v[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
 }
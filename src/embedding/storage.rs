@@ -4,12 +4,16 @@
 //!
 //! This module provides persistent storage for embedding vectors associated with
 //! symbols. It supports CRUD operations, incremental updates based on file
-//! hashes, and brute-force cosine similarity search.
+//! hashes, and cosine similarity search: approximate through an IVF index
+//! once the repository is large enough, brute-force otherwise.
 
 use anyhow::{Context, Result};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use super::ann::{IvfIndex, MIN_ANN_VECTORS, TRAINING_POINTS_PER_LIST};
+
 /// Default embedding dimension for sentence-transformers/all-MiniLM-L6-v2.
 pub const DEFAULT_EMBEDDING_DIM: usize = 384;
 
@@ -61,12 +65,18 @@ pub struct SimilarityResult {
     pub score: f32,
 }
 
+/// Columns read by [`EmbeddingStorage::symbol_from_row`].
+const SYMBOL_COLUMNS: &str = "symbol_id, path, lang, symbol_kind, symbol_name, start_line, \
+     end_line, file_hash, content_hash, embedding, created_at";
+
 /// SQLite-based storage for embedding vectors.
 ///
 /// Stores embeddings in `.cgrep/embeddings.sqlite` by default.
 pub struct EmbeddingStorage {
     conn: Connection,
     path: PathBuf,
+    /// Trained IVF centroids, if an ANN index has been built
+    ann: Option<IvfIndex>,
 }
 
 impl EmbeddingStorage {
@@ -89,9 +99,14 @@ impl EmbeddingStorage {
         )?;
         let bootstrap_meta = has_tables == 0;
 
-        let storage = Self { conn, path };
+        let mut storage = Self {
+            conn,
+            path,
+            ann: None,
+        };
         storage.init_schema(bootstrap_meta)?;
         storage.ensure_symbol_schema()?;
+        storage.ann = storage.load_ann()?;
 
         Ok(storage)
     }
@@ -123,12 +138,18 @@ impl EmbeddingStorage {
                 file_hash TEXT NOT NULL,
                 content_hash TEXT NOT NULL,
                 embedding BLOB NOT NULL,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                ann_list INTEGER
             );
 
             CREATE INDEX IF NOT EXISTS idx_symbol_embeddings_path_line
                 ON symbol_embeddings(path, start_line, end_line);
 
+            CREATE TABLE IF NOT EXISTS ann_centroids (
+                list_id INTEGER PRIMARY KEY,
+                centroid BLOB NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS symbol_files (
                 path TEXT PRIMARY KEY,
                 file_hash TEXT NOT NULL,
@@ -148,23 +169,24 @@ impl EmbeddingStorage {
     }
 
     fn ensure_symbol_schema(&self) -> Result<()> {
+        // Databases written before the ANN index existed lack the cluster column.
+        if !self.has_column("symbol_embeddings", "ann_list")? {
+            self.conn.execute(
+                "ALTER TABLE symbol_embeddings ADD COLUMN ann_list INTEGER",
+                [],
+            )?;
+        }
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_symbol_embeddings_ann_list ON symbol_embeddings(ann_list)",
+            [],
+        )?;
+
         let unit = self.get_meta("unit")?;
         if unit.as_deref() != Some("symbol") {
             return Ok(());
         }
 
-        let mut stmt = self.conn.prepare("PRAGMA table_info(symbol_embeddings)")?;
-        let mut rows = stmt.query([])?;
-        let mut has_content_hash = false;
-        while let Some(row) = rows.next()? {
-            let name: String = row.get(1)?;
-            if name == "content_hash" {
-                has_content_hash = true;
-                break;
-            }
-        }
-
-        if !has_content_hash {
+        if !self.has_column("symbol_embeddings", "content_hash")? {
             self.conn.execute(
                 "ALTER TABLE symbol_embeddings ADD COLUMN content_hash TEXT NOT NULL DEFAULT ''",
                 [],
@@ -175,8 +197,38 @@ impl EmbeddingStorage {
         Ok(())
     }
 
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            if name == column {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Loads stored IVF centroids, if any.
+    fn load_ann(&self) -> Result<Option<IvfIndex>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT centroid FROM ann_centroids ORDER BY list_id")?;
+        let centroids = stmt
+            .query_map([], |row| {
+                let blob: Vec<u8> = row.get(0)?;
+                Ok(Self::blob_to_embedding(&blob))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load ANN centroids")?;
+
+        Ok((!centroids.is_empty()).then(|| IvfIndex::from_centroids(centroids)))
+    }
+
     /// Resets the database schema, dropping old tables.
-    pub fn reset_schema(&self) -> Result<()> {
+    pub fn reset_schema(&mut self) -> Result<()> {
         self.conn
             .execute_batch(
                 r#"
@@ -185,12 +237,15 @@ impl EmbeddingStorage {
             DROP TABLE IF EXISTS embeddings_meta;
             DROP TABLE IF EXISTS symbol_embeddings;
             DROP TABLE IF EXISTS symbol_files;
+            DROP TABLE IF EXISTS ann_centroids;
             DROP TABLE IF EXISTS meta;
             "#,
             )
             .context("Failed to reset embedding schema")?;
 
-        self.init_schema(true)
+        self.ann = None;
+        self.init_schema(true)?;
+        self.ensure_symbol_schema()
     }
 
     /// Returns the path to the database file.
@@ -237,9 +292,9 @@ impl EmbeddingStorage {
                 r#"
                 INSERT INTO symbol_embeddings (
                     symbol_id, path, lang, symbol_kind, symbol_name, start_line, end_line,
-                    file_hash, content_hash, embedding, created_at
+                    file_hash, content_hash, embedding, created_at, ann_list
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
                 "#,
             )?;
 
@@ -256,7 +311,8 @@ impl EmbeddingStorage {
                     file_hash,
                     symbol.content_hash,
                     embedding_blob,
-                    created_at
+                    created_at,
+                    Self::ann_list_for(self.ann.as_ref(), symbol.embedding)
                 ])?;
             }
         }
@@ -348,9 +404,9 @@ impl EmbeddingStorage {
                 r#"
                 INSERT INTO symbol_embeddings (
                     symbol_id, path, lang, symbol_kind, symbol_name, start_line, end_line,
-                    file_hash, content_hash, embedding, created_at
+                    file_hash, content_hash, embedding, created_at, ann_list
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
                 ON CONFLICT(symbol_id) DO UPDATE SET
                     path = excluded.path,
                     lang = excluded.lang,
@@ -361,7 +417,8 @@ impl EmbeddingStorage {
                     file_hash = excluded.file_hash,
                     content_hash = excluded.content_hash,
                     embedding = excluded.embedding,
-                    created_at = excluded.created_at
+                    created_at = excluded.created_at,
+                    ann_list = excluded.ann_list
                 "#,
             )?;
 
@@ -378,7 +435,8 @@ impl EmbeddingStorage {
                     file_hash,
                     symbol.content_hash,
                     embedding_blob,
-                    created_at
+                    created_at,
+                    Self::ann_list_for(self.ann.as_ref(), symbol.embedding)
                 ])?;
             }
         }
@@ -468,6 +526,32 @@ impl EmbeddingStorage {
         Ok(symbol)
     }
 
+    /// Retrieves several symbol embeddings at once, keyed by symbol ID.
+    ///
+    /// Unknown IDs are simply absent from the result.
+    pub fn get_symbols(&self, symbol_ids: &[&str]) -> Result<HashMap<String, SymbolEmbedding>> {
+        let mut symbols = HashMap::with_capacity(symbol_ids.len());
+
+        for chunk in symbol_ids.chunks(900) {
+            let placeholders: Vec<String> = (1..=chunk.len()).map(|i| format!("?{}", i)).collect();
+            let sql = format!(
+                "SELECT {} FROM symbol_embeddings WHERE symbol_id IN ({})",
+                SYMBOL_COLUMNS,
+                placeholders.join(", ")
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(chunk), Self::symbol_from_row)?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .context("Failed to query symbols")?;
+            for symbol in rows {
+                symbols.insert(symbol.symbol_id.clone(), symbol);
+            }
+        }
+
+        Ok(symbols)
+    }
+
     /// Deletes all symbol embeddings for a given file path.
     pub fn delete_file_symbols(&self, path: &str) -> Result<usize> {
         let deleted = self
@@ -484,17 +568,19 @@ impl EmbeddingStorage {
         Ok(deleted)
     }
 
-    /// Deletes all embeddings from the database.
-    pub fn clear_all(&self) -> Result<()> {
+    /// Deletes all embeddings (and the ANN index) from the database.
+    pub fn clear_all(&mut self) -> Result<()> {
         self.conn
             .execute_batch(
                 r#"
             DELETE FROM symbol_embeddings;
             DELETE FROM symbol_files;
+            DELETE FROM ann_centroids;
             "#,
             )
             .context("Failed to clear all embeddings")?;
 
+        self.ann = None;
         Ok(())
     }
 
@@ -554,41 +640,190 @@ impl EmbeddingStorage {
 
     /// Performs brute-force similarity search across all embeddings.
     ///
-    /// Returns symbols sorted by descending cosine similarity.
+    /// Returns symbols sorted by descending cosine similarity. This is the
+    /// exact reference for [`search_approximate`](Self::search_approximate).
     pub fn search_similar(
         &self,
         query_embedding: &[f32],
         top_k: usize,
     ) -> Result<Vec<SimilarityResult>> {
-        let mut stmt = self.conn.prepare(
+        let sql = format!("SELECT {} FROM symbol_embeddings", SYMBOL_COLUMNS);
+        self.score_rows(&sql, query_embedding, top_k)
+    }
+
+    /// Performs approximate similarity search through the IVF index.
+    ///
+    /// Only symbols in the `nprobe` clusters closest to the query (plus any
+    /// symbols without a cluster) are scored. Falls back to
+    /// [`search_similar`](Self::search_similar) when no index has been built
+    /// or its dimension does not match the query.
+    pub fn search_approximate(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        nprobe: usize,
+    ) -> Result<Vec<SimilarityResult>> {
+        let Some(ann) = self
+            .ann
+            .as_ref()
+            .filter(|ann| ann.dimension() == query_embedding.len())
+        else {
+            return self.search_similar(query_embedding, top_k);
+        };
+
+        let lists: Vec<String> = ann
+            .probe(query_embedding, nprobe)
+            .into_iter()
+            .map(|list| list.to_string())
+            .collect();
+        let sql = format!(
+            "SELECT {} FROM symbol_embeddings WHERE ann_list IS NULL OR ann_list IN ({})",
+            SYMBOL_COLUMNS,
+            lists.join(", ")
+        );
+        self.score_rows(&sql, query_embedding, top_k)
+    }
+
+    /// Whether searches go through an ANN index.
+    pub fn has_ann_index(&self) -> bool {
+        self.ann.is_some()
+    }
+
+    /// Whether the ANN index is missing, stale, or no longer worth keeping.
+    ///
+    /// New symbols are assigned to the existing clusters as they are written,
+    /// so retraining is only needed once the collection has changed size
+    /// substantially or many vectors could not be assigned.
+    pub fn ann_needs_rebuild(&self) -> Result<bool> {
+        let count = self.count_symbols()?;
+        let Some(ann) = &self.ann else {
+            return Ok(count >= MIN_ANN_VECTORS as u64);
+        };
+        if count < MIN_ANN_VECTORS as u64 {
+            return Ok(true);
+        }
+
+        let trained: u64 = self
+            .get_meta("ann_trained_count")?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        let unassigned: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM symbol_embeddings WHERE ann_list IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(count >= trained.saturating_mul(2)
+            || count.saturating_mul(2) < trained
+            || (unassigned as u64).saturating_mul(10) > count
+            || IvfIndex::suggested_lists(count as usize) > ann.list_count() * 2)
+    }
+
+    /// Trains the IVF index on the stored embeddings and assigns every symbol
+    /// to a cluster. Below [`MIN_ANN_VECTORS`] any existing index is dropped
+    /// and searches stay exact.
+    ///
+    /// Returns the number of clusters (0 when no index was built).
+    pub fn build_ann_index(&mut self) -> Result<usize> {
+        let count = self.count_symbols()? as usize;
+        let tx = self.conn.transaction()?;
+        tx.execute_batch(
             r#"
-            SELECT symbol_id, path, lang, symbol_kind, symbol_name, start_line, end_line,
-                   file_hash, content_hash, embedding, created_at
-            FROM symbol_embeddings
+            DELETE FROM ann_centroids;
+            UPDATE symbol_embeddings SET ann_list = NULL;
             "#,
         )?;
 
+        if count < MIN_ANN_VECTORS {
+            tx.commit()?;
+            self.ann = None;
+            return Ok(0);
+        }
+
+        // Train on an evenly strided sample to bound memory and time.
+        let lists = IvfIndex::suggested_lists(count);
+        let stride = (count / (lists * TRAINING_POINTS_PER_LIST)).max(1);
+        let mut sample: Vec<Vec<f32>> = Vec::new();
+        {
+            let mut stmt = tx.prepare("SELECT embedding FROM symbol_embeddings ORDER BY rowid")?;
+            let mut rows = stmt.query([])?;
+            let mut position = 0usize;
+            while let Some(row) = rows.next()? {
+                if position.is_multiple_of(stride) {
+                    let blob: Vec<u8> = row.get(0)?;
+                    sample.push(Self::blob_to_embedding(&blob));
+                }
+                position += 1;
+            }
+        }
+
+        let Some(ann) = IvfIndex::train(&sample, lists) else {
+            tx.commit()?;
+            self.ann = None;
+            return Ok(0);
+        };
+        drop(sample);
+
+        {
+            let mut insert =
+                tx.prepare("INSERT INTO ann_centroids (list_id, centroid) VALUES (?1, ?2)")?;
+            for (list, centroid) in ann.centroids().iter().enumerate() {
+                insert.execute(params![list as i64, Self::embedding_to_blob(centroid)])?;
+            }
+
+            // Assign in rowid-ordered pages so the full collection never has
+            // to be held in memory.
+            const PAGE: i64 = 4096;
+            let mut select = tx.prepare(
+                "SELECT rowid, embedding FROM symbol_embeddings WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+            )?;
+            let mut update =
+                tx.prepare("UPDATE symbol_embeddings SET ann_list = ?1 WHERE rowid = ?2")?;
+            let mut last_rowid = i64::MIN;
+            loop {
+                let page: Vec<(i64, Vec<u8>)> = select
+                    .query_map(params![last_rowid, PAGE], |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                let Some(&(rowid, _)) = page.last() else {
+                    break;
+                };
+                last_rowid = rowid;
+
+                for (rowid, blob) in page {
+                    let list = Self::ann_list_for(Some(&ann), &Self::blob_to_embedding(&blob));
+                    update.execute(params![list, rowid])?;
+                }
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('ann_trained_count', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![count.to_string()],
+        )?;
+        tx.commit()?;
+
+        let list_count = ann.list_count();
+        self.ann = Some(ann);
+        Ok(list_count)
+    }
+
+    /// Scores every row returned by `sql` against the query, best first.
+    fn score_rows(
+        &self,
+        sql: &str,
+        query_embedding: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<SimilarityResult>> {
+        let mut stmt = self.conn.prepare(sql)?;
+
         let mut results: Vec<SimilarityResult> = stmt
             .query_map([], |row| {
-                let embedding_blob: Vec<u8> = row.get(9)?;
-                let embedding = Self::blob_to_embedding(&embedding_blob);
-                let score = Self::cosine_similarity(query_embedding, &embedding);
-                Ok(SimilarityResult {
-                    symbol: SymbolEmbedding {
-                        symbol_id: row.get(0)?,
-                        path: row.get(1)?,
-                        lang: row.get(2)?,
-                        symbol_kind: row.get(3)?,
-                        symbol_name: row.get(4)?,
-                        start_line: row.get(5)?,
-                        end_line: row.get(6)?,
-                        file_hash: row.get(7)?,
-                        content_hash: row.get(8)?,
-                        embedding,
-                        created_at: row.get(10)?,
-                    },
-                    score,
-                })
+                let symbol = Self::symbol_from_row(row)?;
+                let score = Self::cosine_similarity(query_embedding, &symbol.embedding);
+                Ok(SimilarityResult { symbol, score })
             })?
             .filter_map(|r| r.ok())
             .collect();
@@ -651,6 +886,30 @@ impl EmbeddingStorage {
         Ok(())
     }
 
+    /// Cluster to store alongside an embedding (`None` without a usable index).
+    fn ann_list_for(ann: Option<&IvfIndex>, embedding: &[f32]) -> Option<i64> {
+        ann.and_then(|ann| ann.assign(embedding))
+            .map(|list| list as i64)
+    }
+
+    /// Maps a row selected with [`SYMBOL_COLUMNS`].
+    fn symbol_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SymbolEmbedding> {
+        let embedding_blob: Vec<u8> = row.get(9)?;
+        Ok(SymbolEmbedding {
+            symbol_id: row.get(0)?,
+            path: row.get(1)?,
+            lang: row.get(2)?,
+            symbol_kind: row.get(3)?,
+            symbol_name: row.get(4)?,
+            start_line: row.get(5)?,
+            end_line: row.get(6)?,
+            file_hash: row.get(7)?,
+            content_hash: row.get(8)?,
+            embedding: Self::blob_to_embedding(&embedding_blob),
+            created_at: row.get(10)?,
+        })
+    }
+
     /// Converts an embedding vector to a compact blob.
     fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
         embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
@@ -684,6 +943,7 @@ impl EmbeddingStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::ann::DEFAULT_NPROBE;
     use tempfile::tempdir;
 
     fn create_test_embedding(dim: usize, value: f32) -> Vec<f32> {
@@ -851,4 +1111,111 @@ mod tests {
         let remaining = storage.get_symbols_for_path("a.rs").unwrap();
         assert!(remaining.is_empty());
     }
+
+    /// Deterministic clustered vectors: `clusters` random directions plus noise.
+    fn clustered_embeddings(count: usize, clusters: usize, dim: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 10_000) as f32 / 5_000.0 - 1.0
+        };
+        let centers: Vec<Vec<f32>> = (0..clusters)
+            .map(|_| (0..dim).map(|_| next()).collect())
+            .collect();
+        (0..count)
+            .map(|i| {
+                centers[i % clusters]
+                    .iter()
+                    .map(|c| c + next() * 0.3)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn store_embeddings(storage: &mut EmbeddingStorage, path: &str, embeddings: &[Vec<f32>]) {
+        let ids: Vec<String> = (0..embeddings.len())
+            .map(|i| format!("{}#{}", path, i))
+            .collect();
+        let inputs: Vec<SymbolEmbeddingInput<'_>> = embeddings
+            .iter()
+            .zip(&ids)
+            .enumerate()
+            .map(|(i, (embedding, id))| SymbolEmbeddingInput {
+                symbol_id: id,
+                lang: "rust",
+                symbol_kind: "function",
+                symbol_name: id,
+                start_line: i as u32 + 1,
+                end_line: i as u32 + 1,
+                content_hash: id,
+                embedding,
+            })
+            .collect();
+        storage
+            .sync_file_symbols(path, "hash", 1000, &ids, &inputs)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ann_recall_matches_exact_search() {
+        let dir = tempdir().unwrap();
+        let mut storage = EmbeddingStorage::open(dir.path().join("test.sqlite")).unwrap();
+        let embeddings = clustered_embeddings(3000, 40, 16);
+        store_embeddings(&mut storage, "src/lib.rs", &embeddings);
+
+        assert!(!storage.has_ann_index());
+        assert!(storage.ann_needs_rebuild().unwrap());
+        let lists = storage.build_ann_index().unwrap();
+        assert_eq!(lists, IvfIndex::suggested_lists(3000));
+        assert!(!storage.ann_needs_rebuild().unwrap());
+
+        let queries = clustered_embeddings(20, 40, 16);
+        let mut hits = 0;
+        for query in &queries {
+            let exact = storage.search_similar(query, 10).unwrap();
+            let approximate = storage
+                .search_approximate(query, 10, DEFAULT_NPROBE)
+                .unwrap();
+            hits += approximate
+                .iter()
+                .filter(|a| {
+                    exact
+                        .iter()
+                        .any(|e| e.symbol.symbol_id == a.symbol.symbol_id)
+                })
+                .count();
+        }
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall >= 0.9, "recall {}", recall);
+
+        // The index survives reopening the database.
+        storage.close().unwrap();
+        let storage = EmbeddingStorage::open(dir.path().join("test.sqlite")).unwrap();
+        assert!(storage.has_ann_index());
+    }
+
+    #[test]
+    fn test_ann_assigns_new_symbols_incrementally() {
+        let dir = tempdir().unwrap();
+        let mut storage = EmbeddingStorage::open(dir.path().join("test.sqlite")).unwrap();
+        store_embeddings(&mut storage, "a.rs", &clustered_embeddings(1500, 20, 8));
+        storage.build_ann_index().unwrap();
+
+        let added = vec![vec![5.0, -5.0, 5.0, -5.0, 5.0, -5.0, 5.0, -5.0]];
+        store_embeddings(&mut storage, "b.rs", &added);
+        let results = storage
+            .search_approximate(&added[0], 1, DEFAULT_NPROBE)
+            .unwrap();
+        assert_eq!(results[0].symbol.symbol_id, "b.rs#0");
+
+        // Shrinking below the threshold drops the index in favour of exact search.
+        storage.delete_file_symbols("a.rs").unwrap();
+        assert!(storage.ann_needs_rebuild().unwrap());
+        assert_eq!(storage.build_ann_index().unwrap(), 0);
+        assert!(!storage.has_ann_index());
+        let found = storage.get_symbols(&["b.rs#0", "missing"]).unwrap();
+        assert_eq!(found.len(), 1);
+    }
 }
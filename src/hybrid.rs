@@ -7,10 +7,10 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
-use crate::embedding::EmbeddingStorage;
+use crate::embedding::{EmbeddingStorage, SymbolEmbedding, DEFAULT_NPROBE};
 
 /// Search mode for queries
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -35,6 +35,16 @@ impl std::fmt::Display for SearchMode {
     }
 }
 
+impl From<crate::config::SearchMode> for SearchMode {
+    fn from(mode: crate::config::SearchMode) -> Self {
+        match mode {
+            crate::config::SearchMode::Keyword => SearchMode::Keyword,
+            crate::config::SearchMode::Semantic => SearchMode::Semantic,
+            crate::config::SearchMode::Hybrid => SearchMode::Hybrid,
+        }
+    }
+}
+
 impl std::str::FromStr for SearchMode {
     type Err = String;
 
@@ -48,6 +58,29 @@ impl std::str::FromStr for SearchMode {
     }
 }
 
+/// How hybrid mode merges the keyword and vector candidate lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FusionMethod {
+    /// Weighted sum of min-max normalized text and vector scores
+    #[default]
+    Weighted,
+    /// Reciprocal rank fusion of the two rankings
+    Rrf,
+}
+
+impl std::fmt::Display for FusionMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FusionMethod::Weighted => write!(f, "weighted"),
+            FusionMethod::Rrf => write!(f, "rrf"),
+        }
+    }
+}
+
+/// Rank offset for reciprocal rank fusion (the usual constant from the RRF paper)
+const RRF_K: f32 = 60.0;
+
 /// Configuration for hybrid search
 #[derive(Debug, Clone)]
 pub struct HybridConfig {
@@ -59,6 +92,10 @@ pub struct HybridConfig {
     pub candidate_k: usize,
     /// Maximum results to return
     pub max_results: usize,
+    /// How keyword and vector candidates are merged in hybrid mode
+    pub fusion: FusionMethod,
+    /// ANN clusters scanned when retrieving vector candidates
+    pub nprobe: usize,
 }
 
 impl Default for HybridConfig {
@@ -68,6 +105,8 @@ impl Default for HybridConfig {
             weight_vector: 0.3,
             candidate_k: 200,
             max_results: 20,
+            fusion: FusionMethod::default(),
+            nprobe: DEFAULT_NPROBE,
         }
     }
 }
@@ -94,6 +133,18 @@ impl HybridConfig {
         self
     }
 
+    /// Set the fusion method for hybrid mode
+    pub fn with_fusion(mut self, fusion: FusionMethod) -> Self {
+        self.fusion = fusion;
+        self
+    }
+
+    /// Set the number of ANN clusters scanned per query
+    pub fn with_nprobe(mut self, nprobe: usize) -> Self {
+        self.nprobe = nprobe;
+        self
+    }
+
     /// Calculate candidate_k based on max_results if not explicitly set
     pub fn effective_candidate_k(&self) -> usize {
         if self.candidate_k > 0 {
@@ -208,52 +259,166 @@ impl HybridSearcher {
         query_embedding: &[f32],
         storage: &EmbeddingStorage,
     ) -> Result<Vec<HybridResult>> {
-        if bm25_results.is_empty() {
-            return Ok(Vec::new());
-        }
+        let mut hybrid_results = self.score_bm25_results(bm25_results, query_embedding, storage);
+        self.finish(&mut hybrid_results);
+        Ok(hybrid_results)
+    }
 
-        // Normalize text scores
-        let text_norms = Self::normalize_text_scores(&bm25_results);
+    /// Perform pure semantic search: the top symbols by embedding similarity,
+    /// retrieved from the store (through its ANN index when one is built)
+    /// rather than from BM25 candidates.
+    ///
+    /// Returns up to `candidate_k` results, best first, so callers can apply
+    /// path filters before truncating. Snippets are left empty because the
+    /// store does not keep symbol text.
+    pub fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        storage: &EmbeddingStorage,
+    ) -> Result<Vec<HybridResult>> {
+        let hits = storage.search_approximate(
+            query_embedding,
+            self.config.effective_candidate_k(),
+            self.config.nprobe,
+        )?;
+
+        Ok(hits
+            .into_iter()
+            .map(|hit| {
+                let vector_norm = Self::normalize_vector_score(hit.score);
+                HybridResult {
+                    path: hit.symbol.path,
+                    score: vector_norm,
+                    text_score: 0.0,
+                    vector_score: hit.score,
+                    text_norm: 0.0,
+                    vector_norm,
+                    snippet: String::new(),
+                    line: Some(hit.symbol.start_line as usize),
+                    chunk_start: Some(hit.symbol.start_line),
+                    chunk_end: Some(hit.symbol.end_line),
+                    result_id: Some(hit.symbol.symbol_id),
+                }
+            })
+            .collect())
+    }
 
-        // Build hybrid results with embedding lookup
-        let mut hybrid_results: Vec<HybridResult> = Vec::with_capacity(bm25_results.len());
-
-        for (i, bm25) in bm25_results.into_iter().enumerate() {
-            let text_norm = text_norms[i];
-
-            // Look up embedding for this symbol
-            let (vector_score, vector_norm) = if let Some(ref symbol_id) = bm25.symbol_id {
-                match storage.get_symbol(symbol_id) {
-                    Ok(Some(symbol)) => {
-                        let cos_sim = Self::cosine_similarity(query_embedding, &symbol.embedding);
-                        let norm = Self::normalize_vector_score(cos_sim);
-                        (cos_sim, norm)
-                    }
-                    _ => (0.0, 0.5),
+    /// Merge BM25 candidates with vector candidates from
+    /// [`semantic_search`](Self::semantic_search) using the configured
+    /// [`FusionMethod`].
+    ///
+    /// Symbols found by both retrievers appear once, keeping the BM25 snippet.
+    pub fn fuse(
+        &self,
+        bm25_results: Vec<BM25Result>,
+        vector_results: Vec<HybridResult>,
+        query_embedding: &[f32],
+        storage: &EmbeddingStorage,
+    ) -> Result<Vec<HybridResult>> {
+        let text_ranked = self.score_bm25_results(bm25_results, query_embedding, storage);
+        let seen: HashSet<&str> = text_ranked
+            .iter()
+            .filter_map(|r| r.result_id.as_deref())
+            .collect();
+        let vector_only: Vec<HybridResult> = vector_results
+            .into_iter()
+            .filter(|r| r.result_id.as_deref().is_none_or(|id| !seen.contains(id)))
+            .collect();
+
+        let mut fused = match self.config.fusion {
+            FusionMethod::Weighted => {
+                let mut fused = text_ranked;
+                fused.extend(vector_only.into_iter().map(|mut r| {
+                    r.score = self.combine_scores(0.0, r.vector_norm);
+                    r
+                }));
+                fused
+            }
+            FusionMethod::Rrf => Self::reciprocal_rank_fusion(text_ranked, vector_only),
+        };
+
+        self.finish(&mut fused);
+        Ok(fused)
+    }
+
+    /// Score BM25 candidates (in BM25 order) with their stored embeddings
+    fn score_bm25_results(
+        &self,
+        bm25_results: Vec<BM25Result>,
+        query_embedding: &[f32],
+        storage: &EmbeddingStorage,
+    ) -> Vec<HybridResult> {
+        let text_norms = Self::normalize_text_scores(&bm25_results);
+        let embeddings = Self::lookup_embeddings(&bm25_results, storage);
+
+        bm25_results
+            .into_iter()
+            .zip(text_norms)
+            .map(|(bm25, text_norm)| {
+                let (vector_score, vector_norm) =
+                    Self::vector_scores(&bm25, query_embedding, &embeddings);
+                HybridResult {
+                    score: self.combine_scores(text_norm, vector_norm),
+                    text_score: bm25.score,
+                    vector_score,
+                    text_norm,
+                    vector_norm,
+                    path: bm25.path,
+                    snippet: bm25.snippet,
+                    line: bm25.line,
+                    chunk_start: bm25.chunk_start,
+                    chunk_end: bm25.chunk_end,
+                    result_id: bm25.symbol_id,
                 }
+            })
+            .collect()
+    }
+
+    /// Reciprocal rank fusion, scaled so a result ranked first by both lists scores 1.0
+    ///
+    /// `text_ranked` is in BM25 order. The vector ranking covers every result
+    /// with an embedding: vector-only candidates and BM25 candidates alike.
+    fn reciprocal_rank_fusion(
+        text_ranked: Vec<HybridResult>,
+        vector_only: Vec<HybridResult>,
+    ) -> Vec<HybridResult> {
+        let text_count = text_ranked.len();
+        let mut results = text_ranked;
+        results.extend(vector_only);
+
+        // BM25 candidates without a stored embedding carry a 0.0 vector score
+        let mut by_vector: Vec<usize> = (0..results.len())
+            .filter(|&i| results[i].vector_score != 0.0 || i >= text_count)
+            .collect();
+        by_vector.sort_by(|a, b| {
+            results[*b]
+                .vector_score
+                .total_cmp(&results[*a].vector_score)
+                .then_with(|| a.cmp(b))
+        });
+        let mut vector_rank: HashMap<usize, usize> = HashMap::new();
+        for (rank, index) in by_vector.into_iter().enumerate() {
+            vector_rank.insert(index, rank + 1);
+        }
+
+        let max_score = 2.0 / (RRF_K + 1.0);
+        for (index, result) in results.iter_mut().enumerate() {
+            let text = if index < text_count {
+                1.0 / (RRF_K + (index + 1) as f32)
             } else {
-                (0.0, 0.5)
+                0.0
             };
-
-            let hybrid_score = self.combine_scores(text_norm, vector_norm);
-
-            hybrid_results.push(HybridResult {
-                path: bm25.path,
-                score: hybrid_score,
-                text_score: bm25.score,
-                vector_score,
-                text_norm,
-                vector_norm,
-                snippet: bm25.snippet,
-                line: bm25.line,
-                chunk_start: bm25.chunk_start,
-                chunk_end: bm25.chunk_end,
-                result_id: bm25.symbol_id,
-            });
+            let vector = vector_rank
+                .get(&index)
+                .map_or(0.0, |rank| 1.0 / (RRF_K + *rank as f32));
+            result.score = (text + vector) / max_score;
         }
+        results
+    }
 
-        // Sort by hybrid score (descending)
-        hybrid_results.sort_by(|a, b| {
+    /// Sort by final score (ties broken by text, vector, path) and truncate
+    fn finish(&self, results: &mut Vec<HybridResult>) {
+        results.sort_by(|a, b| {
             b.score
                 .partial_cmp(&a.score)
                 .unwrap_or(std::cmp::Ordering::Equal)
@@ -269,52 +434,37 @@ impl HybridSearcher {
                 })
                 .then_with(|| a.path.cmp(&b.path))
         });
-
-        // Truncate to max results
-        hybrid_results.truncate(self.config.max_results);
-
-        Ok(hybrid_results)
+        results.truncate(self.config.max_results);
     }
 
-    /// Perform pure semantic search using embeddings only
-    pub fn semantic_search(
-        &self,
-        bm25_results: Vec<BM25Result>,
-        query_embedding: &[f32],
+    /// Fetch the embeddings of all candidates in one storage round trip
+    fn lookup_embeddings(
+        results: &[BM25Result],
         storage: &EmbeddingStorage,
-    ) -> Result<Vec<HybridResult>> {
-        let mut results = Vec::with_capacity(bm25_results.len());
-
-        for bm25 in bm25_results {
-            let (vector_score, vector_norm) = if let Some(ref symbol_id) = bm25.symbol_id {
-                match storage.get_symbol(symbol_id) {
-                    Ok(Some(symbol)) => {
-                        let cos_sim = Self::cosine_similarity(query_embedding, &symbol.embedding);
-                        let norm = Self::normalize_vector_score(cos_sim);
-                        (cos_sim, norm)
-                    }
-                    _ => (0.0, 0.5),
-                }
-            } else {
-                (0.0, 0.5)
-            };
-
-            results.push(HybridResult {
-                path: bm25.path,
-                score: vector_norm,
-                text_score: 0.0,
-                vector_score,
-                text_norm: 0.0,
-                vector_norm,
-                snippet: bm25.snippet,
-                line: bm25.line,
-                chunk_start: bm25.chunk_start,
-                chunk_end: bm25.chunk_end,
-                result_id: bm25.symbol_id,
-            });
+    ) -> HashMap<String, SymbolEmbedding> {
+        let ids: Vec<&str> = results
+            .iter()
+            .filter_map(|r| r.symbol_id.as_deref())
+            .collect();
+        if ids.is_empty() {
+            return HashMap::new();
         }
+        storage.get_symbols(&ids).unwrap_or_default()
+    }
 
-        Ok(results)
+    /// Raw and normalized vector score of a candidate (neutral if it has no embedding)
+    fn vector_scores(
+        bm25: &BM25Result,
+        query_embedding: &[f32],
+        embeddings: &HashMap<String, SymbolEmbedding>,
+    ) -> (f32, f32) {
+        match bm25.symbol_id.as_ref().and_then(|id| embeddings.get(id)) {
+            Some(symbol) => {
+                let cos_sim = Self::cosine_similarity(query_embedding, &symbol.embedding);
+                (cos_sim, Self::normalize_vector_score(cos_sim))
+            }
+            None => (0.0, 0.5),
+        }
     }
 
     /// Compute cosine similarity between two vectors
@@ -389,41 +539,49 @@ impl ContextPackBuilder {
 
         let mut packs = Vec::new();
 
-        for (path, mut ranges) in file_ranges {
-            // Sort and merge overlapping ranges
-            ranges.sort_by_key(|r| r.0);
-            let merged = Self::merge_ranges(&ranges);
-
+        for (path, ranges) in file_ranges {
             // Read file and extract blocks
             let file_path = root.join(&path);
             let content = std::fs::read_to_string(&file_path)
                 .with_context(|| format!("Failed to read file: {}", path))?;
             let lines: Vec<&str> = content.lines().collect();
-
-            let blocks: Vec<ContextBlock> = merged
-                .into_iter()
-                .map(|(start, end)| {
-                    let actual_start = start.max(1);
-                    let actual_end = end.min(lines.len());
-                    let text = lines
-                        .get(actual_start.saturating_sub(1)..actual_end)
-                        .map(|slice| slice.join("\n"))
-                        .unwrap_or_default();
-
-                    ContextBlock {
-                        start_line: actual_start,
-                        end_line: actual_end,
-                        text,
-                    }
-                })
-                .collect();
-
-            packs.push(ContextPack { path, blocks });
+            packs.push(Self::pack_ranges(path, ranges, &lines));
         }
 
         Ok(packs)
     }
 
+    /// Merge line ranges (1-indexed, inclusive) of one file into blocks of its `lines`
+    pub fn pack_ranges(
+        path: String,
+        mut ranges: Vec<(usize, usize)>,
+        lines: &[&str],
+    ) -> ContextPack {
+        // Sort and merge overlapping ranges
+        ranges.sort_by_key(|r| r.0);
+        let merged = Self::merge_ranges(&ranges);
+
+        let blocks: Vec<ContextBlock> = merged
+            .into_iter()
+            .map(|(start, end)| {
+                let actual_start = start.max(1);
+                let actual_end = end.min(lines.len());
+                let text = lines
+                    .get(actual_start.saturating_sub(1)..actual_end)
+                    .map(|slice| slice.join("\n"))
+                    .unwrap_or_default();
+
+                ContextBlock {
+                    start_line: actual_start,
+                    end_line: actual_end,
+                    text,
+                }
+            })
+            .collect();
+
+        ContextPack { path, blocks }
+    }
+
     /// Merge overlapping ranges
     fn merge_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
         if ranges.is_empty() {
@@ -452,6 +610,43 @@ impl ContextPackBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::SymbolEmbeddingInput;
+    use tempfile::tempdir;
+
+    /// Storage with `a` pointing along x and `b` along y
+    fn storage_with_two_symbols(dir: &Path) -> EmbeddingStorage {
+        let mut storage = EmbeddingStorage::open(dir.join("test.sqlite")).unwrap();
+        let embeddings = [("a", 1, vec![1.0, 0.0]), ("b", 10, vec![0.0, 1.0])];
+        let inputs: Vec<SymbolEmbeddingInput<'_>> = embeddings
+            .iter()
+            .map(|(id, line, embedding)| SymbolEmbeddingInput {
+                symbol_id: id,
+                lang: "rust",
+                symbol_kind: "function",
+                symbol_name: id,
+                start_line: *line,
+                end_line: line + 2,
+                content_hash: id,
+                embedding,
+            })
+            .collect();
+        storage
+            .replace_file_symbols("src/lib.rs", "hash", 1000, &inputs)
+            .unwrap();
+        storage
+    }
+
+    fn bm25(symbol_id: &str, score: f32) -> BM25Result {
+        BM25Result {
+            path: "src/lib.rs".into(),
+            score,
+            snippet: format!("fn {}()", symbol_id),
+            line: Some(1),
+            chunk_start: None,
+            chunk_end: None,
+            symbol_id: Some(symbol_id.into()),
+        }
+    }
 
     #[test]
     fn test_search_mode_parsing() {
@@ -539,4 +734,70 @@ mod tests {
         let c = vec![0.0, 1.0, 0.0];
         assert!(HybridSearcher::cosine_similarity(&a, &c).abs() < 0.001);
     }
+
+    #[test]
+    fn test_semantic_search_reads_embedding_store() {
+        let dir = tempdir().unwrap();
+        let storage = storage_with_two_symbols(dir.path());
+        let searcher = HybridSearcher::with_defaults();
+
+        // No BM25 candidates are involved: the store alone answers.
+        let results = searcher.semantic_search(&[0.1, 1.0], &storage).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].result_id.as_deref(), Some("b"));
+        assert_eq!(results[0].line, Some(10));
+        assert_eq!(results[0].chunk_end, Some(12));
+        assert!(results[0].snippet.is_empty());
+        assert!(results[0].vector_norm > results[1].vector_norm);
+    }
+
+    #[test]
+    fn test_fuse_merges_vector_only_candidates() {
+        let dir = tempdir().unwrap();
+        let storage = storage_with_two_symbols(dir.path());
+        let query = [0.0, 1.0];
+
+        for fusion in [FusionMethod::Weighted, FusionMethod::Rrf] {
+            let searcher = HybridSearcher::new(HybridConfig::default().with_fusion(fusion));
+            let vector = searcher.semantic_search(&query, &storage).unwrap();
+            let fused = searcher
+                .fuse(vec![bm25("a", 4.0)], vector, &query, &storage)
+                .unwrap();
+
+            // `a` is found by both retrievers but reported once, with its BM25 snippet.
+            let ids: Vec<&str> = fused
+                .iter()
+                .filter_map(|r| r.result_id.as_deref())
+                .collect();
+            assert_eq!(ids, vec!["a", "b"], "{}", fusion);
+            assert_eq!(fused[0].snippet, "fn a()");
+            assert!(fused[1].text_norm == 0.0 && fused[1].vector_norm == 1.0);
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_scores() {
+        let result = |id: &str, vector_score: f32| HybridResult {
+            path: "a.rs".into(),
+            score: 0.0,
+            text_score: 0.0,
+            vector_score,
+            text_norm: 0.0,
+            vector_norm: 0.0,
+            snippet: String::new(),
+            line: None,
+            chunk_start: None,
+            chunk_end: None,
+            result_id: Some(id.into()),
+        };
+        let fused = HybridSearcher::reciprocal_rank_fusion(
+            vec![result("text-and-vector", 0.9), result("text-only", 0.0)],
+            vec![result("vector-only", 0.5)],
+        );
+
+        // First in both rankings scores 1.0; one list alone scores about half.
+        assert!((fused[0].score - 1.0).abs() < 0.001);
+        assert!((fused[1].score - 61.0 / 124.0).abs() < 0.001);
+        assert!((fused[2].score - 61.0 / 124.0).abs() < 0.001);
+    }
 }
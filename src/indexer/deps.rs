@@ -0,0 +1,375 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Persistent file-level dependency graph stored next to the tantivy index.
+//!
+//! `IndexBuilder::build` resolves every file's imports and writes the edges to
+//! `.cgrep/deps.json`, so forward, reverse and transitive dependency queries
+//! can be answered without re-parsing the tree.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::utils::INDEX_DIR;
+
+/// File name of the dependency graph inside the index directory.
+pub const DEPS_FILE: &str = "deps.json";
+
+/// Bump when the stored layout changes; older graphs are treated as missing.
+const GRAPH_VERSION: u32 = 2;
+
+/// An import edge to another file in the repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// Root-relative path of the imported file
+    pub path: String,
+    /// Line of the import statement in the importing file
+    pub line: usize,
+    /// Rust `mod` declaration of a child module rather than a use of it
+    #[serde(default)]
+    pub declaration: bool,
+}
+
+/// A file reached while walking the graph from a starting file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReachedFile {
+    pub path: String,
+    /// Number of import hops from the starting file
+    pub depth: usize,
+    /// File the walk came from when it first reached `path`
+    pub via: String,
+    /// Line of the import statement that links `via` and `path`
+    pub line: usize,
+}
+
+/// Resolved imports of every indexed file, keyed by root-relative path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    version: u32,
+    files: BTreeMap<String, Vec<DependencyEdge>>,
+}
+
+impl Default for DependencyGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self {
+            version: GRAPH_VERSION,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Location of the graph for an index rooted at `root`.
+    pub fn path_for(root: &Path) -> PathBuf {
+        root.join(INDEX_DIR).join(DEPS_FILE)
+    }
+
+    /// Load the stored graph, or `None` if it is missing or from an older layout.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read dependency graph: {}", path.display()))?;
+        let graph: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse dependency graph: {}", path.display()))?;
+        Ok((graph.version == GRAPH_VERSION).then_some(graph))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = Self::path_for(root);
+        std::fs::write(&path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write dependency graph: {}", path.display()))
+    }
+
+    /// Register a file so it shows up as a node even without imports.
+    pub fn add_file(&mut self, path: &str) {
+        self.files.entry(path.to_string()).or_default();
+    }
+
+    /// Record that `from` imports `to`; only the first import line is kept.
+    pub fn add_edge(&mut self, from: &str, to: &str, line: usize) {
+        self.insert_edge(from, to, line, false);
+    }
+
+    /// Record that `from` declares the child module `to` (`mod to;`).
+    pub fn add_declaration(&mut self, from: &str, to: &str, line: usize) {
+        self.insert_edge(from, to, line, true);
+    }
+
+    fn insert_edge(&mut self, from: &str, to: &str, line: usize, declaration: bool) {
+        self.add_file(to);
+        let edges = self.files.entry(from.to_string()).or_default();
+        match edges.iter_mut().find(|edge| edge.path == to) {
+            Some(edge) => edge.declaration |= declaration,
+            None => edges.push(DependencyEdge {
+                path: to.to_string(),
+                line,
+                declaration,
+            }),
+        }
+    }
+
+    /// Whether `root` has a graph in the current layout.
+    pub fn is_current(root: &Path) -> bool {
+        matches!(Self::load(root), Ok(Some(_)))
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// All `(from, edge)` pairs in path order.
+    pub fn edges(&self) -> impl Iterator<Item = (&str, &DependencyEdge)> {
+        self.files
+            .iter()
+            .flat_map(|(from, edges)| edges.iter().map(move |edge| (from.as_str(), edge)))
+    }
+
+    /// Files reachable from `start`, breadth-first.
+    ///
+    /// With `reverse` the walk follows importers instead of imports. A
+    /// `max_depth` of 1 yields only direct neighbours.
+    pub fn walk(&self, start: &str, reverse: bool, max_depth: Option<usize>) -> Vec<ReachedFile> {
+        let importers = reverse.then(|| self.importers());
+        let mut reached: Vec<ReachedFile> = Vec::new();
+        let mut seen: HashSet<&str> = HashSet::from([start]);
+        let mut queue: VecDeque<(&str, usize)> = VecDeque::from([(start, 0)]);
+
+        while let Some((current, depth)) = queue.pop_front() {
+            if max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            let neighbours: Vec<(&str, usize)> = match &importers {
+                Some(importers) => importers.get(current).cloned().unwrap_or_default(),
+                None => self
+                    .files
+                    .get(current)
+                    .map(|edges| {
+                        edges
+                            .iter()
+                            .map(|edge| (edge.path.as_str(), edge.line))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+            for (next, line) in neighbours {
+                if !seen.insert(next) {
+                    continue;
+                }
+                reached.push(ReachedFile {
+                    path: next.to_string(),
+                    depth: depth + 1,
+                    via: current.to_string(),
+                    line,
+                });
+                queue.push_back((next, depth + 1));
+            }
+        }
+
+        reached.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.path.cmp(&b.path)));
+        reached
+    }
+
+    /// Import cycles: strongly connected components with more than one file.
+    ///
+    /// `mod` declarations are left out, so a parent module and a child that
+    /// uses `super::` items do not count as a cycle.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let nodes: Vec<&str> = self.files.keys().map(String::as_str).collect();
+        let ids: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let successors: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|node| {
+                self.files[*node]
+                    .iter()
+                    .filter(|edge| !edge.declaration)
+                    .filter_map(|edge| ids.get(edge.path.as_str()).copied())
+                    .collect()
+            })
+            .collect();
+
+        let mut cycles: Vec<Vec<String>> = strongly_connected(&successors)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                let mut files: Vec<String> = component
+                    .into_iter()
+                    .map(|id| nodes[id].to_string())
+                    .collect();
+                files.sort();
+                files
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// Importers of each file with the import line in the importer.
+    fn importers(&self) -> HashMap<&str, Vec<(&str, usize)>> {
+        let mut importers: HashMap<&str, Vec<(&str, usize)>> = HashMap::new();
+        for (from, edge) in self.edges() {
+            importers
+                .entry(edge.path.as_str())
+                .or_default()
+                .push((from, edge.line));
+        }
+        importers
+    }
+}
+
+/// Tarjan's algorithm without recursion, so deep import chains cannot
+/// overflow the stack.
+fn strongly_connected(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let count = successors.len();
+    let mut index = vec![UNVISITED; count];
+    let mut low = vec![0usize; count];
+    let mut on_stack = vec![false; count];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut next_index = 0usize;
+
+    for start in 0..count {
+        if index[start] != UNVISITED {
+            continue;
+        }
+        // (node, position of the next successor to visit)
+        let mut work: Vec<(usize, usize)> = vec![(start, 0)];
+        while let Some(frame) = work.last_mut() {
+            let node = frame.0;
+            if index[node] == UNVISITED {
+                index[node] = next_index;
+                low[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+
+            if let Some(&next) = successors[node].get(frame.1) {
+                frame.1 += 1;
+                if index[next] == UNVISITED {
+                    work.push((next, 0));
+                } else if on_stack[next] {
+                    low[node] = low[node].min(index[next]);
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge("main.rs", "a.rs", 1);
+        graph.add_edge("a.rs", "b.rs", 2);
+        graph.add_edge("b.rs", "c.rs", 3);
+        graph.add_edge("c.rs", "a.rs", 4);
+        graph.add_file("lonely.rs");
+        graph
+    }
+
+    #[test]
+    fn walk_forward_and_reverse() {
+        let graph = sample_graph();
+
+        let direct = graph.walk("main.rs", false, Some(1));
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].path, "a.rs");
+        assert_eq!(direct[0].line, 1);
+
+        let transitive: Vec<(String, usize)> = graph
+            .walk("main.rs", false, None)
+            .into_iter()
+            .map(|r| (r.path, r.depth))
+            .collect();
+        assert_eq!(
+            transitive,
+            vec![
+                ("a.rs".to_string(), 1),
+                ("b.rs".to_string(), 2),
+                ("c.rs".to_string(), 3)
+            ]
+        );
+
+        let importers = graph.walk("a.rs", true, Some(1));
+        let paths: Vec<&str> = importers.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["c.rs", "main.rs"]);
+        assert_eq!(importers[0].line, 4);
+        assert!(graph.walk("lonely.rs", true, None).is_empty());
+    }
+
+    #[test]
+    fn cycles_report_strongly_connected_files() {
+        let graph = sample_graph();
+        assert_eq!(
+            graph.cycles(),
+            vec![vec![
+                "a.rs".to_string(),
+                "b.rs".to_string(),
+                "c.rs".to_string()
+            ]]
+        );
+    }
+
+    #[test]
+    fn module_declarations_are_not_cycles() {
+        let mut graph = DependencyGraph::new();
+        graph.add_declaration("src/query/mod.rs", "src/query/search.rs", 1);
+        graph.add_edge("src/query/mod.rs", "src/query/search.rs", 3);
+        graph.add_edge("src/query/search.rs", "src/query/mod.rs", 1);
+        assert!(graph.cycles().is_empty());
+
+        let importers = graph.walk("src/query/search.rs", true, Some(1));
+        assert_eq!(importers.len(), 1);
+        assert_eq!(importers[0].path, "src/query/mod.rs");
+        assert_eq!(importers[0].line, 1);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join(INDEX_DIR)).expect("index dir");
+        assert!(DependencyGraph::load(dir.path()).expect("load").is_none());
+
+        sample_graph().save(dir.path()).expect("save");
+        let loaded = DependencyGraph::load(dir.path())
+            .expect("load")
+            .expect("graph");
+        assert_eq!(loaded.file_count(), 5);
+        assert_eq!(loaded.edges().count(), 4);
+    }
+}
@@ -11,35 +11,45 @@ use rayon::ThreadPoolBuilder;
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::SystemTime;
 use tantivy::{
-    schema::{Field, Schema, Term, STORED, STRING, TEXT},
+    schema::{
+        Field, IndexRecordOption, Schema, Term, TextFieldIndexing, TextOptions, STORED, STRING,
+        TEXT,
+    },
     Index, IndexWriter, TantivyDocument,
 };
 
-use crate::indexer::scanner::{detect_language, FileScanner};
-use crate::parser::symbols::{Symbol, SymbolExtractor, SymbolKind};
-use cgrep::config::{Config, EmbeddingProviderType};
-use cgrep::embedding::{
+use crate::config::{Config, EmbeddingProviderType};
+use crate::embedding::{
     CommandProvider, DummyProvider, EmbeddingProvider, EmbeddingProviderConfig, EmbeddingStorage,
-    FastEmbedder, SymbolEmbeddingInput, DEFAULT_EMBEDDING_DIM,
+    FastEmbedder, HttpProvider, SymbolEmbeddingInput, DEFAULT_EMBEDDING_DIM,
 };
-use cgrep::utils::INDEX_DIR;
+use crate::indexer::deps::DependencyGraph;
+use crate::indexer::resolve::ImportResolver;
+use crate::indexer::scanner::{detect_language, FileScanner};
+use crate::indexer::symbol_table::SymbolTable;
+use crate::indexer::tokenizer;
+use crate::parser::imports::{Import, ImportExtractor, ImportKind};
+use crate::parser::symbols::{Symbol, SymbolExtractor, SymbolKind};
+use crate::utils::INDEX_DIR;
 const METADATA_FILE: &str = ".cgrep/metadata.json";
 pub(crate) const DEFAULT_WRITER_BUDGET_BYTES: usize = 50_000_000;
 const HIGH_MEMORY_WRITER_BUDGET_BYTES: usize = 1024 * 1024 * 1024;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum EmbeddingsMode {
+/// Embedding generation mode for `cgrep index --embeddings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbeddingsMode {
+    #[default]
     Off,
     Auto,
     Precompute,
 }
 
 impl EmbeddingsMode {
-    fn parse(s: &str) -> Result<Self> {
+    pub fn parse(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "off" | "false" | "0" => Ok(Self::Off),
             "auto" => Ok(Self::Auto),
@@ -73,13 +83,16 @@ struct SymbolEmbeddingMeta {
     content_hash: String,
 }
 
-#[derive(Default)]
-struct EmbeddingIndexStats {
-    files_total: usize,
-    files_embedded: usize,
-    files_skipped_up_to_date: usize,
-    files_deleted: usize,
-    symbols_embedded: usize,
+/// Embedding work done by one index run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EmbeddingIndexStats {
+    pub files_total: usize,
+    pub files_embedded: usize,
+    pub files_skipped_up_to_date: usize,
+    pub files_deleted: usize,
+    pub symbols_embedded: usize,
+    /// Cluster count when the ANN index was (re)built during this run
+    pub ann_lists: Option<usize>,
 }
 
 fn create_embedding_provider(
@@ -91,7 +104,7 @@ fn create_embedding_provider(
     if mode == EmbeddingsMode::Auto
         && matches!(
             config.embeddings.enabled(),
-            cgrep::config::EmbeddingEnabled::Off
+            crate::config::EmbeddingEnabled::Off
         )
     {
         return Ok(None);
@@ -106,6 +119,7 @@ fn create_embedding_provider(
         || config.embeddings.max_chars.is_some()
         || config.embeddings.model.is_some()
         || config.embeddings.command.is_some()
+        || config.embeddings.base_url.is_some()
         || config.embeddings.chunk_lines.is_some()
         || config.embeddings.chunk_overlap.is_some()
         || config.embeddings.max_file_bytes.is_some()
@@ -129,6 +143,8 @@ fn create_embedding_provider(
             config.embeddings.command().to_string(),
             config.embeddings.model().to_string(),
         ))),
+        EmbeddingProviderType::Http => HttpProvider::from_embedding_config(&config.embeddings)
+            .map(|provider| Box::new(provider) as Box<dyn EmbeddingProvider>),
     };
 
     match mode {
@@ -233,6 +249,7 @@ fn flush_embedding_batch(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn embed_large_file_symbols(
     provider: &mut dyn EmbeddingProvider,
     path: &str,
@@ -326,6 +343,7 @@ fn index_embeddings(
         EmbeddingProviderType::Builtin => "builtin",
         EmbeddingProviderType::Dummy => "dummy",
         EmbeddingProviderType::Command => "command",
+        EmbeddingProviderType::Http => "http",
     };
 
     let _ = storage.set_meta("schema_version", "3");
@@ -529,6 +547,12 @@ fn index_embeddings(
             &mut stats,
         )?;
 
+        // Symbols written above were assigned to the existing ANN clusters;
+        // only retrain when the collection has drifted too far from them.
+        if storage.ann_needs_rebuild()? {
+            stats.ann_lists = Some(storage.build_ann_index()?);
+        }
+
         Ok(())
     })();
 
@@ -558,6 +582,10 @@ struct FileMetadata {
     hash: String,
     symbols: String,
     is_binary: bool,
+    /// Raw imports, resolved into `.cgrep/deps.json`; `None` for entries
+    /// written before imports were recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    imports: Option<Vec<Import>>,
 }
 
 impl FileMetadata {
@@ -701,8 +729,15 @@ fn join_chunks(chunks: &[TextChunk]) -> String {
     text
 }
 
-fn extract_symbols_from_text(text: &str, lang: &str) -> Vec<Symbol> {
+fn extract_symbols_from_text(text: &str, lang: &str, rel_path: &Path) -> Vec<Symbol> {
     let extractor = SymbolExtractor::new();
+    extractor
+        .extract_for_path(text, lang, rel_path)
+        .unwrap_or_default()
+}
+
+fn extract_imports_from_text(text: &str, lang: &str) -> Vec<Import> {
+    let extractor = ImportExtractor::new();
     extractor.extract(text, lang).unwrap_or_default()
 }
 
@@ -751,13 +786,13 @@ fn filter_symbols(
                 .then_with(|| a.0.cmp(&b.0))
         });
         filtered.truncate(max_symbols);
-        filtered.sort_by(|a, b| a.0.cmp(&b.0));
+        filtered.sort_by_key(|entry| entry.0);
     }
 
     filtered.into_iter().map(|(_, symbol, _)| symbol).collect()
 }
 
-fn symbol_id_for(path: &str, lang: &str, symbol: &Symbol) -> String {
+pub(crate) fn symbol_id_for(path: &str, lang: &str, symbol: &Symbol) -> String {
     let range = if let (Some(start), Some(end)) = (symbol.byte_start, symbol.byte_end) {
         format!("{}:{}", start, end)
     } else {
@@ -830,7 +865,7 @@ fn should_skip_without_read(
         return Some(meta.clone());
     }
 
-    if !meta.hash.is_empty() && meta.mtime == mtime && meta.size == size {
+    if !meta.hash.is_empty() && meta.imports.is_some() && meta.mtime == mtime && meta.size == size {
         return Some(meta.clone());
     }
 
@@ -847,6 +882,7 @@ pub struct IndexFields {
     pub doc_type: Field,
     pub symbol_id: Field,
     pub symbol_end_line: Field,
+    pub symbol_kind: Field,
     #[allow(dead_code)]
     pub line_number: Field,
 }
@@ -861,6 +897,24 @@ pub struct IndexBuilder {
     symbol_max_chars: usize,
     max_symbols_per_file: usize,
     allowed_symbol_kinds: Option<HashSet<String>>,
+    show_progress: bool,
+    /// Analyzer of the `content` and `symbols` fields of a new index
+    tokenizer: &'static str,
+}
+
+/// File counts from one [`IndexBuilder`] run
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BuildStats {
+    /// Files (re)indexed
+    pub indexed: usize,
+    /// Files skipped because their content hash was unchanged
+    pub unchanged: usize,
+    /// Files dropped from the index because they no longer exist
+    pub removed: usize,
+    /// Files that could not be read
+    pub unreadable: usize,
+    /// Files seen by the scanner
+    pub total: usize,
 }
 
 impl IndexBuilder {
@@ -893,16 +947,44 @@ impl IndexBuilder {
         max_symbols_per_file: usize,
         allowed_symbol_kinds: Option<HashSet<String>>,
     ) -> Result<Self> {
+        let tokenizer = tokenizer::CODE_TOKENIZER;
+        let (schema, fields) = Self::schema_for(tokenizer);
+
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+            schema,
+            fields,
+            exclude_patterns: excludes,
+            symbol_preview_lines,
+            symbol_max_chars,
+            max_symbols_per_file,
+            allowed_symbol_kinds,
+            show_progress: true,
+            tokenizer,
+        })
+    }
+
+    fn schema_for(tokenizer: &str) -> (Schema, IndexFields) {
         let mut schema_builder = Schema::builder();
 
+        // Code fields split identifiers into sub-words (`IndexBuilder` -> `index`, `builder`)
+        let code_text = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(tokenizer)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+            .set_stored();
+
         let path = schema_builder.add_text_field("path", TEXT | STORED);
         let path_exact = schema_builder.add_text_field("path_exact", STRING | STORED);
-        let content = schema_builder.add_text_field("content", TEXT | STORED);
+        let content = schema_builder.add_text_field("content", code_text.clone());
         let language = schema_builder.add_text_field("language", TEXT | STORED);
-        let symbols = schema_builder.add_text_field("symbols", TEXT | STORED);
+        let symbols = schema_builder.add_text_field("symbols", code_text);
         let doc_type = schema_builder.add_text_field("doc_type", STRING | STORED);
         let symbol_id = schema_builder.add_text_field("symbol_id", STRING | STORED);
         let symbol_end_line = schema_builder.add_u64_field("symbol_end_line", STORED);
+        let symbol_kind = schema_builder.add_text_field("symbol_kind", STRING | STORED);
         let line_number =
             schema_builder.add_u64_field("line_number", tantivy::schema::INDEXED | STORED);
 
@@ -916,23 +998,34 @@ impl IndexBuilder {
             doc_type,
             symbol_id,
             symbol_end_line,
+            symbol_kind,
             line_number,
         };
+        (schema, fields)
+    }
 
-        Ok(Self {
-            root: root.as_ref().to_path_buf(),
-            schema,
-            fields,
-            exclude_patterns: excludes,
-            symbol_preview_lines,
-            symbol_max_chars,
-            max_symbols_per_file,
-            allowed_symbol_kinds,
-        })
+    /// Stem the sub-words of new indexes (`[index] stemming`)
+    pub fn with_stemming(mut self, stemming: bool) -> Self {
+        self.tokenizer = tokenizer::tokenizer_name(stemming);
+        (self.schema, self.fields) = Self::schema_for(self.tokenizer);
+        self
+    }
+
+    /// Skip the terminal progress bar
+    pub fn without_progress(mut self) -> Self {
+        self.show_progress = false;
+        self
     }
 
     /// Build or rebuild the index (with incremental support)
     pub fn build(&self, force: bool, writer_budget_bytes: usize) -> Result<usize> {
+        let stats = self.build_stats(force, writer_budget_bytes)?;
+        print_build_stats(&stats);
+        Ok(stats.indexed)
+    }
+
+    /// Build or rebuild the index without printing a summary
+    pub fn build_stats(&self, force: bool, writer_budget_bytes: usize) -> Result<BuildStats> {
         let index_path = self.root.join(INDEX_DIR);
         let metadata_path = self.root.join(METADATA_FILE);
 
@@ -957,26 +1050,49 @@ impl IndexBuilder {
                 || schema.get_field("doc_type").is_err()
                 || schema.get_field("symbol_id").is_err()
                 || schema.get_field("symbol_end_line").is_err()
+                || schema.get_field("symbol_kind").is_err()
             {
                 anyhow::bail!(
                     "Index schema upgrade required: missing symbol-level fields.\n\
                      Run 'cgrep index --force' to rebuild the index."
                 );
             }
+            match content_tokenizer(&schema).as_deref() {
+                Some(tokenizer::CODE_TOKENIZER | tokenizer::CODE_STEM_TOKENIZER) => {}
+                _ => anyhow::bail!(
+                    "Index schema upgrade required: content is not indexed with the code tokenizer.\n\
+                     Run 'cgrep index --force' to rebuild the index."
+                ),
+            }
+            if content_tokenizer(&schema).as_deref() != Some(self.tokenizer) {
+                eprintln!(
+                    "Warning: [index] stemming changed since the index was built; \
+                     run 'cgrep index --force' to apply it"
+                );
+            }
+            tokenizer::register(&index);
             index
         } else {
             if index_path.exists() {
                 std::fs::remove_dir_all(&index_path)?;
             }
             std::fs::create_dir_all(&index_path)?;
-            Index::create_in_dir(&index_path, self.schema.clone())
-                .context("Failed to create index")?
+            let index = Index::create_in_dir(&index_path, self.schema.clone())
+                .context("Failed to create index")?;
+            tokenizer::register(&index);
+            index
         };
 
         let mut writer: IndexWriter = index
             .writer(writer_budget_bytes)
             .context("Failed to create index writer")?;
 
+        // Files skipped as unchanged must still be re-parsed when the symbol
+        // table is new, otherwise it would never learn about them.
+        let mut symbol_table = SymbolTable::open(SymbolTable::path_for(&self.root))?;
+        let reparse = force || (symbol_table.is_new() && !old_metadata.files.is_empty());
+        symbol_table.begin()?;
+
         let scanner = FileScanner::with_excludes(&self.root, self.exclude_patterns.clone())
             .with_gitignore(false);
         let files = scanner.list_files()?;
@@ -996,6 +1112,8 @@ impl IndexBuilder {
                 path: String,
                 meta: FileMetadata,
                 docs: Vec<TantivyDocument>,
+                language: String,
+                symbols: Vec<Symbol>,
             },
             ReadError {
                 path: String,
@@ -1010,9 +1128,14 @@ impl IndexBuilder {
         let mut skipped_count = 0usize;
         let mut deleted_count = 0usize;
         let mut error_count = 0usize;
+        let mut graph_dirty = reparse || !DependencyGraph::is_current(&self.root);
         let mut indexing_error: Option<anyhow::Error> = None;
 
-        let pb = ProgressBar::new(total_files as u64);
+        let pb = if self.show_progress {
+            ProgressBar::new(total_files as u64)
+        } else {
+            ProgressBar::hidden()
+        };
         pb.set_style(
             ProgressStyle::default_bar()
                 .template("[{bar:40.cyan/blue}] {pos}/{len} files | Indexing {msg}")
@@ -1029,6 +1152,7 @@ impl IndexBuilder {
         let doc_type_field = self.fields.doc_type;
         let symbol_id_field = self.fields.symbol_id;
         let symbol_end_line_field = self.fields.symbol_end_line;
+        let symbol_kind_field = self.fields.symbol_kind;
         let line_number_field = self.fields.line_number;
 
         if !old_metadata.files.is_empty() {
@@ -1041,8 +1165,10 @@ impl IndexBuilder {
             if !removed_paths.is_empty() {
                 for path in &removed_paths {
                     writer.delete_term(Term::from_field_text(path_exact_field, path));
+                    symbol_table.delete_file_symbols(path)?;
                 }
                 deleted_count = removed_paths.len();
+                graph_dirty = true;
             }
         }
 
@@ -1086,7 +1212,7 @@ impl IndexBuilder {
                     let existing_meta = old_metadata.files.get(&path_str).cloned();
 
                     if let Some(meta) =
-                        should_skip_without_read(existing_meta.as_ref(), mtime, size, force)
+                        should_skip_without_read(existing_meta.as_ref(), mtime, size, reparse)
                     {
                         let _ = tx.send(ProcessedFile::Skipped {
                             path: path_str,
@@ -1118,6 +1244,7 @@ impl IndexBuilder {
                                 hash: hash.unwrap_or_default(),
                                 symbols: String::new(),
                                 is_binary: true,
+                                imports: Some(Vec::new()),
                             };
                             let _ = tx.send(ProcessedFile::Skipped {
                                 path: path_str,
@@ -1130,7 +1257,11 @@ impl IndexBuilder {
                     };
 
                     if let Some(meta) = existing_meta.as_ref() {
-                        if !force && !hash.is_empty() && meta.hash == hash {
+                        if !reparse
+                            && !hash.is_empty()
+                            && meta.hash == hash
+                            && meta.imports.is_some()
+                        {
                             let mut updated = meta.clone();
                             updated.mtime = mtime;
                             updated.size = size;
@@ -1152,7 +1283,8 @@ impl IndexBuilder {
 
                     let full_text = join_chunks(&chunks);
                     let symbol_list = if !lang_str.is_empty() {
-                        extract_symbols_from_text(&full_text, &lang_str)
+                        let rel_path = path.strip_prefix(&self.root).unwrap_or(path);
+                        extract_symbols_from_text(&full_text, &lang_str, rel_path)
                     } else {
                         Vec::new()
                     };
@@ -1161,6 +1293,11 @@ impl IndexBuilder {
                     } else {
                         String::new()
                     };
+                    let imports = if !lang_str.is_empty() {
+                        extract_imports_from_text(&full_text, &lang_str)
+                    } else {
+                        Vec::new()
+                    };
                     let symbol_docs = filter_symbols(
                         symbol_list.clone(),
                         self.allowed_symbol_kinds.as_ref(),
@@ -1173,6 +1310,7 @@ impl IndexBuilder {
                         hash,
                         symbols: symbols.clone(),
                         is_binary: false,
+                        imports: Some(imports),
                     };
 
                     if chunks.is_empty() {
@@ -1221,6 +1359,7 @@ impl IndexBuilder {
                         doc.add_text(symbol_id_field, &symbol_id);
                         doc.add_u64(line_number_field, symbol.line as u64);
                         doc.add_u64(symbol_end_line_field, symbol.end_line as u64);
+                        doc.add_text(symbol_kind_field, symbol.kind.to_string());
                         docs.push(doc);
                     }
 
@@ -1228,6 +1367,8 @@ impl IndexBuilder {
                         path: path_str,
                         meta,
                         docs,
+                        language: lang_str,
+                        symbols: symbol_list,
                     });
                     pb_producer.inc(1);
                 });
@@ -1242,12 +1383,22 @@ impl IndexBuilder {
                         delete_docs,
                     } => {
                         if delete_docs {
+                            graph_dirty = true;
                             writer.delete_term(Term::from_field_text(path_exact_field, &path));
+                            if let Err(err) = symbol_table.delete_file_symbols(&path) {
+                                indexing_error.get_or_insert(err);
+                            }
                         }
                         skipped_count += 1;
                         new_metadata.files.insert(path, meta);
                     }
-                    ProcessedFile::Indexed { path, meta, docs } => {
+                    ProcessedFile::Indexed {
+                        path,
+                        meta,
+                        docs,
+                        language,
+                        symbols,
+                    } => {
                         if indexing_error.is_none() {
                             writer.delete_term(Term::from_field_text(path_exact_field, &path));
                             for doc in docs {
@@ -1256,8 +1407,14 @@ impl IndexBuilder {
                                     break;
                                 }
                             }
+                            if let Err(err) =
+                                symbol_table.replace_file_symbols(&path, &language, &symbols)
+                            {
+                                indexing_error.get_or_insert(err);
+                            }
                         }
                         indexed_count += 1;
+                        graph_dirty = true;
                         new_metadata.files.insert(path, meta);
                     }
                     ProcessedFile::ReadError { path, fallback } => {
@@ -1278,64 +1435,152 @@ impl IndexBuilder {
         }
 
         writer.commit()?;
+        symbol_table.commit()?;
 
         // Save updated metadata
         let metadata_json = serde_json::to_string_pretty(&new_metadata)?;
         std::fs::write(&metadata_path, metadata_json)?;
 
-        let indexed = indexed_count;
-        let skipped = skipped_count;
-
-        if error_count > 0 {
-            eprintln!("Warning: {} files could not be read", error_count);
+        if graph_dirty {
+            self.build_dependency_graph(&new_metadata)
+                .save(&self.root)?;
         }
 
-        if skipped > 0 || deleted_count > 0 {
-            println!(
-                "{} Indexed {} files ({} unchanged, {} removed, {} total)",
-                "✓".green(),
-                indexed.to_string().cyan(),
-                skipped.to_string().dimmed(),
-                deleted_count.to_string().dimmed(),
-                total_files
-            );
-        } else {
-            println!("{} Indexed {} files", "✓".green(), indexed);
-        }
+        Ok(BuildStats {
+            indexed: indexed_count,
+            unchanged: skipped_count,
+            removed: deleted_count,
+            unreadable: error_count,
+            total: total_files,
+        })
+    }
 
-        Ok(indexed)
+    /// Resolve the recorded imports of every file into a dependency graph
+    fn build_dependency_graph(&self, metadata: &IndexMetadata) -> DependencyGraph {
+        let mut files: Vec<(std::path::PathBuf, &FileMetadata)> = metadata
+            .files
+            .iter()
+            .map(|(path, meta)| {
+                let path = Path::new(path);
+                let rel = path.strip_prefix(&self.root).unwrap_or(path);
+                (rel.to_path_buf(), meta)
+            })
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut resolver = ImportResolver::new(&self.root)
+            .with_files(files.iter().map(|(rel, _)| rel.clone()).collect());
+        let mut graph = DependencyGraph::new();
+        for (rel, meta) in &files {
+            let from = rel.to_string_lossy();
+            graph.add_file(&from);
+            let Some(lang) = rel
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(detect_language)
+            else {
+                continue;
+            };
+            for import in meta.imports.iter().flatten() {
+                for target in resolver.resolve(rel, &lang, import) {
+                    let target = target.to_string_lossy();
+                    if import.kind == ImportKind::Mod {
+                        graph.add_declaration(&from, &target, import.line);
+                    } else {
+                        graph.add_edge(&from, &target, import.line);
+                    }
+                }
+            }
+        }
+        graph
     }
 
     /// Open existing index
     #[allow(dead_code)]
     pub fn open(root: impl AsRef<Path>) -> Result<Index> {
         let index_path = root.as_ref().join(INDEX_DIR);
-        Index::open_in_dir(&index_path).context("Failed to open index. Run 'cgrep index' first.")
+        let index = Index::open_in_dir(&index_path)
+            .context("Failed to open index. Run 'cgrep index' first.")?;
+        tokenizer::register(&index);
+        Ok(index)
     }
 }
 
-/// Run the index command
-pub fn run(
-    path: Option<&str>,
-    force: bool,
-    excludes: Vec<String>,
-    high_memory: bool,
-    embeddings_mode: &str,
-    embeddings_force: bool,
-) -> Result<()> {
-    let root = path
-        .map(std::path::PathBuf::from)
-        .or_else(|| std::env::current_dir().ok())
-        .ok_or_else(|| anyhow::anyhow!("Cannot determine current directory"))?;
+/// Analyzer the `content` field of an index was built with
+fn content_tokenizer(schema: &Schema) -> Option<String> {
+    let field = schema.get_field("content").ok()?;
+    match schema.get_field_entry(field).field_type() {
+        tantivy::schema::FieldType::Str(options) => options
+            .get_indexing_options()
+            .map(|indexing| indexing.tokenizer().to_string()),
+        _ => None,
+    }
+}
 
-    let config = Config::load_for_dir(&root);
+fn print_build_stats(stats: &BuildStats) {
+    if stats.unreadable > 0 {
+        eprintln!("Warning: {} files could not be read", stats.unreadable);
+    }
+
+    if stats.unchanged > 0 || stats.removed > 0 {
+        println!(
+            "{} Indexed {} files ({} unchanged, {} removed, {} total)",
+            "✓".green(),
+            stats.indexed.to_string().cyan(),
+            stats.unchanged.to_string().dimmed(),
+            stats.removed.to_string().dimmed(),
+            stats.total
+        );
+    } else {
+        println!("{} Indexed {} files", "✓".green(), stats.indexed);
+    }
+}
+
+/// Options for [`build`]
+#[derive(Debug, Clone, Default)]
+pub struct IndexOptions {
+    pub root: PathBuf,
+    /// Rebuild from scratch instead of updating incrementally
+    pub force: bool,
+    /// Extra exclude patterns, applied before the config's `index.exclude_paths`
+    pub excludes: Vec<String>,
+    /// Use a 1GiB tantivy writer budget
+    pub high_memory: bool,
+    pub embeddings: EmbeddingsMode,
+    /// Regenerate all embeddings even when up to date
+    pub embeddings_force: bool,
+    /// Draw a progress bar on the terminal
+    pub progress: bool,
+}
+
+impl IndexOptions {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Outcome of [`build`]
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexSummary {
+    pub files: BuildStats,
+    /// Embedding work, when embeddings are enabled
+    pub embeddings: Option<EmbeddingIndexStats>,
+}
+
+/// Build or update the index under `options.root`, honoring its config
+pub fn build(options: &IndexOptions) -> Result<IndexSummary> {
+    let root = &options.root;
+    let config = Config::load_for_dir(root);
 
     // Merge CLI excludes with config excludes (CLI takes precedence by being added first)
-    let mut all_excludes = excludes;
+    let mut all_excludes = options.excludes.clone();
     all_excludes.extend(config.index().exclude_paths().iter().cloned());
 
     let builder = IndexBuilder::with_excludes_and_symbols(
-        &root,
+        root,
         all_excludes,
         config.embeddings.symbol_preview_lines(),
         config.embeddings.symbol_max_chars(),
@@ -1344,32 +1589,82 @@ pub fn run(
             .embeddings
             .symbol_kinds()
             .map(|kinds| kinds.into_iter().collect()),
-    )?;
-    let writer_budget_bytes = if high_memory {
-        eprintln!("Using high-memory indexing: writer budget = 1GiB");
+    )?
+    .with_stemming(config.index().stemming());
+    let builder = if options.progress {
+        builder
+    } else {
+        builder.without_progress()
+    };
+    let writer_budget_bytes = if options.high_memory {
         HIGH_MEMORY_WRITER_BUDGET_BYTES
     } else {
         DEFAULT_WRITER_BUDGET_BYTES
     };
-    let count = builder.build(force, writer_budget_bytes)?;
+    let files = builder.build_stats(options.force, writer_budget_bytes)?;
+
+    if options.embeddings == EmbeddingsMode::Off {
+        return Ok(IndexSummary {
+            files,
+            embeddings: None,
+        });
+    }
 
-    println!("Index complete: {} files", count);
+    let metadata_path = root.join(METADATA_FILE);
+    let content = std::fs::read_to_string(&metadata_path)
+        .with_context(|| format!("Failed to read index metadata: {}", metadata_path.display()))?;
+    let index_metadata: IndexMetadata =
+        serde_json::from_str(&content).context("Failed to parse index metadata")?;
+
+    let stats = index_embeddings(
+        root,
+        options.embeddings,
+        options.embeddings_force,
+        &config,
+        &index_metadata,
+    )?;
+    Ok(IndexSummary {
+        files,
+        embeddings: Some(stats),
+    })
+}
 
-    let mode = EmbeddingsMode::parse(embeddings_mode)?;
-    if embeddings_force && mode == EmbeddingsMode::Off {
-        eprintln!("Warning: --embeddings-force has no effect when --embeddings=off");
-        return Ok(());
+/// Run the index command
+pub fn run(
+    path: Option<&str>,
+    force: bool,
+    excludes: Vec<String>,
+    high_memory: bool,
+    embeddings_mode: &str,
+    embeddings_force: bool,
+) -> Result<()> {
+    let root = path
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine current directory"))?;
+    let options = IndexOptions {
+        root,
+        force,
+        excludes,
+        high_memory,
+        embeddings: EmbeddingsMode::parse(embeddings_mode)?,
+        embeddings_force,
+        progress: true,
+    };
+
+    if high_memory {
+        eprintln!("Using high-memory indexing: writer budget = 1GiB");
     }
+    let summary = build(&options)?;
 
-    if mode != EmbeddingsMode::Off {
-        let metadata_path = root.join(METADATA_FILE);
-        let content = std::fs::read_to_string(&metadata_path).with_context(|| {
-            format!("Failed to read index metadata: {}", metadata_path.display())
-        })?;
-        let index_metadata: IndexMetadata =
-            serde_json::from_str(&content).context("Failed to parse index metadata")?;
+    print_build_stats(&summary.files);
+    println!("Index complete: {} files", summary.files.indexed);
 
-        let stats = index_embeddings(&root, mode, embeddings_force, &config, &index_metadata)?;
+    if embeddings_force && options.embeddings == EmbeddingsMode::Off {
+        eprintln!("Warning: --embeddings-force has no effect when --embeddings=off");
+    }
+
+    if let Some(stats) = summary.embeddings {
         if stats.files_embedded > 0 || stats.files_skipped_up_to_date > 0 || stats.files_deleted > 0
         {
             println!(
@@ -1380,6 +1675,11 @@ pub fn run(
                 stats.files_deleted
             );
         }
+        match stats.ann_lists {
+            Some(0) => println!("Embeddings: ANN index removed, using exact search"),
+            Some(lists) => println!("Embeddings: ANN index rebuilt with {} clusters", lists),
+            None => {}
+        }
     }
 
     Ok(())
@@ -1621,6 +1921,82 @@ mod tests {
         assert!(meta.symbols.contains("cached_symbol"));
     }
 
+    #[test]
+    fn symbol_table_tracks_index_updates() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        let file_path = root.join("lib.rs");
+        std::fs::write(&file_path, "fn table_alpha() {}\nstruct TableBeta;\n").expect("write");
+
+        let builder = IndexBuilder::new(root).expect("builder");
+        builder
+            .build(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+
+        let table = SymbolTable::open_existing(root)
+            .expect("open")
+            .expect("table");
+        let found = table.find_by_name("table").expect("find");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "table_alpha");
+        assert_eq!(found[0].kind, "function");
+        assert!(found[0].byte_start.is_some());
+        drop(table);
+
+        // A missing table is backfilled even though the file is unchanged.
+        std::fs::remove_file(SymbolTable::path_for(root)).expect("remove table");
+        builder
+            .build(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("backfill build");
+        let table = SymbolTable::open_existing(root)
+            .expect("open")
+            .expect("table");
+        assert_eq!(table.find_by_name("table").expect("find").len(), 2);
+        drop(table);
+
+        std::fs::remove_file(&file_path).expect("remove file");
+        builder
+            .build(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("rebuild");
+        let table = SymbolTable::open_existing(root)
+            .expect("open")
+            .expect("table");
+        assert!(table.find_by_name("table").expect("find").is_empty());
+    }
+
+    #[test]
+    fn dependency_graph_tracks_index_updates() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        std::fs::write(root.join("main.rs"), "mod util;\nfn main() {}\n").expect("write");
+        std::fs::create_dir_all(root.join("util")).expect("mkdir");
+        std::fs::write(root.join("util/helpers.rs"), "pub fn h() {}\n").expect("write");
+
+        let builder = IndexBuilder::new(root).expect("builder");
+        builder
+            .build(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+        let graph = DependencyGraph::load(root).expect("load").expect("graph");
+        assert!(graph.walk("main.rs", false, None).is_empty());
+
+        // Adding the imported file links the unchanged importer to it.
+        std::fs::write(root.join("util.rs"), "mod helpers;\n").expect("write");
+        builder
+            .build(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("rebuild");
+        let graph = DependencyGraph::load(root).expect("load").expect("graph");
+        let reached: Vec<String> = graph
+            .walk("main.rs", false, None)
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(reached, vec!["util.rs", "util/helpers.rs"]);
+        assert_eq!(
+            graph.walk("util/helpers.rs", true, Some(1))[0].path,
+            "util.rs"
+        );
+    }
+
     #[test]
     fn symbol_id_is_stable() {
         let symbol = Symbol {
@@ -2,8 +2,12 @@
 
 //! Indexer module - handles file scanning, indexing, and watching
 
+pub mod deps;
 pub mod index;
+pub mod resolve;
 pub mod scanner;
+pub mod symbol_table;
+pub mod tokenizer;
 pub mod watch;
 
 pub use index::IndexBuilder;
@@ -0,0 +1,696 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Language-aware resolution of import statements to repository files
+//!
+//! Resolution is lexical: it follows each language's module layout rules
+//! (Rust module trees, Python packages, TS/JS relative paths and `tsconfig`
+//! aliases, Go module paths, Java packages) and only reports files that exist
+//! under the repository root. Third-party and standard-library imports resolve
+//! to nothing.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::indexer::scanner::FileScanner;
+use crate::parser::imports::{Import, ImportKind};
+
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "d.ts", "js", "jsx", "mjs", "cjs"];
+
+/// Path alias configuration from a `tsconfig.json` / `jsconfig.json`
+#[derive(Debug, Clone, Default)]
+struct TsConfig {
+    base_url: Option<PathBuf>,
+    paths: Vec<(String, Vec<String>)>,
+}
+
+/// Resolves extracted imports to repository-relative file paths
+pub struct ImportResolver {
+    root: PathBuf,
+    known_files: Option<Vec<PathBuf>>,
+    cargo_crates: HashMap<PathBuf, Option<(PathBuf, Option<String>)>>,
+    go_modules: HashMap<PathBuf, Option<(PathBuf, String)>>,
+    ts_configs: HashMap<PathBuf, Option<TsConfig>>,
+}
+
+impl ImportResolver {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            known_files: None,
+            cargo_crates: HashMap::new(),
+            go_modules: HashMap::new(),
+            ts_configs: HashMap::new(),
+        }
+    }
+
+    /// Use an already-listed set of root-relative files instead of scanning
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.known_files = Some(files);
+        self
+    }
+
+    /// Resolve an import made by `from` (root-relative) to root-relative files
+    pub fn resolve(&mut self, from: &Path, language: &str, import: &Import) -> Vec<PathBuf> {
+        let mut resolved = match language {
+            "rust" => self.resolve_rust(from, import).into_iter().collect(),
+            "python" => self.resolve_python(from, import),
+            "typescript" | "tsx" | "javascript" => {
+                self.resolve_javascript(from, import).into_iter().collect()
+            }
+            "go" => self.resolve_go(from, import),
+            "java" => self.resolve_java(import),
+            _ => Vec::new(),
+        };
+        resolved.retain(|path| path != from);
+        resolved.sort();
+        resolved.dedup();
+        resolved
+    }
+
+    fn is_file(&self, rel: &Path) -> bool {
+        self.root.join(rel).is_file()
+    }
+
+    fn first_file(&self, candidates: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+        candidates
+            .into_iter()
+            .filter_map(|candidate| normalize(&candidate))
+            .find(|candidate| self.is_file(candidate))
+    }
+
+    // ---- Rust ----
+
+    fn resolve_rust(&mut self, from: &Path, import: &Import) -> Option<PathBuf> {
+        let self_dir = rust_module_dir(from);
+        if import.kind == ImportKind::Mod {
+            return self.first_file([
+                self_dir.join(format!("{}.rs", import.module)),
+                self_dir.join(&import.module).join("mod.rs"),
+            ]);
+        }
+
+        let segments: Vec<&str> = import.module.split("::").collect();
+        let (first, rest) = segments.split_first()?;
+        let crate_info = self.cargo_crate(from);
+
+        let (base_dir, rest) = match *first {
+            "crate" => (crate_info.as_ref()?.0.clone(), rest),
+            "self" => (self_dir, rest),
+            "super" => {
+                let mut dir = self_dir.parent()?.to_path_buf();
+                let mut rest = rest;
+                while let Some((&"super", tail)) = rest.split_first() {
+                    dir = dir.parent()?.to_path_buf();
+                    rest = tail;
+                }
+                (dir, rest)
+            }
+            name if crate_info
+                .as_ref()
+                .and_then(|(_, lib)| lib.as_deref())
+                .is_some_and(|lib| lib == name) =>
+            {
+                let src = crate_info.as_ref()?.0.clone();
+                return self
+                    .walk_rust_modules(&src, rest)
+                    .or_else(|| self.first_file([src.join("lib.rs")]));
+            }
+            // Uniform paths: `use child::Item` relative to the current module
+            _ => return self.walk_rust_modules(&self_dir, &segments),
+        };
+
+        self.walk_rust_modules(&base_dir, rest)
+            .or_else(|| self.rust_module_file(&base_dir))
+    }
+
+    /// Deepest module file reachable by following `segments` from `dir`
+    fn walk_rust_modules(&self, dir: &Path, segments: &[&str]) -> Option<PathBuf> {
+        let mut dir = dir.to_path_buf();
+        let mut best = None;
+        for segment in segments {
+            let Some(file) = self.first_file([
+                dir.join(format!("{}.rs", segment)),
+                dir.join(segment).join("mod.rs"),
+            ]) else {
+                break;
+            };
+            best = Some(file);
+            dir = dir.join(segment);
+        }
+        best
+    }
+
+    /// File that defines the module whose children live in `dir`
+    fn rust_module_file(&self, dir: &Path) -> Option<PathBuf> {
+        let mut candidates = vec![dir.join("mod.rs"), dir.join("lib.rs"), dir.join("main.rs")];
+        if let (Some(parent), Some(name)) = (dir.parent(), dir.file_name()) {
+            candidates.insert(0, parent.join(format!("{}.rs", name.to_string_lossy())));
+        }
+        self.first_file(candidates)
+    }
+
+    /// `src` dir and library crate name of the package containing `from`
+    fn cargo_crate(&mut self, from: &Path) -> Option<(PathBuf, Option<String>)> {
+        let package_dir = from
+            .ancestors()
+            .skip(1)
+            .find(|dir| self.root.join(dir).join("Cargo.toml").is_file())?
+            .to_path_buf();
+        if let Some(cached) = self.cargo_crates.get(&package_dir) {
+            return cached.clone();
+        }
+
+        let manifest = std::fs::read_to_string(self.root.join(&package_dir).join("Cargo.toml"))
+            .ok()
+            .and_then(|content| content.parse::<toml::Table>().ok());
+        let lib_name = manifest.as_ref().and_then(|manifest| {
+            manifest
+                .get("lib")
+                .and_then(|lib| lib.get("name"))
+                .or_else(|| manifest.get("package").and_then(|pkg| pkg.get("name")))
+                .and_then(|name| name.as_str())
+                .map(|name| name.replace('-', "_"))
+        });
+        let info = Some((package_dir.join("src"), lib_name));
+        self.cargo_crates.insert(package_dir, info.clone());
+        info
+    }
+
+    // ---- Python ----
+
+    fn resolve_python(&self, from: &Path, import: &Import) -> Vec<PathBuf> {
+        let dots = import.module.chars().take_while(|c| *c == '.').count();
+        let segments: Vec<&str> = import.module[dots..]
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let bases: Vec<PathBuf> = if dots > 0 {
+            let mut base = from.parent().unwrap_or(Path::new("")).to_path_buf();
+            for _ in 1..dots {
+                base = base.parent().unwrap_or(Path::new("")).to_path_buf();
+            }
+            vec![base]
+        } else {
+            vec![
+                PathBuf::new(),
+                PathBuf::from("src"),
+                from.parent().unwrap_or(Path::new("")).to_path_buf(),
+            ]
+        };
+
+        for base in bases {
+            let module_file = self.python_module(&base, &segments);
+            let mut resolved: Vec<PathBuf> = import
+                .names
+                .iter()
+                .filter_map(|name| {
+                    let mut path = segments.clone();
+                    path.extend(name.split('.'));
+                    self.python_module(&base, &path)
+                })
+                .collect();
+            if resolved.len() < import.names.len() || import.names.is_empty() {
+                resolved.extend(module_file);
+            }
+            if !resolved.is_empty() {
+                return resolved;
+            }
+        }
+        Vec::new()
+    }
+
+    fn python_module(&self, base: &Path, segments: &[&str]) -> Option<PathBuf> {
+        let mut path = base.to_path_buf();
+        for segment in segments {
+            path.push(segment);
+        }
+        let mut candidates = vec![path.join("__init__.py")];
+        if !segments.is_empty() {
+            candidates.insert(0, path.with_extension("py"));
+        }
+        self.first_file(candidates)
+    }
+
+    // ---- TypeScript / JavaScript ----
+
+    fn resolve_javascript(&mut self, from: &Path, import: &Import) -> Option<PathBuf> {
+        let spec = import.module.as_str();
+        let from_dir = from.parent().unwrap_or(Path::new(""));
+        if spec.starts_with("./") || spec.starts_with("../") || spec == "." || spec == ".." {
+            return self.probe_javascript(&from_dir.join(spec));
+        }
+
+        let config = self.ts_config(from_dir)?;
+        for (pattern, targets) in &config.paths {
+            let Some(captured) = match_alias(pattern, spec) else {
+                continue;
+            };
+            let base = config.base_url.clone().unwrap_or_default();
+            for target in targets {
+                let candidate = base.join(target.replace('*', captured));
+                if let Some(found) = self.probe_javascript(&candidate) {
+                    return Some(found);
+                }
+            }
+        }
+        config
+            .base_url
+            .as_ref()
+            .and_then(|base| self.probe_javascript(&base.join(spec)))
+    }
+
+    fn probe_javascript(&self, path: &Path) -> Option<PathBuf> {
+        let path = normalize(path)?;
+        let display = path.to_string_lossy().to_string();
+        let mut candidates = vec![path.clone()];
+        // TS ESM sources import `./x.js` while the file on disk is `x.ts`
+        for (emitted, source) in [("js", "ts"), ("js", "tsx"), ("jsx", "tsx"), ("mjs", "mts")] {
+            if let Some(stem) = display.strip_suffix(&format!(".{}", emitted)) {
+                candidates.push(PathBuf::from(format!("{}.{}", stem, source)));
+            }
+        }
+        for ext in JS_EXTENSIONS {
+            candidates.push(PathBuf::from(format!("{}.{}", display, ext)));
+        }
+        for ext in JS_EXTENSIONS {
+            candidates.push(path.join(format!("index.{}", ext)));
+        }
+        self.first_file(candidates)
+    }
+
+    /// Nearest `tsconfig.json`/`jsconfig.json` at or above `dir`
+    fn ts_config(&mut self, dir: &Path) -> Option<TsConfig> {
+        let config_dir = dir.ancestors().find(|d| {
+            self.root.join(d).join("tsconfig.json").is_file()
+                || self.root.join(d).join("jsconfig.json").is_file()
+        })?;
+        let config_dir = config_dir.to_path_buf();
+        if let Some(cached) = self.ts_configs.get(&config_dir) {
+            return cached.clone();
+        }
+
+        let parsed = ["tsconfig.json", "jsconfig.json"]
+            .iter()
+            .find_map(|name| std::fs::read_to_string(self.root.join(&config_dir).join(name)).ok())
+            .and_then(|content| parse_ts_config(&content, &config_dir));
+        self.ts_configs.insert(config_dir, parsed.clone());
+        parsed
+    }
+
+    // ---- Go ----
+
+    fn resolve_go(&mut self, from: &Path, import: &Import) -> Vec<PathBuf> {
+        let Some((module_dir, module_path)) = self.go_module(from) else {
+            return Vec::new();
+        };
+        let rest = if import.module == module_path {
+            ""
+        } else if let Some(rest) = import.module.strip_prefix(&format!("{}/", module_path)) {
+            rest
+        } else {
+            return Vec::new();
+        };
+
+        let package_dir = module_dir.join(rest);
+        let Ok(entries) = std::fs::read_dir(self.root.join(&package_dir)) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".go") && !name.ends_with("_test.go"))
+            .filter_map(|name| normalize(&package_dir.join(name)))
+            .filter(|path| self.is_file(path))
+            .collect()
+    }
+
+    /// Directory and module path of the `go.mod` governing `from`
+    fn go_module(&mut self, from: &Path) -> Option<(PathBuf, String)> {
+        let module_dir = from
+            .ancestors()
+            .skip(1)
+            .find(|dir| self.root.join(dir).join("go.mod").is_file())?
+            .to_path_buf();
+        if let Some(cached) = self.go_modules.get(&module_dir) {
+            return cached.clone();
+        }
+
+        let module = std::fs::read_to_string(self.root.join(&module_dir).join("go.mod"))
+            .ok()
+            .and_then(|content| {
+                content.lines().find_map(|line| {
+                    line.trim()
+                        .strip_prefix("module ")
+                        .map(|name| name.trim().trim_matches('"').to_string())
+                })
+            })
+            .map(|name| (module_dir.clone(), name));
+        self.go_modules.insert(module_dir, module.clone());
+        module
+    }
+
+    // ---- Java ----
+
+    fn resolve_java(&mut self, import: &Import) -> Vec<PathBuf> {
+        let files = self.java_files();
+        if let Some(package) = import.module.strip_suffix(".*") {
+            let package_dir: PathBuf = package.split('.').collect();
+            return files
+                .iter()
+                .filter(|path| path.parent().is_some_and(|dir| dir.ends_with(&package_dir)))
+                .cloned()
+                .collect();
+        }
+
+        // Static imports name a member: drop trailing segments until a class matches
+        let segments: Vec<&str> = import.module.split('.').collect();
+        for len in (2..=segments.len()).rev() {
+            let mut class_path: PathBuf = segments[..len].iter().collect();
+            class_path.set_extension("java");
+            let matches: Vec<PathBuf> = files
+                .iter()
+                .filter(|path| path.ends_with(&class_path))
+                .cloned()
+                .collect();
+            if !matches.is_empty() {
+                return matches;
+            }
+        }
+        Vec::new()
+    }
+
+    fn java_files(&mut self) -> Vec<PathBuf> {
+        if self.known_files.is_none() {
+            let files = FileScanner::new(&self.root)
+                .list_files()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|path| path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf())
+                .collect();
+            self.known_files = Some(files);
+        }
+        self.known_files
+            .iter()
+            .flatten()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "java"))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Directory holding the child modules of a Rust source file
+fn rust_module_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new(""));
+    match file.file_name().and_then(|name| name.to_str()) {
+        Some("mod.rs" | "lib.rs" | "main.rs") => parent.to_path_buf(),
+        _ => match file.file_stem() {
+            Some(stem) => parent.join(stem),
+            None => parent.to_path_buf(),
+        },
+    }
+}
+
+/// Lexically normalize a root-relative path; `None` if it escapes the root
+pub fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => out.push(part),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Match a `tsconfig` paths pattern with at most one `*` wildcard
+fn match_alias<'a>(pattern: &str, spec: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => spec
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix)),
+        None => (pattern == spec).then_some(""),
+    }
+}
+
+fn parse_ts_config(content: &str, config_dir: &Path) -> Option<TsConfig> {
+    let value: serde_json::Value = serde_json::from_str(&strip_json_comments(content)).ok()?;
+    let options = value.get("compilerOptions");
+    let base_url = options
+        .and_then(|o| o.get("baseUrl"))
+        .and_then(|b| b.as_str())
+        .map(|b| config_dir.join(b));
+    let paths = options
+        .and_then(|o| o.get("paths"))
+        .and_then(|p| p.as_object())
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|t| t.as_str().map(str::to_string))
+                        .collect();
+                    (pattern.clone(), targets)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // `paths` without `baseUrl` resolve relative to the config file
+    let base_url = base_url.or_else(|| Some(config_dir.to_path_buf()));
+    Some(TsConfig { base_url, paths })
+}
+
+/// Remove `//` and `/* */` comments and trailing commas from JSONC
+fn strip_json_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            if ch == '\\' {
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match (ch, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(ch);
+            }
+            ('/', Some('/')) => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            _ => out.push(ch),
+        }
+    }
+
+    // Drop trailing commas before a closing bracket
+    let mut cleaned = String::with_capacity(out.len());
+    let chars: Vec<char> = out.chars().collect();
+    for (idx, ch) in chars.iter().enumerate() {
+        if *ch == ',' {
+            let next = chars[idx + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        cleaned.push(*ch);
+    }
+    cleaned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn import(module: &str) -> Import {
+        Import {
+            module: module.to_string(),
+            names: Vec::new(),
+            kind: ImportKind::Import,
+            line: 1,
+        }
+    }
+
+    fn paths(items: &[&str]) -> Vec<PathBuf> {
+        items.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn resolves_rust_module_paths() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"my-crate\"\n");
+        write(root, "src/lib.rs", "");
+        write(root, "src/main.rs", "");
+        write(root, "src/query/mod.rs", "");
+        write(root, "src/query/search.rs", "");
+        write(root, "src/utils.rs", "");
+
+        let mut resolver = ImportResolver::new(root);
+        let from = Path::new("src/query/search.rs");
+        assert_eq!(
+            resolver.resolve(from, "rust", &import("crate::utils::helper")),
+            paths(&["src/utils.rs"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "rust", &import("super::Thing")),
+            paths(&["src/query/mod.rs"])
+        );
+        assert_eq!(
+            resolver.resolve(Path::new("src/main.rs"), "rust", &import("my_crate::utils")),
+            paths(&["src/utils.rs"])
+        );
+        assert!(resolver
+            .resolve(from, "rust", &import("std::collections::HashMap"))
+            .is_empty());
+
+        let module = Import {
+            kind: ImportKind::Mod,
+            ..import("search")
+        };
+        assert_eq!(
+            resolver.resolve(Path::new("src/query/mod.rs"), "rust", &module),
+            paths(&["src/query/search.rs"])
+        );
+    }
+
+    #[test]
+    fn resolves_python_packages_and_relative_imports() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "app/__init__.py", "");
+        write(root, "app/utils.py", "");
+        write(root, "app/core/__init__.py", "");
+        write(root, "app/core/engine.py", "");
+        write(root, "other/utils.py", "");
+
+        let mut resolver = ImportResolver::new(root);
+        let from = Path::new("app/core/engine.py");
+        assert_eq!(
+            resolver.resolve(from, "python", &import("app.utils")),
+            paths(&["app/utils.py"])
+        );
+        let relative = Import {
+            names: vec!["utils".to_string()],
+            ..import("..")
+        };
+        assert_eq!(
+            resolver.resolve(from, "python", &relative),
+            paths(&["app/utils.py"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "python", &import("app.core")),
+            paths(&["app/core/__init__.py"])
+        );
+        assert!(resolver.resolve(from, "python", &import("json")).is_empty());
+    }
+
+    #[test]
+    fn resolves_javascript_relative_and_aliases() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "web/tsconfig.json",
+            "{\n  // comment\n  \"compilerOptions\": {\n    \"baseUrl\": \".\",\n    \"paths\": { \"@lib/*\": [\"src/lib/*\"], },\n  },\n}\n",
+        );
+        write(root, "web/src/lib/format.ts", "");
+        write(root, "web/src/components/index.tsx", "");
+        write(root, "web/src/app.ts", "");
+
+        let mut resolver = ImportResolver::new(root);
+        let from = Path::new("web/src/app.ts");
+        assert_eq!(
+            resolver.resolve(from, "typescript", &import("./components")),
+            paths(&["web/src/components/index.tsx"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "typescript", &import("./lib/format.js")),
+            paths(&["web/src/lib/format.ts"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "typescript", &import("@lib/format")),
+            paths(&["web/src/lib/format.ts"])
+        );
+        assert!(resolver
+            .resolve(from, "typescript", &import("react"))
+            .is_empty());
+    }
+
+    #[test]
+    fn resolves_go_and_java_imports() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "go.mod", "module example.com/app\n\ngo 1.22\n");
+        write(root, "pkg/util/strings.go", "");
+        write(root, "pkg/util/strings_test.go", "");
+        write(root, "cmd/main.go", "");
+        write(root, "src/main/java/com/acme/Widget.java", "");
+        write(root, "src/main/java/com/acme/model/User.java", "");
+
+        let mut resolver = ImportResolver::new(root);
+        assert_eq!(
+            resolver.resolve(
+                Path::new("cmd/main.go"),
+                "go",
+                &import("example.com/app/pkg/util")
+            ),
+            paths(&["pkg/util/strings.go"])
+        );
+        assert!(resolver
+            .resolve(Path::new("cmd/main.go"), "go", &import("fmt"))
+            .is_empty());
+
+        let from = Path::new("src/main/java/com/acme/App.java");
+        assert_eq!(
+            resolver.resolve(from, "java", &import("com.acme.Widget")),
+            paths(&["src/main/java/com/acme/Widget.java"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "java", &import("com.acme.Widget.DEFAULT")),
+            paths(&["src/main/java/com/acme/Widget.java"])
+        );
+        assert_eq!(
+            resolver.resolve(from, "java", &import("com.acme.model.*")),
+            paths(&["src/main/java/com/acme/model/User.java"])
+        );
+    }
+}
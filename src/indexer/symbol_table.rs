@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Persistent symbol table stored next to the tantivy index.
+//!
+//! `IndexBuilder::build` writes every extracted symbol (kind, position, byte
+//! range, scope) into `.cgrep/symbols.sqlite` so lookups such as `definition`
+//! and `symbols` can be answered without re-parsing source files.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::indexer::index::symbol_id_for;
+use crate::parser::symbols::{qualify, Symbol};
+use crate::utils::INDEX_DIR;
+
+/// File name of the symbol table inside the index directory.
+pub const SYMBOL_TABLE_FILE: &str = "symbols.sqlite";
+
+/// Bump when the table layout or stored values change; older tables are
+/// rebuilt on open.
+const SCHEMA_VERSION: i64 = 2;
+
+/// A symbol row as stored in the symbol table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolRecord {
+    pub symbol_id: String,
+    pub path: String,
+    pub language: String,
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub byte_start: Option<usize>,
+    pub byte_end: Option<usize>,
+    pub scope: Option<String>,
+}
+
+impl SymbolRecord {
+    /// Build a record from a freshly extracted symbol.
+    pub fn from_symbol(path: &str, language: &str, symbol: &Symbol) -> Self {
+        Self {
+            symbol_id: symbol_id_for(path, language, symbol),
+            path: path.to_string(),
+            language: language.to_string(),
+            name: symbol.name.clone(),
+            kind: symbol.kind.to_string(),
+            line: symbol.line,
+            column: symbol.column,
+            end_line: symbol.end_line,
+            byte_start: symbol.byte_start,
+            byte_end: symbol.byte_end,
+            scope: symbol.scope.clone(),
+        }
+    }
+
+    /// Name prefixed with its scope (`crate::index::IndexBuilder::build`)
+    pub fn qualified_name(&self) -> String {
+        qualify(self.scope.as_deref(), &self.name, &self.language)
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            symbol_id: row.get(0)?,
+            path: row.get(1)?,
+            language: row.get(2)?,
+            name: row.get(3)?,
+            kind: row.get(4)?,
+            line: row.get::<_, i64>(5)? as usize,
+            column: row.get::<_, i64>(6)? as usize,
+            end_line: row.get::<_, i64>(7)? as usize,
+            byte_start: row.get::<_, Option<i64>>(8)?.map(|v| v as usize),
+            byte_end: row.get::<_, Option<i64>>(9)?.map(|v| v as usize),
+            scope: row.get(10)?,
+        })
+    }
+}
+
+const SELECT_COLUMNS: &str = "symbol_id, path, language, name, kind, line, col, end_line, \
+                              byte_start, byte_end, scope";
+
+/// SQLite-backed symbol table.
+pub struct SymbolTable {
+    conn: Connection,
+    created: bool,
+}
+
+impl SymbolTable {
+    /// Location of the symbol table for a repository root.
+    pub fn path_for(root: &Path) -> PathBuf {
+        root.join(INDEX_DIR).join(SYMBOL_TABLE_FILE)
+    }
+
+    /// Opens or creates the symbol table at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open symbol table: {}", path.display()))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let created = version != SCHEMA_VERSION;
+        if created {
+            conn.execute_batch("DROP TABLE IF EXISTS symbols;")?;
+        }
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS symbols (
+                symbol_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                language TEXT NOT NULL,
+                name TEXT NOT NULL,
+                name_lower TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                col INTEGER NOT NULL,
+                end_line INTEGER NOT NULL,
+                byte_start INTEGER,
+                byte_end INTEGER,
+                scope TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_symbols_name_lower ON symbols(name_lower);
+            CREATE INDEX IF NOT EXISTS idx_symbols_path ON symbols(path, line);
+            "#,
+        )
+        .context("Failed to initialize symbol table schema")?;
+        conn.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+
+        Ok(Self { conn, created })
+    }
+
+    /// Opens the symbol table for a repository if one has been built.
+    pub fn open_existing(root: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open symbol table: {}", path.display()))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            conn,
+            created: false,
+        }))
+    }
+
+    /// Whether the table was created (or reset) by this open call.
+    pub fn is_new(&self) -> bool {
+        self.created
+    }
+
+    /// Starts a write batch; pair with [`SymbolTable::commit`].
+    pub fn begin(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN")?;
+        Ok(())
+    }
+
+    /// Commits a write batch started with [`SymbolTable::begin`].
+    pub fn commit(&self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// Replaces all symbols stored for a file.
+    pub fn replace_file_symbols(
+        &mut self,
+        path: &str,
+        language: &str,
+        symbols: &[Symbol],
+    ) -> Result<()> {
+        self.delete_file_symbols(path)?;
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO symbols (symbol_id, path, language, name, name_lower, kind, line, col, \
+             end_line, byte_start, byte_end, scope) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for symbol in symbols {
+            let record = SymbolRecord::from_symbol(path, language, symbol);
+            stmt.execute(params![
+                record.symbol_id,
+                record.path,
+                record.language,
+                record.name,
+                record.name.to_lowercase(),
+                record.kind,
+                record.line as i64,
+                record.column as i64,
+                record.end_line as i64,
+                record.byte_start.map(|v| v as i64),
+                record.byte_end.map(|v| v as i64),
+                record.scope,
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Removes all symbols stored for a file.
+    pub fn delete_file_symbols(&mut self, path: &str) -> Result<()> {
+        self.conn
+            .prepare_cached("DELETE FROM symbols WHERE path = ?1")?
+            .execute(params![path])?;
+        Ok(())
+    }
+
+    /// Finds symbols whose name contains `name` (case-insensitive).
+    pub fn find_by_name(&self, name: &str) -> Result<Vec<SymbolRecord>> {
+        let sql = format!(
+            "SELECT {} FROM symbols WHERE instr(name_lower, ?1) > 0 ORDER BY path, line, col",
+            SELECT_COLUMNS
+        );
+        let mut stmt = self.conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map(params![name.to_lowercase()], SymbolRecord::from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    /// Finds symbols named exactly `name` (case-insensitive), through the name index.
+    pub fn find_exact(&self, name: &str) -> Result<Vec<SymbolRecord>> {
+        let sql = format!(
+            "SELECT {} FROM symbols WHERE name_lower = ?1 ORDER BY path, line, col",
+            SELECT_COLUMNS
+        );
+        let mut stmt = self.conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map(params![name.to_lowercase()], SymbolRecord::from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    /// Every stored symbol, ordered by file and position.
+    pub fn all(&self) -> Result<Vec<SymbolRecord>> {
+        let sql = format!(
+            "SELECT {} FROM symbols ORDER BY path, line, col",
+            SELECT_COLUMNS
+        );
+        let mut stmt = self.conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map([], SymbolRecord::from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::symbols::SymbolKind;
+    use tempfile::TempDir;
+
+    fn symbol(name: &str, kind: SymbolKind, line: usize) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind,
+            line,
+            column: 1,
+            end_line: line + 2,
+            byte_start: Some(line * 10),
+            byte_end: Some(line * 10 + 5),
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn replace_and_lookup_symbols() {
+        let dir = TempDir::new().expect("tempdir");
+        let mut table = SymbolTable::open(dir.path().join("symbols.sqlite")).expect("open");
+        assert!(table.is_new());
+
+        table
+            .replace_file_symbols(
+                "src/lib.rs",
+                "rust",
+                &[
+                    symbol("build_index", SymbolKind::Function, 3),
+                    symbol("IndexState", SymbolKind::Struct, 10),
+                ],
+            )
+            .expect("insert");
+
+        let found = table.find_by_name("INDEX").expect("find");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "build_index");
+        assert_eq!(found[0].kind, "function");
+        assert_eq!(found[0].byte_start, Some(30));
+        let exact = table.find_exact("indexstate").expect("find exact");
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].name, "IndexState");
+        assert!(table.find_exact("index").expect("find exact").is_empty());
+
+        table
+            .replace_file_symbols(
+                "src/lib.rs",
+                "rust",
+                &[symbol("other", SymbolKind::Function, 1)],
+            )
+            .expect("replace");
+        assert!(table.find_by_name("index").expect("find").is_empty());
+        assert_eq!(table.find_by_name("").expect("all").len(), 1);
+        assert_eq!(table.all().expect("all")[0].name, "other");
+
+        table.delete_file_symbols("src/lib.rs").expect("delete");
+        assert!(table.find_by_name("").expect("all").is_empty());
+    }
+
+    #[test]
+    fn open_existing_reads_built_table() {
+        let dir = TempDir::new().expect("tempdir");
+        assert!(SymbolTable::open_existing(dir.path())
+            .expect("open missing")
+            .is_none());
+
+        {
+            let mut table = SymbolTable::open(SymbolTable::path_for(dir.path())).expect("open");
+            table
+                .replace_file_symbols("a.rs", "rust", &[symbol("alpha", SymbolKind::Function, 1)])
+                .expect("insert");
+        }
+
+        let table = SymbolTable::open_existing(dir.path())
+            .expect("open existing")
+            .expect("table present");
+        assert!(!table.is_new());
+        assert_eq!(table.find_by_name("alp").expect("find").len(), 1);
+    }
+
+    #[test]
+    fn exact_lookup_uses_name_index() {
+        let dir = TempDir::new().expect("tempdir");
+        let table = SymbolTable::open(dir.path().join("symbols.sqlite")).expect("open");
+        let plan: String = table
+            .conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT path FROM symbols WHERE name_lower = ?1",
+                params!["alpha"],
+                |row| row.get(3),
+            )
+            .expect("plan");
+        assert!(plan.contains("idx_symbols_name_lower"), "{}", plan);
+    }
+}
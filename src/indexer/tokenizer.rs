@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Code-aware tokenizer for the `content` and `symbols` fields.
+//!
+//! Every identifier is emitted whole, then split into its camelCase,
+//! snake_case and kebab-case sub-words at consecutive positions, so
+//! "index builder" matches `IndexBuilder` and "max results" matches
+//! `max_results` and `maxResults`. Tokens are lowercased and, for
+//! [`CODE_STEM_TOKENIZER`], stemmed.
+//!
+//! Queries are analyzed the same way minus the whole identifier, so a query
+//! identifier also matches longer ones (`expand_me` in `expand_me_too`).
+
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, Stemmer, TextAnalyzer, Token, TokenStream, Tokenizer,
+    TokenizerManager,
+};
+use tantivy::Index;
+
+/// Analyzer name for identifiers and their sub-words
+pub const CODE_TOKENIZER: &str = "code";
+/// [`CODE_TOKENIZER`] followed by English stemming
+pub const CODE_STEM_TOKENIZER: &str = "code_stem";
+
+/// Tokens longer than this are dropped, as by tantivy's default analyzer
+const MAX_TOKEN_BYTES: usize = 40;
+
+/// Register the code analyzers on an opened or created index.
+///
+/// Analyzers are not persisted with the index, so every open must do this
+/// before writing or parsing queries.
+pub fn register(index: &Index) {
+    let tokenizers = index.tokenizers();
+    tokenizers.register(CODE_TOKENIZER, code_analyzer(true, false));
+    tokenizers.register(CODE_STEM_TOKENIZER, code_analyzer(true, true));
+}
+
+/// Tokenizers for parsing queries: tantivy's defaults plus the code
+/// analyzers without whole identifiers
+pub fn query_tokenizers() -> TokenizerManager {
+    let tokenizers = TokenizerManager::default();
+    tokenizers.register(CODE_TOKENIZER, code_analyzer(false, false));
+    tokenizers.register(CODE_STEM_TOKENIZER, code_analyzer(false, true));
+    tokenizers
+}
+
+/// Analyzer name for the `[index] stemming` setting
+pub fn tokenizer_name(stemming: bool) -> &'static str {
+    if stemming {
+        CODE_STEM_TOKENIZER
+    } else {
+        CODE_TOKENIZER
+    }
+}
+
+fn code_analyzer(whole_identifiers: bool, stemming: bool) -> TextAnalyzer {
+    let builder = TextAnalyzer::builder(CodeTokenizer { whole_identifiers })
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_BYTES))
+        .filter(LowerCaser);
+    if stemming {
+        builder.filter(Stemmer::new(Language::English)).build()
+    } else {
+        builder.build()
+    }
+}
+
+/// Splits text into identifiers plus their sub-words
+#[derive(Clone)]
+pub struct CodeTokenizer {
+    /// Emit `IndexBuilder` itself before `Index` and `Builder`
+    whole_identifiers: bool,
+}
+
+pub struct CodeTokenStream {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream {
+        CodeTokenStream {
+            tokens: tokenize(text, self.whole_identifiers),
+            next: 0,
+        }
+    }
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.next < self.tokens.len() {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.next - 1]
+    }
+}
+
+fn tokenize(text: &str, whole_identifiers: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if !is_identifier_char(c) {
+            chars.next();
+            continue;
+        }
+        let mut end = start;
+        while let Some(&(offset, c)) = chars.peek() {
+            if !is_identifier_char(c) {
+                break;
+            }
+            end = offset + c.len_utf8();
+            chars.next();
+        }
+
+        let words = sub_words(text, start, end);
+        let Some(&(first, _)) = words.first() else {
+            continue;
+        };
+        let last = words.last().map(|&(_, to)| to).unwrap_or(end);
+        if whole_identifiers && words.len() > 1 {
+            tokens.push(Token {
+                offset_from: first,
+                offset_to: last,
+                position,
+                text: text[first..last].to_string(),
+                position_length: words.len(),
+            });
+        }
+        for (idx, &(from, to)) in words.iter().enumerate() {
+            tokens.push(Token {
+                offset_from: from,
+                offset_to: to,
+                position: position + idx,
+                text: text[from..to].to_string(),
+                position_length: 1,
+            });
+        }
+        position += words.len();
+    }
+    tokens
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Byte ranges of the sub-words of `text[start..end]`: split on `_` and `-`,
+/// then at lower-to-upper (`maxResults`) and acronym (`HTTPServer`) boundaries
+fn sub_words(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let chars: Vec<(usize, char)> = text[start..end]
+        .char_indices()
+        .map(|(offset, c)| (start + offset, c))
+        .collect();
+    for (idx, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if let Some(from) = word_start.take() {
+                words.push((from, offset));
+            }
+            continue;
+        }
+        if let Some(from) = word_start {
+            let prev = chars[idx - 1].1;
+            let next_is_lower = chars.get(idx + 1).is_some_and(|&(_, n)| n.is_lowercase());
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next_is_lower));
+            if boundary {
+                words.push((from, offset));
+                word_start = Some(offset);
+            }
+        } else {
+            word_start = Some(offset);
+        }
+    }
+    if let Some(from) = word_start {
+        words.push((from, end));
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(stemming: bool, text: &str) -> Vec<(String, usize)> {
+        let mut analyzer = code_analyzer(true, stemming);
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        stream.process(&mut |token| tokens.push((token.text.clone(), token.position)));
+        tokens
+    }
+
+    fn texts(tokens: &[(String, usize)]) -> Vec<&str> {
+        tokens.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_identifiers_into_sub_words() {
+        assert_eq!(
+            analyze(false, "let b = IndexBuilder::new(max_results);"),
+            vec![
+                ("let".to_string(), 0),
+                ("b".to_string(), 1),
+                ("indexbuilder".to_string(), 2),
+                ("index".to_string(), 2),
+                ("builder".to_string(), 3),
+                ("new".to_string(), 4),
+                ("max_results".to_string(), 5),
+                ("max".to_string(), 5),
+                ("results".to_string(), 6),
+            ]
+        );
+        assert_eq!(
+            texts(&analyze(false, "parseHTTPServer2Config --dry-run _private")),
+            vec![
+                "parsehttpserver2config",
+                "parse",
+                "http",
+                "server2",
+                "config",
+                "dry-run",
+                "dry",
+                "run",
+                "private",
+            ]
+        );
+    }
+
+    #[test]
+    fn stems_sub_words_when_enabled() {
+        assert_eq!(
+            texts(&analyze(true, "runningTests")),
+            vec!["runningtest", "run", "test"]
+        );
+        assert_eq!(texts(&analyze(false, "a - b")), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn queries_omit_whole_identifiers() {
+        let mut analyzer = query_tokenizers().get(CODE_TOKENIZER).expect("code");
+        let mut stream = analyzer.token_stream("IndexBuilder max");
+        let mut tokens = Vec::new();
+        stream.process(&mut |token| tokens.push((token.text.clone(), token.position)));
+        assert_eq!(
+            tokens,
+            vec![
+                ("index".to_string(), 0),
+                ("builder".to_string(), 1),
+                ("max".to_string(), 2),
+            ]
+        );
+    }
+}
@@ -2,13 +2,20 @@
 
 //! cgrep - Local semantic code search library
 //!
-//! Shared modules for the cgrep CLI tool.
+//! Shared modules for the cgrep CLI tool. Embedders should start at [`api`].
 
+pub mod api;
 pub mod cache;
 pub mod config;
+pub mod daemon;
 pub mod embedding;
 pub mod errors;
 pub mod filters;
 pub mod hybrid;
+pub mod indexer;
 pub mod output;
+pub mod parser;
+pub mod query;
+pub mod session;
+pub mod tokens;
 pub mod utils;
@@ -0,0 +1,840 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Language Server Protocol front-end.
+//!
+//! `cgrep lsp` answers navigation requests from the symbol table, the
+//! tree-sitter extractors and the tantivy index, so every language in the
+//! registry gets go-to-definition, references, symbols and incoming calls
+//! without a dedicated language server. Messages use the LSP base protocol
+//! (`Content-Length` framed JSON-RPC) on stdin/stdout.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use cgrep::config::Config;
+use cgrep::indexer::scanner::detect_language;
+use cgrep::parser::languages::LANGUAGES;
+use cgrep::parser::references::ReferenceKind;
+use cgrep::parser::symbols::SymbolExtractor;
+use cgrep::query::definition::{self, DefinitionResult};
+use cgrep::query::index_filter::preload_index;
+use cgrep::query::{callers, references, symbols};
+use cgrep::utils::get_root_with_index;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// Upper bound on `workspace/symbol` and `textDocument/references` results.
+const MAX_LOCATIONS: usize = 500;
+
+/// `SymbolKind` values from the LSP specification.
+fn symbol_kind(kind: &str) -> u32 {
+    match kind {
+        "module" => 2,
+        "class" => 5,
+        "method" => 6,
+        "property" => 7,
+        "enum" => 10,
+        "interface" | "trait" => 11,
+        "function" => 12,
+        "constant" => 14,
+        "struct" => 23,
+        "type" => 26,
+        _ => 13,
+    }
+}
+
+/// Read one `Content-Length` framed message; `None` at end of input.
+fn read_message<R: BufRead>(input: &mut R) -> Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse().context("Invalid Content-Length")?);
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length.unwrap_or_default()];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n", body.len())?;
+    output.write_all(&body)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// `file://` URI for an absolute path.
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Local path of a `file://` URI.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // Drop the authority (usually empty or `localhost`)
+    let rest = &rest[rest.find('/')?..];
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut iter = rest.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let decoded = String::from_utf8(bytes).ok()?;
+    // `/C:/dir` on Windows
+    if cfg!(windows) && decoded.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&decoded[1..]));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+/// UTF-16 column of a byte offset within a line.
+fn utf16_column(line: &str, byte: usize) -> usize {
+    let mut byte = byte.min(line.len());
+    while !line.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    line[..byte].encode_utf16().count()
+}
+
+/// Byte offset of a UTF-16 column within a line.
+fn byte_offset(line: &str, column: usize) -> usize {
+    let mut units = 0;
+    for (offset, ch) in line.char_indices() {
+        if units >= column {
+            return offset;
+        }
+        units += ch.len_utf16();
+    }
+    line.len()
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+/// Identifier under (or immediately before) the cursor.
+fn word_at(text: &str, line: usize, character: usize) -> Option<String> {
+    let line = text.lines().nth(line)?;
+    let cursor = byte_offset(line, character);
+    let start = line[..cursor]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_identifier_char(*ch))
+        .last()
+        .map_or(cursor, |(offset, _)| offset);
+    let end = line[cursor..]
+        .char_indices()
+        .find(|(_, ch)| !is_identifier_char(*ch))
+        .map_or(line.len(), |(offset, _)| cursor + offset);
+    (start < end).then(|| line[start..end].to_string())
+}
+
+fn position(line: usize, character: usize) -> Value {
+    json!({"line": line, "character": character})
+}
+
+/// Request handling error carrying a JSON-RPC error code.
+struct RequestError {
+    code: i64,
+    message: String,
+}
+
+impl From<anyhow::Error> for RequestError {
+    fn from(err: anyhow::Error) -> Self {
+        Self {
+            code: INTERNAL_ERROR,
+            message: format!("{:#}", err),
+        }
+    }
+}
+
+/// Navigation server for one workspace.
+pub struct LspServer {
+    root: PathBuf,
+    initialized: bool,
+    shutdown_requested: bool,
+    /// Text of open documents by URI; unsaved edits win over the file on disk
+    documents: HashMap<String, String>,
+    /// File contents read while building locations for one request
+    file_cache: HashMap<PathBuf, Option<String>>,
+}
+
+impl LspServer {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            initialized: false,
+            shutdown_requested: false,
+            documents: HashMap::new(),
+            file_cache: HashMap::new(),
+        }
+    }
+
+    /// Serve framed messages until `exit` or end of input.
+    pub fn serve<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            if message.get("method").and_then(Value::as_str) == Some("exit") {
+                break;
+            }
+            if let Some(response) = self.handle(&message) {
+                write_message(&mut output, &response)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one message; notifications get no response.
+    fn handle(&mut self, message: &Value) -> Option<Value> {
+        let method = message.get("method").and_then(Value::as_str)?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let Some(id) = message.get("id").cloned() else {
+            self.notification(method, &params);
+            return None;
+        };
+
+        self.file_cache.clear();
+        let result = if method == "initialize" {
+            Ok(self.initialize(&params))
+        } else if !self.initialized {
+            Err(RequestError {
+                code: SERVER_NOT_INITIALIZED,
+                message: "Server not initialized".to_string(),
+            })
+        } else {
+            self.request(method, &params)
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": err.code, "message": err.message},
+            }),
+        })
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                if let Some(text) = params.pointer("/textDocument/text").and_then(Value::as_str) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // Full document sync: the last change holds the whole text
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Value::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Value::as_str);
+                if let Some(text) = text {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let workspace = params
+            .get("rootUri")
+            .and_then(Value::as_str)
+            .or_else(|| {
+                params
+                    .pointer("/workspaceFolders/0/uri")
+                    .and_then(Value::as_str)
+            })
+            .and_then(uri_to_path)
+            .or_else(|| {
+                params
+                    .get("rootPath")
+                    .and_then(Value::as_str)
+                    .map(PathBuf::from)
+            });
+        if let Some(workspace) = workspace {
+            self.root = get_root_with_index(workspace);
+        }
+        preload_index(&self.root);
+        self.initialized = true;
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": {"openClose": true, "change": 1},
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "documentSymbolProvider": true,
+                "workspaceSymbolProvider": true,
+                "callHierarchyProvider": true,
+            },
+            "serverInfo": {"name": "cgrep", "version": env!("CARGO_PKG_VERSION")},
+        })
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, RequestError> {
+        match method {
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => Ok(self.definition(params)?),
+            "textDocument/references" => Ok(self.references(params)?),
+            "textDocument/documentSymbol" => Ok(self.document_symbols(params)?),
+            "workspace/symbol" => Ok(self.workspace_symbols(params)?),
+            "textDocument/prepareCallHierarchy" => Ok(self.prepare_call_hierarchy(params)?),
+            "callHierarchy/incomingCalls" => Ok(self.incoming_calls(params)?),
+            // Only incoming calls are indexed; report no outgoing edges
+            "callHierarchy/outgoingCalls" => Ok(json!([])),
+            _ => Err(RequestError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Method not found: {}", method),
+            }),
+        }
+    }
+
+    /// Identifier at `params.position` in `params.textDocument`.
+    fn word_at_position(&mut self, params: &Value) -> Result<Option<String>> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing textDocument.uri"))?;
+        let line = params.pointer("/position/line").and_then(Value::as_u64);
+        let character = params
+            .pointer("/position/character")
+            .and_then(Value::as_u64);
+        let (Some(line), Some(character)) = (line, character) else {
+            bail!("missing position");
+        };
+        let Some(text) = self.document_text(uri) else {
+            return Ok(None);
+        };
+        Ok(word_at(&text, line as usize, character as usize))
+    }
+
+    fn document_text(&mut self, uri: &str) -> Option<String> {
+        if let Some(text) = self.documents.get(uri) {
+            return Some(text.clone());
+        }
+        let path = uri_to_path(uri)?;
+        self.file_text(&path)
+    }
+
+    fn file_text(&mut self, path: &Path) -> Option<String> {
+        if let Some(text) = self.documents.get(&path_to_uri(path)) {
+            return Some(text.clone());
+        }
+        self.file_cache
+            .entry(path.to_path_buf())
+            .or_insert_with(|| std::fs::read_to_string(path).ok())
+            .clone()
+    }
+
+    /// Range of `len` bytes at a 1-based line and byte column of a file.
+    fn range(&mut self, path: &Path, line: usize, column: usize, len: usize) -> Value {
+        let line_index = line.saturating_sub(1);
+        let text = self.file_text(path).unwrap_or_default();
+        let line_text = text.lines().nth(line_index).unwrap_or_default();
+        let start = column.saturating_sub(1);
+        json!({
+            "start": position(line_index, utf16_column(line_text, start)),
+            "end": position(line_index, utf16_column(line_text, start + len)),
+        })
+    }
+
+    /// Range of a symbol's name on its definition line.
+    ///
+    /// Symbol columns point at the start of the declaration (`pub fn ...`), so
+    /// the name is searched for from there.
+    fn name_range(&mut self, path: &Path, line: usize, column: usize, name: &str) -> Value {
+        let text = self.file_text(path).unwrap_or_default();
+        let line_text = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+        let start = column.saturating_sub(1).min(line_text.len());
+        let column = line_text
+            .get(start..)
+            .and_then(|rest| rest.find(name))
+            .map_or(column, |offset| start + offset + 1);
+        self.range(path, line, column, name.len())
+    }
+
+    fn definition_location(&mut self, result: &DefinitionResult) -> Value {
+        let path = self.root.join(&result.path);
+        json!({
+            "uri": path_to_uri(&path),
+            "range": self.name_range(&path, result.line, result.column, &result.name),
+        })
+    }
+
+    fn definition(&mut self, params: &Value) -> Result<Value> {
+        let Some(word) = self.word_at_position(params)? else {
+            return Ok(Value::Null);
+        };
+        let results = definition::find(&self.root, &word)?;
+        let exact: Vec<&DefinitionResult> = results.iter().filter(|r| r.name == word).collect();
+        let locations: Vec<Value> = exact
+            .into_iter()
+            .map(|result| self.definition_location(result))
+            .collect();
+        Ok(Value::Array(locations))
+    }
+
+    fn references(&mut self, params: &Value) -> Result<Value> {
+        let Some(word) = self.word_at_position(params)? else {
+            return Ok(Value::Null);
+        };
+        let include_declaration = params
+            .pointer("/context/includeDeclaration")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        let results = references::find(&self.root, &word, MAX_LOCATIONS, None, &[], true)?;
+        let locations: Vec<Value> = results
+            .iter()
+            .filter(|r| include_declaration || r.kind != ReferenceKind::Definition)
+            .map(|r| {
+                let path = self.root.join(&r.path);
+                json!({
+                    "uri": path_to_uri(&path),
+                    "range": self.range(&path, r.line, r.column, word.len()),
+                })
+            })
+            .collect();
+        Ok(Value::Array(locations))
+    }
+
+    fn document_symbols(&mut self, params: &Value) -> Result<Value> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing textDocument.uri"))?
+            .to_string();
+        let Some(path) = uri_to_path(&uri) else {
+            return Ok(Value::Null);
+        };
+        let Some(language) = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(detect_language)
+            .filter(|lang| LANGUAGES.get(lang).is_some())
+        else {
+            return Ok(json!([]));
+        };
+        let Some(text) = self.document_text(&uri) else {
+            return Ok(Value::Null);
+        };
+
+        let rel_path = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+        let extracted = SymbolExtractor::new().extract_for_path(&text, &language, &rel_path)?;
+        let symbols: Vec<Value> = extracted
+            .iter()
+            .map(|symbol| {
+                let end_line = symbol.end_line.max(symbol.line);
+                let end_text = text.lines().nth(end_line - 1).unwrap_or_default();
+                let mut info = json!({
+                    "name": symbol.name,
+                    "kind": symbol_kind(&symbol.kind.to_string()),
+                    "location": {
+                        "uri": uri,
+                        "range": {
+                            "start": self.name_range(&path, symbol.line, symbol.column, &symbol.name)["start"],
+                            "end": position(end_line - 1, utf16_column(end_text, end_text.len())),
+                        },
+                    },
+                });
+                if let Some(scope) = &symbol.scope {
+                    info["containerName"] = json!(scope);
+                }
+                info
+            })
+            .collect();
+        Ok(Value::Array(symbols))
+    }
+
+    fn workspace_symbols(&mut self, params: &Value) -> Result<Value> {
+        let query = params.get("query").and_then(Value::as_str).unwrap_or("");
+        if query.trim().is_empty() {
+            return Ok(json!([]));
+        }
+        let config = Config::load_for_dir(&self.root);
+        let (results, _) = symbols::find(
+            &self.root,
+            &config,
+            query,
+            &symbols::SymbolFilters::default(),
+        )?;
+        let symbols: Vec<Value> = results
+            .iter()
+            .take(MAX_LOCATIONS)
+            .map(|symbol| {
+                let path = self.root.join(&symbol.path);
+                let mut info = json!({
+                    "name": symbol.name,
+                    "kind": symbol_kind(&symbol.kind),
+                    "location": {
+                        "uri": path_to_uri(&path),
+                        "range": self.name_range(&path, symbol.line, 1, &symbol.name),
+                    },
+                });
+                if let Some((container, _)) = symbol
+                    .qualified_name
+                    .as_deref()
+                    .and_then(|q| q.rsplit_once(['.', ':']))
+                {
+                    info["containerName"] = json!(container.trim_end_matches(':'));
+                }
+                info
+            })
+            .collect();
+        Ok(Value::Array(symbols))
+    }
+
+    fn call_hierarchy_item(&mut self, result: &DefinitionResult) -> Value {
+        let path = self.root.join(&result.path);
+        let range = self.name_range(&path, result.line, result.column, &result.name);
+        let mut item = json!({
+            "name": result.name,
+            "kind": symbol_kind(&result.kind),
+            "uri": path_to_uri(&path),
+            "range": range,
+            "selectionRange": range,
+            "data": {"name": result.name},
+        });
+        if let Some(qualified) = &result.qualified_name {
+            item["detail"] = json!(qualified);
+        }
+        item
+    }
+
+    fn prepare_call_hierarchy(&mut self, params: &Value) -> Result<Value> {
+        let Some(word) = self.word_at_position(params)? else {
+            return Ok(Value::Null);
+        };
+        let results = definition::find(&self.root, &word)?;
+        let items: Vec<Value> = results
+            .iter()
+            .filter(|r| r.name == word)
+            .map(|result| self.call_hierarchy_item(result))
+            .collect();
+        Ok(if items.is_empty() {
+            Value::Null
+        } else {
+            Value::Array(items)
+        })
+    }
+
+    fn incoming_calls(&mut self, params: &Value) -> Result<Value> {
+        let item = params
+            .get("item")
+            .ok_or_else(|| anyhow!("missing call hierarchy item"))?;
+        let name = item
+            .pointer("/data/name")
+            .or_else(|| item.get("name"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("call hierarchy item has no name"))?;
+
+        // Call sites grouped by file and enclosing function
+        let mut groups: BTreeMap<(String, Option<String>), Vec<callers::CallerResult>> =
+            BTreeMap::new();
+        for call in callers::find(&self.root, name)? {
+            groups
+                .entry((call.path.clone(), call.caller.clone()))
+                .or_default()
+                .push(call);
+        }
+
+        let mut incoming = Vec::new();
+        for ((rel_path, caller), calls) in groups {
+            let path = self.root.join(&rel_path);
+            let first_line = calls[0].line;
+            let from = match caller.as_deref() {
+                Some(caller) => {
+                    // The enclosing definition closest above the first call
+                    let definition = definition::find(&self.root, caller)?
+                        .into_iter()
+                        .filter(|d| d.path == rel_path && d.name == caller)
+                        .filter(|d| d.line <= first_line)
+                        .max_by_key(|d| d.line);
+                    match definition {
+                        Some(definition) => self.call_hierarchy_item(&definition),
+                        None => {
+                            let range = self.range(&path, first_line, 1, 0);
+                            json!({
+                                "name": caller,
+                                "kind": symbol_kind("function"),
+                                "uri": path_to_uri(&path),
+                                "range": range,
+                                "selectionRange": range,
+                                "data": {"name": caller},
+                            })
+                        }
+                    }
+                }
+                None => {
+                    // Top-level code: attribute the calls to the file
+                    let range = self.range(&path, first_line, 1, 0);
+                    json!({
+                        "name": rel_path,
+                        "kind": 1,
+                        "uri": path_to_uri(&path),
+                        "range": range,
+                        "selectionRange": range,
+                    })
+                }
+            };
+            let from_ranges: Vec<Value> = calls
+                .iter()
+                .map(|call| self.range(&path, call.line, call.column, name.len()))
+                .collect();
+            incoming.push(json!({"from": from, "fromRanges": from_ranges}));
+        }
+        Ok(Value::Array(incoming))
+    }
+}
+
+/// Run the language server on stdin/stdout
+pub fn run(path: Option<&str>) -> Result<()> {
+    let start = match path {
+        Some(path) => PathBuf::from(path).canonicalize()?,
+        None => std::env::current_dir()?,
+    };
+    let mut server = LspServer::new(get_root_with_index(start));
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    server.serve(stdin.lock(), stdout.lock())?;
+    if !server.shutdown_requested {
+        // The protocol asks for exit code 1 when `exit` comes without `shutdown`
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    const SOURCE: &str = "\
+pub struct Config;
+
+pub fn parse_config() -> Config {
+    Config
+}
+
+pub fn load() {
+    let _ = parse_config();
+}
+";
+
+    fn framed(messages: &[Value]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).expect("frame");
+        }
+        input
+    }
+
+    fn session(root: &Path, requests: &[Value]) -> Vec<Value> {
+        let mut messages = vec![
+            json!({"jsonrpc": "2.0", "id": 0, "method": "initialize",
+                   "params": {"rootUri": path_to_uri(root), "capabilities": {}}}),
+            json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        ];
+        messages.extend_from_slice(requests);
+        messages.push(json!({"jsonrpc": "2.0", "id": 99, "method": "shutdown"}));
+        messages.push(json!({"jsonrpc": "2.0", "method": "exit"}));
+
+        let mut output = Vec::new();
+        let mut server = LspServer::new(PathBuf::from("."));
+        server
+            .serve(Cursor::new(framed(&messages)), &mut output)
+            .expect("serve");
+        assert!(server.shutdown_requested);
+
+        let mut reader = Cursor::new(output);
+        let mut responses = Vec::new();
+        while let Some(response) = read_message(&mut reader).expect("read") {
+            responses.push(response);
+        }
+        responses
+    }
+
+    #[test]
+    fn uri_and_position_conversions() {
+        let path = Path::new("/tmp/my dir/ü.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20dir/%C3%BC.rs");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+
+        let line = "let ü = foo_bar(x);";
+        assert_eq!(utf16_column(line, line.find("foo").unwrap()), 8);
+        assert_eq!(byte_offset(line, 8), line.find("foo").unwrap());
+        assert_eq!(word_at(line, 0, 10).as_deref(), Some("foo_bar"));
+        assert_eq!(word_at(line, 0, 15).as_deref(), Some("foo_bar"));
+        assert_eq!(word_at(line, 0, 6), None);
+    }
+
+    #[test]
+    fn navigation_requests_resolve_locations() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path().canonicalize().expect("canonical root");
+        let file = root.join("lib.rs");
+        std::fs::write(&file, SOURCE).expect("write");
+        let uri = path_to_uri(&file);
+        // Cursor on `parse_config` in `load`
+        let at_call =
+            json!({"textDocument": {"uri": uri}, "position": {"line": 7, "character": 14}});
+
+        let responses = session(
+            &root,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/definition", "params": at_call}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/references",
+                       "params": {"textDocument": {"uri": uri}, "position": {"line": 7, "character": 14},
+                                  "context": {"includeDeclaration": false}}}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/documentSymbol",
+                       "params": {"textDocument": {"uri": uri}}}),
+                json!({"jsonrpc": "2.0", "id": 4, "method": "workspace/symbol", "params": {"query": "load"}}),
+                json!({"jsonrpc": "2.0", "id": 5, "method": "textDocument/hover", "params": at_call}),
+            ],
+        );
+
+        assert!(
+            responses[0]["result"]["capabilities"]["callHierarchyProvider"]
+                .as_bool()
+                .unwrap()
+        );
+
+        let definition = &responses[1]["result"][0];
+        assert_eq!(definition["uri"], uri.as_str());
+        assert_eq!(
+            definition["range"]["start"],
+            json!({"line": 2, "character": 7})
+        );
+
+        let references = responses[2]["result"].as_array().expect("references");
+        assert_eq!(references.len(), 1);
+        assert_eq!(
+            references[0]["range"],
+            json!({"start": {"line": 7, "character": 12}, "end": {"line": 7, "character": 24}})
+        );
+
+        let names: Vec<&str> = responses[3]["result"]
+            .as_array()
+            .expect("symbols")
+            .iter()
+            .filter_map(|s| s["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["Config", "parse_config", "load"]);
+
+        assert_eq!(responses[4]["result"][0]["name"], "load");
+        assert_eq!(responses[4]["result"][0]["kind"], 12);
+        assert_eq!(responses[5]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn incoming_calls_group_by_caller() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path().canonicalize().expect("canonical root");
+        let file = root.join("lib.rs");
+        std::fs::write(&file, SOURCE).expect("write");
+        let uri = path_to_uri(&file);
+
+        let responses = session(
+            &root,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/prepareCallHierarchy",
+                     "params": {"textDocument": {"uri": uri}, "position": {"line": 2, "character": 9}}}),
+            ],
+        );
+        let item = responses[1]["result"][0].clone();
+        assert_eq!(item["name"], "parse_config");
+
+        let responses = session(
+            &root,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "callHierarchy/incomingCalls",
+                     "params": {"item": item}}),
+            ],
+        );
+        let incoming = responses[1]["result"].as_array().expect("incoming");
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0]["from"]["name"], "load");
+        assert_eq!(incoming[0]["fromRanges"][0]["start"]["line"], 7);
+    }
+
+    #[test]
+    fn methods_resolve_for_definition_and_call_hierarchy() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path().canonicalize().expect("canonical root");
+        let file = root.join("Outer.java");
+        std::fs::write(
+            &file,
+            "class Outer {\n    void render() {}\n\n    void load() {\n        render();\n    }\n}\n",
+        )
+        .expect("write");
+        let uri = path_to_uri(&file);
+
+        let responses = session(
+            &root,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/definition",
+                     "params": {"textDocument": {"uri": uri}, "position": {"line": 4, "character": 10}}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/prepareCallHierarchy",
+                     "params": {"textDocument": {"uri": uri}, "position": {"line": 1, "character": 10}}}),
+            ],
+        );
+        assert_eq!(
+            responses[1]["result"][0]["range"]["start"],
+            json!({"line": 1, "character": 9})
+        );
+        let item = &responses[2]["result"][0];
+        assert_eq!(item["name"], "render");
+        assert_eq!(item["kind"], 6);
+    }
+}
@@ -5,13 +5,14 @@
 //! A high-performance, AST-aware search tool combining tree-sitter
 //! for code structure analysis and tantivy for BM25 text ranking.
 
+mod batch;
 mod cli;
-mod indexer;
 mod install;
-mod parser;
-mod query;
+mod lsp;
+mod mcp;
 
 use anyhow::Result;
+use cgrep::{daemon, indexer, query};
 use clap::{CommandFactory, Parser};
 use clap_complete::generate;
 use cli::{AgentProvider, Cli, CliBudgetPreset, Commands};
@@ -25,14 +26,6 @@ fn config_output_to_cli(format: cgrep::config::ConfigOutputFormat) -> cli::Outpu
     }
 }
 
-fn config_search_mode_to_hybrid(mode: cgrep::config::SearchMode) -> cgrep::hybrid::SearchMode {
-    match mode {
-        cgrep::config::SearchMode::Keyword => cgrep::hybrid::SearchMode::Keyword,
-        cgrep::config::SearchMode::Semantic => cgrep::hybrid::SearchMode::Semantic,
-        cgrep::config::SearchMode::Hybrid => cgrep::hybrid::SearchMode::Hybrid,
-    }
-}
-
 fn cli_search_mode_to_hybrid(mode: cli::CliSearchMode) -> cgrep::hybrid::SearchMode {
     match mode {
         cli::CliSearchMode::Keyword => cgrep::hybrid::SearchMode::Keyword,
@@ -81,6 +74,46 @@ fn budget_defaults(preset: Option<CliBudgetPreset>) -> BudgetDefaults {
     }
 }
 
+/// Search options behind `agent locate`: keyword mode, budgeted json2 output
+/// and the result cache, so repeated lookups stay cheap
+fn agent_locate_options(
+    query: String,
+    path: Option<String>,
+    changed: Option<String>,
+    limit: Option<usize>,
+    mode: Option<cli::CliSearchMode>,
+    budget: Option<CliBudgetPreset>,
+    max_tokens: Option<usize>,
+) -> query::search::SearchOptions {
+    let config = path
+        .as_deref()
+        .map(cgrep::config::Config::load_for_dir)
+        .unwrap_or_else(cgrep::config::Config::load);
+    let defaults = budget_defaults(Some(budget.unwrap_or(CliBudgetPreset::Balanced)));
+
+    query::search::SearchOptions {
+        query,
+        path,
+        max_results: limit.or(config.max_results).unwrap_or(20),
+        changed,
+        search_mode: Some(
+            mode.map(cli_search_mode_to_hybrid)
+                .unwrap_or(cgrep::hybrid::SearchMode::Keyword),
+        ),
+        context_pack: Some(2),
+        use_cache: true,
+        cache_ttl: Some(config.cache.ttl_ms()),
+        max_chars_per_snippet: defaults.max_chars_per_snippet,
+        max_total_chars: defaults.max_total_chars.filter(|_| max_tokens.is_none()),
+        max_tokens,
+        max_context_chars: defaults.max_context_chars,
+        dedupe_context: true,
+        path_alias: true,
+        suppress_boilerplate: true,
+        ..Default::default()
+    }
+}
+
 fn print_search_advanced_help() {
     println!("Advanced search options:");
     println!("  --no-index                     Force scan mode (no index)");
@@ -141,11 +174,14 @@ fn main() -> Result<()> {
             path,
             limit,
             context,
+            scope,
             file_type,
             glob,
             exclude,
             changed,
             budget,
+            max_tokens,
+            session,
             profile,
             quiet,
             regex,
@@ -219,7 +255,10 @@ fn main() -> Result<()> {
             let effective_cache_ttl = cache_ttl.or(Some(config.cache.ttl_ms()));
             let effective_max_chars_per_snippet =
                 max_chars_per_snippet.or(budget_defaults.max_chars_per_snippet);
-            let effective_max_total_chars = max_total_chars.or(budget_defaults.max_total_chars);
+            // A token budget replaces the preset's character total
+            let effective_max_total_chars = max_total_chars.or(budget_defaults
+                .max_total_chars
+                .filter(|_| max_tokens.is_none()));
             let effective_max_context_chars =
                 max_context_chars.or(budget_defaults.max_context_chars);
             let effective_dedupe_context = dedupe_context || budget_defaults.dedupe_context;
@@ -238,11 +277,8 @@ fn main() -> Result<()> {
             }
 
             // Determine effective search mode from flags
-            let profile_mode = profile_config
-                .as_ref()
-                .and_then(|p| p.mode)
-                .map(config_search_mode_to_hybrid);
-            let config_mode = config.search.default_mode.map(config_search_mode_to_hybrid);
+            let profile_mode = profile_config.as_ref().and_then(|p| p.mode).map(Into::into);
+            let config_mode = config.search.default_mode.map(Into::into);
             let effective_mode = if hybrid {
                 Some(cgrep::hybrid::SearchMode::Hybrid)
             } else if semantic {
@@ -259,33 +295,34 @@ fn main() -> Result<()> {
                     .or(config_mode)
             };
 
-            query::search::run(
-                &query,
-                path.as_deref(),
-                effective_max_results,
-                effective_context,
-                file_type.as_deref(),
-                glob.as_deref(),
-                exclude.as_deref(),
-                changed.as_deref(),
-                quiet,
+            let options = query::search::SearchOptions {
+                query,
+                path,
+                max_results: effective_max_results,
+                context: effective_context,
+                scope: scope.unwrap_or_default(),
+                file_type,
+                glob,
+                exclude,
+                changed,
                 fuzzy,
                 no_index,
                 regex,
                 case_sensitive,
-                effective_format,
-                compact,
-                effective_mode,
-                effective_context_pack,
-                effective_agent_cache,
-                effective_cache_ttl,
-                effective_max_chars_per_snippet,
-                effective_max_total_chars,
-                effective_max_context_chars,
-                effective_dedupe_context,
-                effective_path_alias,
-                effective_suppress_boilerplate,
-            )?;
+                search_mode: effective_mode,
+                context_pack: effective_context_pack,
+                use_cache: effective_agent_cache,
+                cache_ttl: effective_cache_ttl,
+                max_chars_per_snippet: effective_max_chars_per_snippet,
+                max_total_chars: effective_max_total_chars,
+                max_tokens,
+                max_context_chars: effective_max_context_chars,
+                dedupe_context: effective_dedupe_context,
+                path_alias: effective_path_alias,
+                suppress_boilerplate: effective_suppress_boilerplate,
+                session,
+            };
+            query::search::run(&options, quiet, effective_format, compact)?;
         }
         Commands::Agent { command } => match command {
             cli::AgentCommands::Locate {
@@ -295,47 +332,52 @@ fn main() -> Result<()> {
                 limit,
                 mode,
                 budget,
+                max_tokens,
+                session,
             } => {
-                let config = path
-                    .as_deref()
-                    .map(cgrep::config::Config::load_for_dir)
-                    .unwrap_or_else(cgrep::config::Config::load);
-                let effective_limit = limit.or(config.max_results).unwrap_or(20);
-                let defaults = budget_defaults(Some(budget.unwrap_or(CliBudgetPreset::Balanced)));
-                let effective_mode = mode
-                    .map(cli_search_mode_to_hybrid)
-                    .or(Some(cgrep::hybrid::SearchMode::Keyword));
-
-                query::search::run(
-                    &query,
+                let mut options =
+                    agent_locate_options(query, path, changed, limit, mode, budget, max_tokens);
+                options.session = session;
+                query::search::run(&options, true, cli::OutputFormat::Json2, compact)?;
+            }
+            cli::AgentCommands::Expand {
+                ids,
+                path,
+                context,
+                scope,
+                session,
+            } => {
+                query::agent::run_expand(
+                    &ids,
                     path.as_deref(),
-                    effective_limit,
-                    0,
-                    None,
-                    None,
-                    None,
-                    changed.as_deref(),
-                    true,
-                    false,
-                    false,
-                    false,
-                    false,
-                    cli::OutputFormat::Json2,
+                    context.unwrap_or(8),
+                    scope.unwrap_or_default(),
+                    session.as_deref(),
                     compact,
-                    effective_mode,
-                    Some(2),
-                    true,
-                    Some(config.cache.ttl_ms()),
-                    defaults.max_chars_per_snippet,
-                    defaults.max_total_chars,
-                    defaults.max_context_chars,
-                    true,
-                    true,
-                    true,
                 )?;
             }
-            cli::AgentCommands::Expand { ids, path, context } => {
-                query::agent::run_expand(&ids, path.as_deref(), context.unwrap_or(8), compact)?;
+            cli::AgentCommands::Context {
+                task,
+                path,
+                changed,
+                limit,
+                mode,
+                budget,
+            } => {
+                let options = agent_locate_options(
+                    task,
+                    path,
+                    changed,
+                    limit,
+                    mode,
+                    Some(CliBudgetPreset::Off),
+                    None,
+                );
+                query::context::run(
+                    &options,
+                    budget.unwrap_or(query::context::DEFAULT_BUDGET_TOKENS),
+                    compact,
+                )?;
             }
             cli::AgentCommands::Install { provider } => {
                 install_for_provider(provider)?;
@@ -373,17 +415,38 @@ fn main() -> Result<()> {
         Commands::Callers { function } => {
             query::callers::run(&function, global_format, compact)?;
         }
+        Commands::Callgraph {
+            function,
+            depth,
+            direction,
+            max_nodes,
+            graph,
+        } => {
+            query::callgraph::run(
+                &function,
+                depth,
+                direction,
+                max_nodes,
+                graph,
+                global_format,
+                compact,
+            )?;
+        }
         Commands::References {
             name,
             path,
             max_results,
             changed,
+            kinds,
+            exclude_comments,
         } => {
             query::references::run(
                 &name,
                 path.as_deref(),
                 max_results,
                 changed.as_deref(),
+                &kinds.into_iter().map(Into::into).collect::<Vec<_>>(),
+                exclude_comments,
                 global_format,
                 compact,
             )?;
@@ -391,6 +454,37 @@ fn main() -> Result<()> {
         Commands::Dependents { file } => {
             query::dependents::run(&file, global_format, compact)?;
         }
+        Commands::Deps {
+            file,
+            reverse,
+            transitive,
+            graph,
+        } => {
+            query::deps::run(
+                file.as_deref(),
+                reverse,
+                transitive,
+                graph,
+                global_format,
+                compact,
+            )?;
+        }
+        Commands::Map {
+            path,
+            budget,
+            markdown,
+        } => {
+            query::map::run(
+                path.as_deref(),
+                budget.unwrap_or(query::map::DEFAULT_BUDGET_TOKENS),
+                markdown,
+                global_format,
+                compact,
+            )?;
+        }
+        Commands::Outline { files } => {
+            query::outline::run(&files, global_format, compact)?;
+        }
         Commands::Index {
             path,
             force,
@@ -411,6 +505,30 @@ fn main() -> Result<()> {
         Commands::Watch { path, debounce } => {
             indexer::watch::run(path.as_deref(), Some(debounce))?;
         }
+        Commands::Daemon { command } => match command {
+            cli::DaemonCommands::Start {
+                path,
+                no_watch,
+                debounce,
+            } => {
+                daemon::run_start(path.as_deref(), (!no_watch).then_some(debounce))?;
+            }
+            cli::DaemonCommands::Stop { path } => {
+                daemon::run_stop(path.as_deref())?;
+            }
+            cli::DaemonCommands::Status { path } => {
+                daemon::run_status(path.as_deref(), global_format, compact)?;
+            }
+        },
+        Commands::Batch { path } => {
+            batch::run(path.as_deref())?;
+        }
+        Commands::Mcp { path } => {
+            mcp::run(path.as_deref())?;
+        }
+        Commands::Lsp { path } => {
+            lsp::run(path.as_deref())?;
+        }
 
         // Legacy installation commands (deprecated)
         Commands::InstallClaudeCode => {
@@ -0,0 +1,753 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Model Context Protocol server over stdio.
+//!
+//! `cgrep mcp` reads newline-delimited JSON-RPC 2.0 messages from stdin and
+//! answers on stdout, exposing the query commands as typed tools. The server
+//! stays up for the whole agent session, so the tantivy index is opened once
+//! (see [`preload_index`]) and every tool call reuses the warm reader.
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::cli::{CliBudgetPreset, CliReferenceKind, CliSearchMode, ContextScope, OutputFormat};
+use cgrep::config::Config;
+use cgrep::parser::references::ReferenceKind;
+use cgrep::query::callgraph::CallDirection;
+use cgrep::query::index_filter::preload_index;
+use cgrep::query::{
+    agent, callers, callgraph, context, definition, dependents, deps, map, outline, references,
+    search, symbols,
+};
+use cgrep::utils::get_root_with_index;
+
+/// Newest protocol revision this server implements.
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Revisions a client may ask for; anything else gets [`PROTOCOL_VERSION`].
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A tool advertised through `tools/list`.
+struct ToolSpec {
+    name: &'static str,
+    description: &'static str,
+    input_schema: Value,
+}
+
+fn budget_property() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["tight", "balanced", "full", "off"],
+        "description": "Output budget preset bounding snippet and payload size"
+    })
+}
+
+fn max_tokens_property() -> Value {
+    json!({
+        "type": "integer",
+        "minimum": 0,
+        "description": "Cap the payload at this many estimated tokens; replaces the preset's total character cap"
+    })
+}
+
+fn session_property() -> Value {
+    json!({
+        "type": "string",
+        "description": "Agent session ID; results this session already received come back under already_sent instead of repeating their text"
+    })
+}
+
+fn mode_property() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["keyword", "semantic", "hybrid"],
+        "description": "Ranking mode; semantic and hybrid need embeddings"
+    })
+}
+
+fn scope_property() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["lines", "symbol", "signature"],
+        "description": "Return the enclosing symbol or its signature instead of context lines"
+    })
+}
+
+fn tool_specs() -> Vec<ToolSpec> {
+    vec![
+        ToolSpec {
+            name: "search",
+            description: "Full-text code search with BM25 ranking (optionally semantic or hybrid). Returns ranked snippets with stable result IDs.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Search query; supports lang:, path:, kind:, sym:, \"phrases\", -exclusions and OR"},
+                    "path": {"type": "string", "description": "Directory to search, relative to the repository root"},
+                    "limit": {"type": "integer", "minimum": 1, "default": 20},
+                    "context": {"type": "integer", "minimum": 0, "default": 0, "description": "Context lines around each match"},
+                    "scope": scope_property(),
+                    "mode": mode_property(),
+                    "file_type": {"type": "string", "description": "File type filter (e.g. rust, ts)"},
+                    "glob": {"type": "string", "description": "Only search paths matching this glob"},
+                    "exclude": {"type": "string", "description": "Skip paths matching this glob"},
+                    "changed": {"type": "string", "description": "Only files changed since this git revision"},
+                    "regex": {"type": "boolean", "default": false},
+                    "case_sensitive": {"type": "boolean", "default": false},
+                    "budget": budget_property(),
+                    "max_tokens": max_tokens_property(),
+                    "session": session_property()
+                },
+                "required": ["query"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "agent_locate",
+            description: "Stage 1 of agent retrieval: locate candidate code regions with a budgeted, minimal payload. Follow up with agent_expand on the returned IDs.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Natural language or keywords; supports lang:, path:, kind:, sym:, \"phrases\", -exclusions and OR"},
+                    "path": {"type": "string", "description": "Directory to search, relative to the repository root"},
+                    "changed": {"type": "string", "description": "Only files changed since this git revision"},
+                    "limit": {"type": "integer", "minimum": 1, "default": 20},
+                    "mode": mode_property(),
+                    "budget": budget_property(),
+                    "max_tokens": max_tokens_property(),
+                    "session": session_property()
+                },
+                "required": ["query"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "agent_expand",
+            description: "Stage 2 of agent retrieval: expand result IDs from search or agent_locate into context windows.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "ids": {"type": "array", "items": {"type": "string"}, "minItems": 1},
+                    "context": {"type": "integer", "minimum": 0, "default": 8, "description": "Lines of context around each result"},
+                    "scope": scope_property(),
+                    "session": session_property()
+                },
+                "required": ["ids"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "agent_context",
+            description: "One-shot agent retrieval: locate hits for a task and return the best of them expanded to their enclosing symbols, packed into a token budget. Hits that did not fit are listed in meta.dropped for agent_expand.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "task": {"type": "string", "description": "Task or question (natural language or keywords)"},
+                    "path": {"type": "string", "description": "Directory to search, relative to the repository root"},
+                    "changed": {"type": "string", "description": "Only files changed since this git revision"},
+                    "limit": {"type": "integer", "minimum": 1, "default": 20, "description": "Candidate hits to consider"},
+                    "mode": mode_property(),
+                    "budget": {"type": "integer", "minimum": 0, "default": 4000, "description": "Token budget for the returned context"}
+                },
+                "required": ["task"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "symbols",
+            description: "Find symbols (functions, types, methods, ...) whose name contains the given text.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "kind": {"type": "string", "description": "Symbol kind filter (function, class, struct, ...)"},
+                    "lang": {"type": "string", "description": "Language filter (rust, python, ...)"},
+                    "file_type": {"type": "string"},
+                    "glob": {"type": "string"},
+                    "exclude": {"type": "string"},
+                    "changed": {"type": "string", "description": "Only files changed since this git revision"}
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "definition",
+            description: "Locate the definition of a symbol; accepts qualified names such as Type::method.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"}
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "references",
+            description: "Find occurrences of a symbol, classified as definition, read, write, import, comment or string.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "limit": {"type": "integer", "minimum": 1, "default": 50},
+                    "kinds": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["definition", "read", "write", "import", "comment", "string", "text"]
+                        }
+                    },
+                    "exclude_comments": {"type": "boolean", "default": false},
+                    "changed": {"type": "string", "description": "Only files changed since this git revision"}
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "callers",
+            description: "Find call sites of a function together with the enclosing caller.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "function": {"type": "string"}
+                },
+                "required": ["function"],
+                "additionalProperties": false
+            }),
+        },
+        ToolSpec {
+            name: "dependents",
+            description: "Find files that import the given file.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {"type": "string", "description": "Path relative to the repository root"}
+                },
+                "required": ["file"],
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+/// Typed accessors over a tool call's `arguments` object.
+struct Arguments<'a>(&'a Map<String, Value>);
+
+impl Arguments<'_> {
+    fn string(&self, key: &str) -> Result<Option<String>> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => bail!("`{}` must be a string", key),
+        }
+    }
+
+    fn required_string(&self, key: &str) -> Result<String> {
+        self.string(key)?
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| anyhow!("missing required argument `{}`", key))
+    }
+
+    fn usize(&self, key: &str) -> Result<Option<usize>> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .map(|n| Some(n as usize))
+                .ok_or_else(|| anyhow!("`{}` must be a non-negative integer", key)),
+        }
+    }
+
+    fn bool(&self, key: &str) -> Result<bool> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(false),
+            Some(Value::Bool(value)) => Ok(*value),
+            Some(_) => bail!("`{}` must be a boolean", key),
+        }
+    }
+
+    fn strings(&self, key: &str) -> Result<Vec<String>> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| anyhow!("`{}` must contain only strings", key))
+                })
+                .collect(),
+            Some(_) => bail!("`{}` must be an array of strings", key),
+        }
+    }
+
+    /// Parse a string argument into one of the CLI's value enums.
+    fn choice<T: ValueEnum>(&self, key: &str) -> Result<Option<T>> {
+        self.string(key)?
+            .map(|value| {
+                T::from_str(&value, true).map_err(|_| anyhow!("invalid `{}`: {}", key, value))
+            })
+            .transpose()
+    }
+}
+
+/// MCP server answering tool calls for one repository.
+pub struct McpServer {
+    root: PathBuf,
+    config: Config,
+}
+
+impl McpServer {
+    pub fn new(root: PathBuf) -> Self {
+        let config = Config::load_for_dir(&root);
+        Self { root, config }
+    }
+
+    /// Serve newline-delimited JSON-RPC messages until `input` is closed.
+    pub fn serve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(err) => Some(error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    &format!("Parse error: {}", err),
+                )),
+            };
+            if let Some(response) = response {
+                serde_json::to_writer(&mut output, &response)?;
+                output.write_all(b"\n")?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one message; notifications get no response.
+    fn handle(&self, message: &Value) -> Option<Value> {
+        let Some(object) = message.as_object() else {
+            return Some(error_response(
+                Value::Null,
+                INVALID_REQUEST,
+                "Invalid request: expected a JSON object",
+            ));
+        };
+        let id = object.get("id").cloned();
+        let Some(method) = object.get("method").and_then(Value::as_str) else {
+            // Responses to requests we never send; nothing to do
+            return id.map(|id| error_response(id, INVALID_REQUEST, "Invalid request"));
+        };
+        let id = id?;
+        let params = object.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": tool_specs()
+                    .into_iter()
+                    .map(|tool| json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "inputSchema": tool.input_schema,
+                    }))
+                    .collect::<Vec<_>>()
+            })),
+            "tools/call" => self.call_tool(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = requested
+            .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(PROTOCOL_VERSION);
+        json!({
+            "protocolVersion": version,
+            "capabilities": {"tools": {"listChanged": false}},
+            "serverInfo": {"name": "cgrep", "version": env!("CARGO_PKG_VERSION")},
+            "instructions": format!(
+                "Code search over {}. Use agent_locate to find candidate regions, then agent_expand on the returned IDs.",
+                self.root.display()
+            ),
+        })
+    }
+
+    fn call_tool(&self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let name = params.get("name").and_then(Value::as_str).ok_or((
+            INVALID_PARAMS,
+            "tools/call requires a tool name".to_string(),
+        ))?;
+        if !tool_specs().iter().any(|tool| tool.name == name) {
+            return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+        }
+        let empty = Map::new();
+        let arguments = match params.get("arguments") {
+            None | Some(Value::Null) => &empty,
+            Some(Value::Object(arguments)) => arguments,
+            Some(_) => {
+                return Err((INVALID_PARAMS, "`arguments` must be an object".to_string()));
+            }
+        };
+
+        // Tool failures are results, so the model can see and correct them
+        Ok(match self.run_tool(name, &Arguments(arguments)) {
+            Ok(structured) => json!({
+                "content": [{"type": "text", "text": structured.to_string()}],
+                "structuredContent": structured,
+                "isError": false,
+            }),
+            Err(err) => json!({
+                "content": [{"type": "text", "text": format!("{:#}", err)}],
+                "isError": true,
+            }),
+        })
+    }
+
+    /// Run one tool, or a query command without a tool, outside the
+    /// JSON-RPC envelope.
+    pub fn run_command(&self, name: &str, arguments: &Map<String, Value>) -> Result<Value> {
+        let args = Arguments(arguments);
+        match name {
+            "callgraph" => {
+                let graph = callgraph::build_graph(
+                    &self.root,
+                    &args.required_string("function")?,
+                    args.usize("depth")?.unwrap_or(3),
+                    args.choice::<CallDirection>("direction")?
+                        .unwrap_or_default(),
+                    args.usize("max_nodes")?.unwrap_or(200).max(1),
+                )?;
+                Ok(serde_json::to_value(graph)?)
+            }
+            "deps" => {
+                let file = args
+                    .string("file")?
+                    .map(|file| self.scoped_path(Some(&file)));
+                Ok(serde_json::to_value(deps::find(
+                    &self.root,
+                    file.as_deref(),
+                    args.bool("reverse")?,
+                    args.bool("transitive")?,
+                )?)?)
+            }
+            "map" => {
+                let path = args
+                    .string("path")?
+                    .map(|path| self.scoped_path(Some(&path)));
+                let budget = args.usize("budget")?.unwrap_or(map::DEFAULT_BUDGET_TOKENS);
+                Ok(serde_json::to_value(map::build(
+                    &self.root,
+                    path.as_deref(),
+                    budget,
+                )?)?)
+            }
+            "outline" => {
+                let files: Vec<String> = args
+                    .strings("files")?
+                    .iter()
+                    .map(|file| self.scoped_path(Some(file)))
+                    .collect();
+                if files.is_empty() {
+                    bail!("missing required argument `files`");
+                }
+                Ok(json!({"results": outline::find(&self.root, &files)?}))
+            }
+            _ if tool_specs().iter().any(|tool| tool.name == name) => self.run_tool(name, &args),
+            _ => bail!("Unknown command: {}", name),
+        }
+    }
+
+    fn run_tool(&self, name: &str, args: &Arguments<'_>) -> Result<Value> {
+        match name {
+            "search" => self.search(args),
+            "agent_locate" => {
+                let mut options = crate::agent_locate_options(
+                    args.required_string("query")?,
+                    Some(self.scoped_path(args.string("path")?.as_deref())),
+                    args.string("changed")?,
+                    args.usize("limit")?,
+                    args.choice::<CliSearchMode>("mode")?,
+                    args.choice::<CliBudgetPreset>("budget")?,
+                    args.usize("max_tokens")?,
+                );
+                options.session = args.string("session")?;
+                let report =
+                    search::execute_with_config(&options, OutputFormat::Json2, &self.config)?;
+                report.to_json2(&options, false)
+            }
+            "agent_expand" => {
+                let ids = args.strings("ids")?;
+                if ids.is_empty() {
+                    bail!("missing required argument `ids`");
+                }
+                let context = args.usize("context")?.unwrap_or(8);
+                let scope = args.choice::<ContextScope>("scope")?.unwrap_or_default();
+                let session = args.string("session")?;
+                Ok(serde_json::to_value(agent::expand(
+                    &ids,
+                    &self.root,
+                    context,
+                    scope,
+                    session.as_deref(),
+                )?)?)
+            }
+            "agent_context" => {
+                let options = crate::agent_locate_options(
+                    args.required_string("task")?,
+                    Some(self.scoped_path(args.string("path")?.as_deref())),
+                    args.string("changed")?,
+                    args.usize("limit")?,
+                    args.choice::<CliSearchMode>("mode")?,
+                    Some(CliBudgetPreset::Off),
+                    None,
+                );
+                let budget = args
+                    .usize("budget")?
+                    .unwrap_or(context::DEFAULT_BUDGET_TOKENS);
+                Ok(serde_json::to_value(context::build(&options, budget)?)?)
+            }
+            "symbols" => {
+                let kind = args.string("kind")?;
+                let lang = args.string("lang")?;
+                let file_type = args.string("file_type")?;
+                let glob = args.string("glob")?;
+                let exclude = args.string("exclude")?;
+                let changed = args.string("changed")?;
+                let filters = symbols::SymbolFilters {
+                    symbol_type: kind.as_deref(),
+                    lang: lang.as_deref(),
+                    file_type: file_type.as_deref(),
+                    glob: glob.as_deref(),
+                    exclude: exclude.as_deref(),
+                    changed: changed.as_deref(),
+                };
+                let name = args.required_string("name")?;
+                let (results, files) = symbols::find(&self.root, &self.config, &name, &filters)?;
+                Ok(json!({"files": files, "results": results}))
+            }
+            "definition" => {
+                let results = definition::find(&self.root, &args.required_string("name")?)?;
+                Ok(json!({"results": results}))
+            }
+            "references" => {
+                let kinds = args
+                    .strings("kinds")?
+                    .iter()
+                    .map(|kind| {
+                        CliReferenceKind::from_str(kind, true)
+                            .map(ReferenceKind::from)
+                            .map_err(|_| anyhow!("invalid `kinds` entry: {}", kind))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let results = references::find(
+                    &self.root,
+                    &args.required_string("name")?,
+                    args.usize("limit")?.unwrap_or(50),
+                    args.string("changed")?.as_deref(),
+                    &kinds,
+                    args.bool("exclude_comments")?,
+                )?;
+                Ok(json!({"results": results}))
+            }
+            "callers" => {
+                let results = callers::find(&self.root, &args.required_string("function")?)?;
+                Ok(json!({"results": results}))
+            }
+            "dependents" => {
+                let file = self.scoped_path(Some(&args.required_string("file")?));
+                let results = dependents::find(&self.root, &file)?;
+                Ok(json!({"results": results}))
+            }
+            _ => bail!("Unknown tool: {}", name),
+        }
+    }
+
+    fn search(&self, args: &Arguments<'_>) -> Result<Value> {
+        let budget = crate::budget_defaults(args.choice::<CliBudgetPreset>("budget")?);
+        let max_tokens = args.usize("max_tokens")?;
+        let options = search::SearchOptions {
+            query: args.required_string("query")?,
+            path: Some(self.scoped_path(args.string("path")?.as_deref())),
+            max_results: args.usize("limit")?.unwrap_or(20),
+            context: args.usize("context")?.unwrap_or(0),
+            scope: args.choice::<ContextScope>("scope")?.unwrap_or_default(),
+            file_type: args.string("file_type")?,
+            glob: args.string("glob")?,
+            exclude: args.string("exclude")?,
+            changed: args.string("changed")?,
+            regex: args.bool("regex")?,
+            case_sensitive: args.bool("case_sensitive")?,
+            search_mode: args
+                .choice::<CliSearchMode>("mode")?
+                .map(crate::cli_search_mode_to_hybrid),
+            max_chars_per_snippet: budget.max_chars_per_snippet,
+            max_total_chars: budget.max_total_chars.filter(|_| max_tokens.is_none()),
+            max_tokens,
+            max_context_chars: budget.max_context_chars,
+            dedupe_context: budget.dedupe_context,
+            path_alias: budget.path_alias,
+            suppress_boilerplate: budget.suppress_boilerplate,
+            session: args.string("session")?,
+            ..Default::default()
+        };
+        let report = search::execute_with_config(&options, OutputFormat::Json2, &self.config)?;
+        report.to_json2(&options, false)
+    }
+
+    /// Absolute path for a root-relative tool argument.
+    fn scoped_path(&self, path: Option<&str>) -> String {
+        match path {
+            Some(path) => self.root.join(path).display().to_string(),
+            None => self.root.display().to_string(),
+        }
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message},
+    })
+}
+
+/// Run the MCP server on stdin/stdout
+pub fn run(path: Option<&str>) -> Result<()> {
+    let start = match path {
+        Some(path) => PathBuf::from(path).canonicalize()?,
+        None => std::env::current_dir()?,
+    };
+    let root = get_root_with_index(start);
+    preload_index(&root);
+    eprintln!("cgrep MCP server ready for {}", root.display());
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    McpServer::new(root).serve(stdin.lock(), stdout.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn exchange(server: &McpServer, requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let mut output = Vec::new();
+        server
+            .serve(Cursor::new(input), &mut output)
+            .expect("serve");
+        String::from_utf8(output)
+            .expect("utf8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json response"))
+            .collect()
+    }
+
+    #[test]
+    fn handshake_lists_typed_tools() {
+        let dir = TempDir::new().expect("tempdir");
+        let server = McpServer::new(dir.path().to_path_buf());
+        let responses = exchange(
+            &server,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                       "params": {"protocolVersion": "2024-11-05", "capabilities": {}}}),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}),
+            ],
+        );
+
+        // The notification gets no response
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "cgrep");
+
+        let tools = responses[1]["result"]["tools"].as_array().expect("tools");
+        let names: Vec<&str> = tools.iter().filter_map(|t| t["name"].as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "search",
+                "agent_locate",
+                "agent_expand",
+                "agent_context",
+                "symbols",
+                "definition",
+                "references",
+                "callers",
+                "dependents"
+            ]
+        );
+        for tool in tools {
+            let schema = &tool["inputSchema"];
+            assert_eq!(schema["type"], "object");
+            for required in schema["required"].as_array().expect("required") {
+                let key = required.as_str().expect("key");
+                assert!(schema["properties"].get(key).is_some(), "{}", key);
+            }
+        }
+
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn tool_calls_return_structured_results() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn parse_config() {}\n\npub fn load() {\n    parse_config();\n}\n",
+        )
+        .expect("write");
+        let server = McpServer::new(dir.path().to_path_buf());
+        let responses = exchange(
+            &server,
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                       "params": {"name": "definition", "arguments": {"name": "parse_config"}}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                       "params": {"name": "callers", "arguments": {"function": "parse_config"}}}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                       "params": {"name": "references", "arguments": {"name": "parse_config", "kinds": ["bogus"]}}}),
+                json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                       "params": {"name": "grep", "arguments": {}}}),
+            ],
+        );
+
+        let definition = &responses[0]["result"];
+        assert_eq!(definition["isError"], false);
+        let found = &definition["structuredContent"]["results"][0];
+        assert_eq!(found["path"], "lib.rs");
+        assert_eq!(found["line"], 1);
+
+        let caller = &responses[1]["result"]["structuredContent"]["results"][0];
+        assert_eq!(caller["caller"], "load");
+        assert_eq!(caller["line"], 4);
+
+        // Bad arguments are tool errors; unknown tools are protocol errors
+        assert_eq!(responses[2]["result"]["isError"], true);
+        assert_eq!(responses[3]["error"]["code"], INVALID_PARAMS);
+    }
+}
@@ -9,6 +9,18 @@ use serde::Serialize;
 
 use anyhow::Result;
 
+/// Output format for results
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// Structured JSON for AI agents (`meta` + `results`)
+    Json2,
+}
+
 /// Print JSON output, optionally compact (no pretty formatting).
 pub fn print_json<T: Serialize>(value: &T, compact: bool) -> Result<()> {
     if compact {
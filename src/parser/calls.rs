@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Call-site extraction from AST using tree-sitter node traversal
+
+use anyhow::Result;
+use serde::Serialize;
+use tree_sitter::{Node, Parser};
+
+use crate::parser::languages::LANGUAGES;
+
+/// Maximum characters kept for a receiver expression
+const MAX_RECEIVER_CHARS: usize = 80;
+
+/// A call expression found in source code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallSite {
+    /// Name of the called function or method
+    pub callee: String,
+    /// Enclosing function or method name, `None` at top level
+    pub caller: Option<String>,
+    /// Receiver expression for method calls (`obj` in `obj.callee()`)
+    pub receiver: Option<String>,
+    /// Whether the call is made on a receiver rather than a bare call
+    pub is_method: bool,
+    pub line: usize,
+    pub column: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+/// Call-site extractor using tree-sitter node traversal
+pub struct CallExtractor;
+
+impl Default for CallExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract all call sites from source code
+    pub fn extract(&self, source: &str, language: &str) -> Result<Vec<CallSite>> {
+        let lang = LANGUAGES
+            .get(language)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language))?;
+
+        let mut parser = Parser::new();
+        parser.set_language(lang)?;
+
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse source"))?;
+
+        let mut calls = Vec::new();
+        self.traverse_node(
+            tree.root_node(),
+            source.as_bytes(),
+            language,
+            None,
+            &mut calls,
+        );
+
+        Ok(calls)
+    }
+
+    /// Traverse the AST, tracking the innermost named function
+    fn traverse_node(
+        &self,
+        node: Node,
+        source: &[u8],
+        lang: &str,
+        enclosing: Option<&str>,
+        calls: &mut Vec<CallSite>,
+    ) {
+        let function_name = self.function_name(node, source, lang);
+        let enclosing = function_name.as_deref().or(enclosing);
+
+        if let Some(call) = self.call_from_node(node, source, lang, enclosing) {
+            calls.push(call);
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.traverse_node(child, source, lang, enclosing, calls);
+        }
+    }
+
+    /// Name of the function defined by this node, if it defines one
+    fn function_name(&self, node: Node, source: &[u8], lang: &str) -> Option<String> {
+        let kind = node.kind();
+        let is_function = match lang {
+            "typescript" | "tsx" | "javascript" => matches!(
+                kind,
+                "function_declaration"
+                    | "generator_function_declaration"
+                    | "function_expression"
+                    | "arrow_function"
+                    | "method_definition"
+            ),
+            "python" => kind == "function_definition",
+            "rust" => kind == "function_item",
+            "go" => matches!(kind, "function_declaration" | "method_declaration"),
+            "c" | "cpp" => kind == "function_definition",
+            "java" => matches!(kind, "method_declaration" | "constructor_declaration"),
+            "ruby" => matches!(kind, "method" | "singleton_method"),
+            _ => false,
+        };
+        if !is_function {
+            return None;
+        }
+
+        if matches!(lang, "c" | "cpp") {
+            // Follow nested declarators down to the bare function name
+            let mut declarator = node.child_by_field_name("declarator")?;
+            while let Some(inner) = declarator.child_by_field_name("declarator") {
+                declarator = inner;
+            }
+            return node_text(declarator, source);
+        }
+
+        if let Some(name) = node.child_by_field_name("name") {
+            return node_text(name, source);
+        }
+
+        // Anonymous JS functions take the name of the variable they are bound to
+        let parent = node.parent()?;
+        if parent.kind() == "variable_declarator" {
+            return parent
+                .child_by_field_name("name")
+                .and_then(|name| node_text(name, source));
+        }
+        None
+    }
+
+    /// Build a call site if the node is a call expression
+    fn call_from_node(
+        &self,
+        node: Node,
+        source: &[u8],
+        lang: &str,
+        enclosing: Option<&str>,
+    ) -> Option<CallSite> {
+        let (name_node, receiver) = match (lang, node.kind()) {
+            ("java", "method_invocation") => (
+                node.child_by_field_name("name")?,
+                node.child_by_field_name("object"),
+            ),
+            ("ruby", "call") => (
+                node.child_by_field_name("method")?,
+                node.child_by_field_name("receiver"),
+            ),
+            ("python", "call")
+            | (
+                "typescript" | "tsx" | "javascript" | "rust" | "go" | "c" | "cpp",
+                "call_expression",
+            ) => callee_parts(node.child_by_field_name("function")?)?,
+            _ => return None,
+        };
+
+        let callee = node_text(name_node, source)?;
+        let receiver = receiver.and_then(|receiver| receiver_text(receiver, source));
+        Some(CallSite {
+            callee,
+            caller: enclosing.map(str::to_string),
+            is_method: receiver.is_some(),
+            receiver,
+            line: name_node.start_position().row + 1,
+            column: name_node.start_position().column + 1,
+            byte_start: node.start_byte(),
+            byte_end: node.end_byte(),
+        })
+    }
+}
+
+/// Split a callee expression into its name node and optional receiver node
+fn callee_parts(function: Node) -> Option<(Node, Option<Node>)> {
+    match function.kind() {
+        "identifier" | "field_identifier" | "property_identifier" => Some((function, None)),
+        // JS/TS `obj.name`
+        "member_expression" => Some((
+            function.child_by_field_name("property")?,
+            function.child_by_field_name("object"),
+        )),
+        // Python `obj.name`
+        "attribute" => Some((
+            function.child_by_field_name("attribute")?,
+            function.child_by_field_name("object"),
+        )),
+        // Rust `obj.name` / C `obj->name`
+        "field_expression" => Some((
+            function.child_by_field_name("field")?,
+            function
+                .child_by_field_name("value")
+                .or_else(|| function.child_by_field_name("argument")),
+        )),
+        // Go `obj.Name`
+        "selector_expression" => Some((
+            function.child_by_field_name("field")?,
+            function.child_by_field_name("operand"),
+        )),
+        // Path-qualified calls (`Type::name`) are not made on a receiver
+        "scoped_identifier" | "qualified_identifier" => {
+            let name = function.child_by_field_name("name")?;
+            callee_parts(name).map(|(name, _)| (name, None))
+        }
+        "generic_function" => callee_parts(function.child_by_field_name("function")?),
+        "template_function" => callee_parts(function.child_by_field_name("name")?),
+        _ => None,
+    }
+}
+
+fn node_text(node: Node, source: &[u8]) -> Option<String> {
+    node.utf8_text(source).ok().map(str::to_string)
+}
+
+fn receiver_text(node: Node, source: &[u8]) -> Option<String> {
+    let text = node.utf8_text(source).ok()?;
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return None;
+    }
+    Some(collapsed.chars().take(MAX_RECEIVER_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls_named<'a>(calls: &'a [CallSite], name: &str) -> Vec<&'a CallSite> {
+        calls.iter().filter(|c| c.callee == name).collect()
+    }
+
+    #[test]
+    fn test_rust_calls_report_caller_and_receiver() {
+        let source = r#"
+// helper() in a comment
+fn run() { helper(); }
+
+fn other(client: Client) {
+    let s = "helper()";
+    client.helper();
+    Parser::helper();
+}
+"#;
+        let calls = CallExtractor::new().extract(source, "rust").unwrap();
+        let helper = calls_named(&calls, "helper");
+
+        assert_eq!(helper.len(), 3);
+        assert_eq!(helper[0].caller.as_deref(), Some("run"));
+        assert_eq!(helper[0].line, 3);
+        assert!(!helper[0].is_method);
+        assert_eq!(helper[1].caller.as_deref(), Some("other"));
+        assert!(helper[1].is_method);
+        assert_eq!(helper[1].receiver.as_deref(), Some("client"));
+        assert!(!helper[2].is_method);
+    }
+
+    #[test]
+    fn test_python_method_and_bare_calls() {
+        let source = r#"
+def main():
+    validate(x)
+    self.validate(y)
+"#;
+        let calls = CallExtractor::new().extract(source, "python").unwrap();
+        let validate = calls_named(&calls, "validate");
+
+        assert_eq!(validate.len(), 2);
+        assert!(validate.iter().all(|c| c.caller.as_deref() == Some("main")));
+        assert!(!validate[0].is_method);
+        assert_eq!(validate[1].receiver.as_deref(), Some("self"));
+    }
+
+    #[test]
+    fn test_javascript_arrow_function_caller() {
+        let source = "const handler = () => { api.fetchUser(1); };\nfetchUser(2);\n";
+        let calls = CallExtractor::new().extract(source, "javascript").unwrap();
+        let fetch = calls_named(&calls, "fetchUser");
+
+        assert_eq!(fetch.len(), 2);
+        assert_eq!(fetch[0].caller.as_deref(), Some("handler"));
+        assert!(fetch[0].is_method);
+        assert_eq!(fetch[1].caller, None);
+    }
+
+    #[test]
+    fn test_java_and_go_calls() {
+        let java = "class A { void run() { service.save(x); save(y); } }";
+        let calls = CallExtractor::new().extract(java, "java").unwrap();
+        let save = calls_named(&calls, "save");
+        assert_eq!(save.len(), 2);
+        assert!(save[0].is_method);
+        assert!(!save[1].is_method);
+        assert_eq!(save[0].caller.as_deref(), Some("run"));
+
+        let go = "package main\nfunc main() { store.Save(x); Save(y) }\n";
+        let calls = CallExtractor::new().extract(go, "go").unwrap();
+        let save = calls_named(&calls, "Save");
+        assert_eq!(save.len(), 2);
+        assert!(save[0].is_method);
+        assert_eq!(save[1].caller.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_c_caller_name_from_declarator() {
+        let source = "int main(void) { return compute(1); }\n";
+        let calls = CallExtractor::new().extract(source, "c").unwrap();
+        let compute = calls_named(&calls, "compute");
+        assert_eq!(compute.len(), 1);
+        assert_eq!(compute[0].caller.as_deref(), Some("main"));
+    }
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Import statement extraction from AST using tree-sitter node traversal
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+use crate::parser::languages::LANGUAGES;
+
+/// How a module is brought into scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportKind {
+    /// `use`, `import`, `require`, `from ... import`
+    Import,
+    /// Rust `mod foo;` declaration of a child module file
+    Mod,
+}
+
+/// An import found in source code, before resolution to files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Import {
+    /// Module path as written (`crate::a::b`, `./util`, `..pkg`, `com.foo.Bar`)
+    pub module: String,
+    /// Names imported from the module (`from pkg import a, b`)
+    pub names: Vec<String>,
+    pub kind: ImportKind,
+    pub line: usize,
+}
+
+/// Import extractor using tree-sitter node traversal
+pub struct ImportExtractor;
+
+impl Default for ImportExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImportExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract imports from source code
+    pub fn extract(&self, source: &str, language: &str) -> Result<Vec<Import>> {
+        let lang = LANGUAGES
+            .get(language)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language))?;
+
+        let mut parser = Parser::new();
+        parser.set_language(lang)?;
+
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse source"))?;
+
+        let mut imports = Vec::new();
+        self.traverse_node(tree.root_node(), source.as_bytes(), language, &mut imports);
+        Ok(imports)
+    }
+
+    fn traverse_node(&self, node: Node, source: &[u8], lang: &str, imports: &mut Vec<Import>) {
+        let found = match lang {
+            "rust" => self.match_rust_node(node, source, imports),
+            "python" => self.match_python_node(node, source, imports),
+            "typescript" | "tsx" | "javascript" => {
+                self.match_javascript_node(node, source, imports)
+            }
+            "go" => self.match_go_node(node, source, imports),
+            "java" => self.match_java_node(node, source, imports),
+            _ => false,
+        };
+        if found {
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.traverse_node(child, source, lang, imports);
+        }
+    }
+
+    /// Rust `use` trees and body-less `mod` declarations
+    fn match_rust_node(&self, node: Node, source: &[u8], imports: &mut Vec<Import>) -> bool {
+        let line = node.start_position().row + 1;
+        match node.kind() {
+            "use_declaration" => {
+                if let Some(text) = node
+                    .child_by_field_name("argument")
+                    .and_then(|arg| text_of(arg, source))
+                {
+                    for module in expand_use_tree(&text) {
+                        imports.push(Import {
+                            module,
+                            names: Vec::new(),
+                            kind: ImportKind::Import,
+                            line,
+                        });
+                    }
+                }
+                true
+            }
+            "mod_item" if node.child_by_field_name("body").is_none() => {
+                if let Some(name) = node
+                    .child_by_field_name("name")
+                    .and_then(|name| text_of(name, source))
+                {
+                    imports.push(Import {
+                        module: name,
+                        names: Vec::new(),
+                        kind: ImportKind::Mod,
+                        line,
+                    });
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Python `import a.b` and `from .a import b`
+    fn match_python_node(&self, node: Node, source: &[u8], imports: &mut Vec<Import>) -> bool {
+        let line = node.start_position().row + 1;
+        match node.kind() {
+            "import_statement" => {
+                let mut cursor = node.walk();
+                for name in node.children_by_field_name("name", &mut cursor) {
+                    if let Some(module) = python_dotted_name(name, source) {
+                        imports.push(Import {
+                            module,
+                            names: Vec::new(),
+                            kind: ImportKind::Import,
+                            line,
+                        });
+                    }
+                }
+                true
+            }
+            "import_from_statement" => {
+                let Some(module) = node
+                    .child_by_field_name("module_name")
+                    .and_then(|module| text_of(module, source))
+                else {
+                    return true;
+                };
+                let mut cursor = node.walk();
+                let names = node
+                    .children_by_field_name("name", &mut cursor)
+                    .filter_map(|name| python_dotted_name(name, source))
+                    .collect();
+                imports.push(Import {
+                    module: module.split_whitespace().collect(),
+                    names,
+                    kind: ImportKind::Import,
+                    line,
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// ES `import`/`export ... from`, `require()` and dynamic `import()`
+    fn match_javascript_node(&self, node: Node, source: &[u8], imports: &mut Vec<Import>) -> bool {
+        let line = node.start_position().row + 1;
+        let module = match node.kind() {
+            "import_statement" | "export_statement" => node
+                .child_by_field_name("source")
+                .and_then(|s| string_literal(s, source)),
+            "call_expression" => {
+                let is_loader = node.child_by_field_name("function").is_some_and(|f| {
+                    matches!(f.kind(), "import") || text_of(f, source).as_deref() == Some("require")
+                });
+                if !is_loader {
+                    return false;
+                }
+                node.child_by_field_name("arguments").and_then(|args| {
+                    let mut cursor = args.walk();
+                    let first = args.named_children(&mut cursor).next()?;
+                    (first.kind() == "string")
+                        .then(|| string_literal(first, source))
+                        .flatten()
+                })
+            }
+            _ => return false,
+        };
+
+        match module {
+            Some(module) => {
+                imports.push(Import {
+                    module,
+                    names: Vec::new(),
+                    kind: ImportKind::Import,
+                    line,
+                });
+                true
+            }
+            // `export function ...` has no source; keep walking for nested requires
+            None => false,
+        }
+    }
+
+    /// Go `import "path"` specs
+    fn match_go_node(&self, node: Node, source: &[u8], imports: &mut Vec<Import>) -> bool {
+        if node.kind() != "import_spec" {
+            return false;
+        }
+        if let Some(module) = node
+            .child_by_field_name("path")
+            .and_then(|path| string_literal(path, source))
+        {
+            imports.push(Import {
+                module,
+                names: Vec::new(),
+                kind: ImportKind::Import,
+                line: node.start_position().row + 1,
+            });
+        }
+        true
+    }
+
+    /// Java `import [static] a.b.C;` and `import a.b.*;`
+    fn match_java_node(&self, node: Node, source: &[u8], imports: &mut Vec<Import>) -> bool {
+        if node.kind() != "import_declaration" {
+            return false;
+        }
+        if let Some(text) = text_of(node, source) {
+            let module: String = text
+                .trim_start_matches("import")
+                .trim_end_matches(';')
+                .split_whitespace()
+                .filter(|part| *part != "static")
+                .collect();
+            if !module.is_empty() {
+                imports.push(Import {
+                    module,
+                    names: Vec::new(),
+                    kind: ImportKind::Import,
+                    line: node.start_position().row + 1,
+                });
+            }
+        }
+        true
+    }
+}
+
+fn text_of(node: Node, source: &[u8]) -> Option<String> {
+    node.utf8_text(source).ok().map(str::to_string)
+}
+
+fn string_literal(node: Node, source: &[u8]) -> Option<String> {
+    let text = node.utf8_text(source).ok()?;
+    let trimmed = text.trim_matches(|c| c == '"' || c == '\'' || c == '`');
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// Dotted name of a Python import target, ignoring `as` aliases
+fn python_dotted_name(node: Node, source: &[u8]) -> Option<String> {
+    let target = if node.kind() == "aliased_import" {
+        node.child_by_field_name("name")?
+    } else {
+        node
+    };
+    text_of(target, source)
+}
+
+/// Flatten a Rust use tree (`crate::a::{b, c::{d as e}}`) into plain paths
+pub fn expand_use_tree(tree: &str) -> Vec<String> {
+    let compact: String = tree.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut paths = Vec::new();
+    expand_into("", &compact, &mut paths);
+    paths
+}
+
+fn expand_into(prefix: &str, tree: &str, out: &mut Vec<String>) {
+    let tree = tree.trim();
+    if tree.is_empty() {
+        return;
+    }
+
+    if let Some(open) = tree.find('{') {
+        let head = tree[..open].trim().trim_end_matches("::");
+        let rest = tree[open + 1..].trim_end();
+        let inner = rest.strip_suffix('}').unwrap_or(rest);
+        let base = join_path(prefix, head);
+        for item in split_top_level(inner) {
+            expand_into(&base, item, out);
+        }
+        return;
+    }
+
+    let path = tree.split(" as ").next().unwrap_or(tree).trim();
+    let path = path.trim_end_matches("::*");
+    let full = if path == "self" {
+        prefix.to_string()
+    } else {
+        join_path(prefix, path)
+    };
+    if !full.is_empty() {
+        out.push(full);
+    }
+}
+
+fn join_path(prefix: &str, path: &str) -> String {
+    match (prefix.is_empty(), path.is_empty()) {
+        (true, _) => path.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}::{}", prefix, path),
+    }
+}
+
+/// Split on commas that are not nested inside braces
+fn split_top_level(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0usize;
+    for (idx, ch) in list.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&list[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&list[start..]);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modules(imports: &[Import]) -> Vec<&str> {
+        imports.iter().map(|i| i.module.as_str()).collect()
+    }
+
+    #[test]
+    fn test_expand_use_tree() {
+        assert_eq!(
+            expand_use_tree("crate::a::{self, b, c::{d as e, f::*}}"),
+            vec![
+                "crate::a",
+                "crate::a::b",
+                "crate::a::c::d",
+                "crate::a::c::f"
+            ]
+        );
+        assert_eq!(expand_use_tree("super::x as y"), vec!["super::x"]);
+    }
+
+    #[test]
+    fn test_rust_imports() {
+        let source =
+            "use crate::utils::{a, b};\nmod parser;\nmod inline { }\n// use crate::nope;\n";
+        let imports = ImportExtractor::new().extract(source, "rust").unwrap();
+        assert_eq!(
+            modules(&imports),
+            vec!["crate::utils::a", "crate::utils::b", "parser"]
+        );
+        assert_eq!(imports[2].kind, ImportKind::Mod);
+        assert_eq!(imports[2].line, 2);
+    }
+
+    #[test]
+    fn test_python_imports() {
+        let source = "import os.path as p, json\nfrom ..pkg import mod_a, mod_b as b\nfrom . import sibling\n";
+        let imports = ImportExtractor::new().extract(source, "python").unwrap();
+        assert_eq!(modules(&imports), vec!["os.path", "json", "..pkg", "."]);
+        assert_eq!(imports[2].names, vec!["mod_a", "mod_b"]);
+        assert_eq!(imports[3].names, vec!["sibling"]);
+    }
+
+    #[test]
+    fn test_javascript_imports() {
+        let source = "import { a } from './a';\nexport * from \"../b\";\nconst c = require('./c');\nconst s = \"require('./nope')\";\n";
+        let imports = ImportExtractor::new()
+            .extract(source, "javascript")
+            .unwrap();
+        assert_eq!(modules(&imports), vec!["./a", "../b", "./c"]);
+    }
+
+    #[test]
+    fn test_go_and_java_imports() {
+        let go = "package main\nimport (\n  \"fmt\"\n  util \"example.com/app/pkg/util\"\n)\n";
+        let imports = ImportExtractor::new().extract(go, "go").unwrap();
+        assert_eq!(modules(&imports), vec!["fmt", "example.com/app/pkg/util"]);
+
+        let java = "import com.acme.Widget;\nimport static com.acme.Util.helper;\nimport com.acme.model.*;\nclass A {}\n";
+        let imports = ImportExtractor::new().extract(java, "java").unwrap();
+        assert_eq!(
+            modules(&imports),
+            vec![
+                "com.acme.Widget",
+                "com.acme.Util.helper",
+                "com.acme.model.*"
+            ]
+        );
+    }
+}
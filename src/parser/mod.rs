@@ -2,5 +2,8 @@
 
 //! Parser module - AST parsing using tree-sitter
 
+pub mod calls;
+pub mod imports;
 pub mod languages;
+pub mod references;
 pub mod symbols;
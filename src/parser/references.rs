@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Identifier occurrence classification from AST using tree-sitter node traversal
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use tree_sitter::{Node, Parser};
+
+use crate::parser::languages::LANGUAGES;
+
+/// How an occurrence of a name is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    /// The name is declared here (function, type, variable, parameter)
+    Definition,
+    /// The value is used without being modified
+    Read,
+    /// The name is the target of an assignment or increment
+    Write,
+    /// The name appears in an import/use statement
+    Import,
+    /// Mentioned inside a comment
+    Comment,
+    /// Mentioned inside a string literal
+    String,
+    /// Matched in a file without a supported grammar
+    Text,
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ReferenceKind::Definition => "definition",
+            ReferenceKind::Read => "read",
+            ReferenceKind::Write => "write",
+            ReferenceKind::Import => "import",
+            ReferenceKind::Comment => "comment",
+            ReferenceKind::String => "string",
+            ReferenceKind::Text => "text",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A classified occurrence of a name in source code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    pub line: usize,
+    pub column: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+/// Statements whose identifiers are imports
+const IMPORT_KINDS: &[&str] = &[
+    "use_declaration",
+    "extern_crate_declaration",
+    "import_statement",
+    "import_from_statement",
+    "future_import_statement",
+    "import_declaration",
+    "import_spec",
+    "using_declaration",
+];
+
+/// Literal kinds whose text is scanned rather than parsed
+const STRING_KINDS: &[&str] = &[
+    "string",
+    "string_literal",
+    "raw_string_literal",
+    "char_literal",
+    "character_literal",
+    "template_string",
+    "interpreted_string_literal",
+    "heredoc_body",
+];
+
+/// Code embedded in strings (`f"{x}"`, `` `${x}` ``, `"#{x}"`)
+const INTERPOLATION_KINDS: &[&str] = &["interpolation", "template_substitution"];
+
+/// Nodes that group several binding or assignment targets
+const TARGET_GROUP_KINDS: &[&str] = &[
+    "expression_list",
+    "pattern_list",
+    "tuple_pattern",
+    "list_pattern",
+    "array_pattern",
+    "object_pattern",
+    "slice_pattern",
+    "ref_pattern",
+    "mut_pattern",
+    "reference_pattern",
+    "parenthesized_expression",
+];
+
+/// Occurrence extractor using tree-sitter node traversal
+pub struct ReferenceExtractor;
+
+impl Default for ReferenceExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReferenceExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find and classify every occurrence of `name` in source code
+    pub fn extract(&self, source: &str, language: &str, name: &str) -> Result<Vec<Reference>> {
+        let lang = LANGUAGES
+            .get(language)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language))?;
+
+        let mut parser = Parser::new();
+        parser.set_language(lang)?;
+
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse source"))?;
+
+        let word = Regex::new(&format!(r"\b{}\b", regex::escape(name)))?;
+        let mut refs = Vec::new();
+        self.traverse_node(tree.root_node(), source, name, &word, &mut refs);
+        refs.sort_by_key(|r| r.byte_start);
+        Ok(refs)
+    }
+
+    fn traverse_node(
+        &self,
+        node: Node,
+        source: &str,
+        name: &str,
+        word: &Regex,
+        refs: &mut Vec<Reference>,
+    ) {
+        let kind = node.kind();
+        if kind.contains("comment") {
+            scan_text(node, source, word, ReferenceKind::Comment, &[], refs);
+            return;
+        }
+
+        if STRING_KINDS.contains(&kind) {
+            let mut cursor = node.walk();
+            let holes: Vec<Node> = node
+                .named_children(&mut cursor)
+                .filter(|child| INTERPOLATION_KINDS.contains(&child.kind()))
+                .collect();
+            scan_text(node, source, word, ReferenceKind::String, &holes, refs);
+            for hole in holes {
+                self.traverse_node(hole, source, name, word, refs);
+            }
+            return;
+        }
+
+        if node.child_count() == 0 {
+            if node.utf8_text(source.as_bytes()).ok() == Some(name) {
+                let start = node.start_position();
+                refs.push(Reference {
+                    kind: classify(node),
+                    line: start.row + 1,
+                    column: start.column + 1,
+                    byte_start: node.start_byte(),
+                    byte_end: node.end_byte(),
+                });
+            }
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.traverse_node(child, source, name, word, refs);
+        }
+    }
+}
+
+/// Record word matches in a comment or string, skipping interpolated code
+fn scan_text(
+    node: Node,
+    source: &str,
+    word: &Regex,
+    kind: ReferenceKind,
+    holes: &[Node],
+    refs: &mut Vec<Reference>,
+) {
+    let Some(text) = source.get(node.start_byte()..node.end_byte()) else {
+        return;
+    };
+    for found in word.find_iter(text) {
+        let start = node.start_byte() + found.start();
+        if holes
+            .iter()
+            .any(|hole| hole.start_byte() <= start && start < hole.end_byte())
+        {
+            continue;
+        }
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        refs.push(Reference {
+            kind,
+            line: source[..start].matches('\n').count() + 1,
+            column: start - line_start + 1,
+            byte_start: start,
+            byte_end: node.start_byte() + found.end(),
+        });
+    }
+}
+
+fn classify(node: Node) -> ReferenceKind {
+    if has_ancestor(node, IMPORT_KINDS) {
+        ReferenceKind::Import
+    } else if is_definition(node) {
+        ReferenceKind::Definition
+    } else if is_write(node) {
+        ReferenceKind::Write
+    } else {
+        ReferenceKind::Read
+    }
+}
+
+fn has_ancestor(node: Node, kinds: &[&str]) -> bool {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if kinds.contains(&parent.kind()) {
+            return true;
+        }
+        current = parent.parent();
+    }
+    false
+}
+
+fn is_field(parent: Node, field: &str, node: Node) -> bool {
+    parent.child_by_field_name(field) == Some(node)
+}
+
+/// Climb out of tuple/list/destructuring groups to the node that binds them
+fn binding_target(node: Node) -> Node {
+    let mut target = node;
+    while let Some(parent) = target.parent() {
+        if !TARGET_GROUP_KINDS.contains(&parent.kind()) {
+            break;
+        }
+        target = parent;
+    }
+    target
+}
+
+fn is_definition_kind(kind: &str) -> bool {
+    const SUFFIXES: &[&str] = &[
+        "_item",
+        "_definition",
+        "_declaration",
+        "_declarator",
+        "_spec",
+        "_signature",
+        "_parameter",
+    ];
+    SUFFIXES.iter().any(|suffix| kind.ends_with(suffix))
+        || matches!(
+            kind,
+            "class"
+                | "module"
+                | "method"
+                | "singleton_method"
+                | "parameter"
+                | "enum_variant"
+                | "enum_constant"
+                | "associated_type"
+        )
+}
+
+fn is_definition(node: Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    if is_field(parent, "name", node) && is_definition_kind(parent.kind()) {
+        return true;
+    }
+    // C/C++ `int x;`, `int f(void)`, `int *p = ...`
+    if is_field(parent, "declarator", node)
+        && (parent.kind().ends_with("_declarator") || parent.kind().ends_with("declaration"))
+    {
+        return true;
+    }
+
+    // Parameter lists that hold bare identifiers
+    if matches!(
+        parent.kind(),
+        "parameters"
+            | "formal_parameters"
+            | "lambda_parameters"
+            | "closure_parameters"
+            | "method_parameters"
+            | "block_parameters"
+    ) {
+        return true;
+    }
+    // Python `x: int`
+    if parent.kind() == "typed_parameter" {
+        let mut cursor = parent.walk();
+        return parent.named_children(&mut cursor).next() == Some(node);
+    }
+
+    let target = binding_target(node);
+    let Some(binder) = target.parent() else {
+        return false;
+    };
+    match binder.kind() {
+        "let_declaration" | "for_expression" | "parameter" | "required_parameter"
+        | "optional_parameter" => is_field(binder, "pattern", target),
+        "variable_declarator" => is_field(binder, "name", target),
+        "short_var_declaration" => is_field(binder, "left", target),
+        _ => false,
+    }
+}
+
+fn is_write(node: Node) -> bool {
+    // `obj.field = ...` writes `field`, not `obj`
+    let mut target = node;
+    while let Some(parent) = target.parent() {
+        let is_member_name = match parent.kind() {
+            "field_expression" | "selector_expression" | "field_access" => {
+                is_field(parent, "field", target)
+            }
+            "member_expression" => is_field(parent, "property", target),
+            "attribute" => is_field(parent, "attribute", target),
+            "call" => is_field(parent, "method", target),
+            _ => false,
+        };
+        if !is_member_name {
+            break;
+        }
+        target = parent;
+    }
+
+    let target = binding_target(target);
+    let Some(parent) = target.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        "assignment"
+        | "assignment_expression"
+        | "assignment_statement"
+        | "augmented_assignment"
+        | "augmented_assignment_expression"
+        | "compound_assignment_expr"
+        | "operator_assignment"
+        | "for_statement"
+        | "for_in_statement"
+        | "range_clause" => is_field(parent, "left", target),
+        "update_expression" | "inc_statement" | "dec_statement" => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str, lang: &str, name: &str) -> Vec<(usize, ReferenceKind)> {
+        ReferenceExtractor::new()
+            .extract(source, lang, name)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.line, r.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_reference_kinds() {
+        let source = r#"use crate::config::limit;
+// limit is clamped below
+fn limit_of(limit: usize) -> usize {
+    let mut total = limit;
+    total += 1;
+    state.limit = total;
+    println!("limit {}", total);
+    total
+}
+"#;
+        assert_eq!(
+            kinds(source, "rust", "limit"),
+            vec![
+                (1, ReferenceKind::Import),
+                (2, ReferenceKind::Comment),
+                (3, ReferenceKind::Definition),
+                (4, ReferenceKind::Read),
+                (6, ReferenceKind::Write),
+                (7, ReferenceKind::String),
+            ]
+        );
+        assert_eq!(
+            kinds(source, "rust", "total"),
+            vec![
+                (4, ReferenceKind::Definition),
+                (5, ReferenceKind::Write),
+                (6, ReferenceKind::Read),
+                (7, ReferenceKind::Read),
+                (8, ReferenceKind::Read),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_reference_kinds() {
+        let source = "from app import count\n\ndef bump(count, step: int):\n    count += step\n    a, count = 1, 2\n    self.count = count\n    return f\"{count} count\"\n";
+        assert_eq!(
+            kinds(source, "python", "count"),
+            vec![
+                (1, ReferenceKind::Import),
+                (3, ReferenceKind::Definition),
+                (4, ReferenceKind::Write),
+                (5, ReferenceKind::Write),
+                (6, ReferenceKind::Write),
+                (6, ReferenceKind::Read),
+                (7, ReferenceKind::Read),
+                (7, ReferenceKind::String),
+            ]
+        );
+        assert_eq!(
+            kinds(source, "python", "step")[0].1,
+            ReferenceKind::Definition
+        );
+    }
+
+    #[test]
+    fn test_javascript_and_go_reference_kinds() {
+        let js = "import { total } from './t';\nlet sum = total;\nsum = sum + 1;\nsum++;\n";
+        assert_eq!(
+            kinds(js, "javascript", "sum"),
+            vec![
+                (2, ReferenceKind::Definition),
+                (3, ReferenceKind::Write),
+                (3, ReferenceKind::Read),
+                (4, ReferenceKind::Write),
+            ]
+        );
+        assert_eq!(kinds(js, "javascript", "total")[0].1, ReferenceKind::Import);
+
+        let go = "package main\nfunc run() {\n  n, err := load()\n  n = n * 2\n  _ = err\n}\n";
+        assert_eq!(
+            kinds(go, "go", "n"),
+            vec![
+                (3, ReferenceKind::Definition),
+                (4, ReferenceKind::Write),
+                (4, ReferenceKind::Read),
+            ]
+        );
+    }
+}
@@ -4,7 +4,8 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use tree_sitter::{Node, Parser};
+use std::path::{Component, Path};
+use tree_sitter::{Node, Parser, Tree};
 
 use crate::parser::languages::LANGUAGES;
 
@@ -56,9 +57,82 @@ pub struct Symbol {
     pub end_line: usize,
     pub byte_start: Option<usize>,
     pub byte_end: Option<usize>,
+    /// Qualified path of the enclosing modules/types (`crate::index::IndexBuilder`)
     pub scope: Option<String>,
 }
 
+/// Separator between scope segments in qualified names
+pub fn scope_separator(language: &str) -> &'static str {
+    match language {
+        "rust" | "cpp" | "ruby" => "::",
+        _ => ".",
+    }
+}
+
+/// Join a scope and a name with the language's separator
+pub fn qualify(scope: Option<&str>, name: &str, language: &str) -> String {
+    match scope {
+        Some(scope) => format!("{}{}{}", scope, scope_separator(language), name),
+        None => name.to_string(),
+    }
+}
+
+/// Module path implied by a file location, for languages where the file
+/// itself is a module (`src/index/mod.rs` -> `crate::index`, `pkg/a.py` -> `pkg.a`)
+pub fn module_path(rel_path: &Path, language: &str) -> Option<String> {
+    let parts: Vec<&str> = rel_path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let (file, dirs) = parts.split_last()?;
+    let stem = Path::new(file).file_stem()?.to_str()?;
+
+    match language {
+        "rust" => {
+            // Module paths start at the crate's `src` directory
+            let src = dirs.iter().rposition(|dir| *dir == "src")?;
+            let mut segments: Vec<&str> = dirs[src + 1..].to_vec();
+            if segments.first() == Some(&"bin") {
+                // `src/bin/*.rs` are crate roots of their own
+                return Some("crate".to_string());
+            }
+            if !matches!(stem, "lib" | "main" | "mod") {
+                segments.push(stem);
+            }
+            segments.insert(0, "crate");
+            Some(segments.join("::"))
+        }
+        "python" => {
+            let dirs = match dirs.first() {
+                Some(&"src") => &dirs[1..],
+                _ => dirs,
+            };
+            let mut segments: Vec<&str> = dirs.to_vec();
+            if stem != "__init__" {
+                segments.push(stem);
+            }
+            (!segments.is_empty()).then(|| segments.join("."))
+        }
+        _ => None,
+    }
+}
+
+/// Node that scopes the symbols nested inside it (a class, an `impl` block)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    /// Name the nested symbols are scoped under
+    pub name: String,
+    /// Tree-sitter node kind (`impl_item`, `class_definition`, ...)
+    pub node_kind: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
 /// Symbol extractor using tree-sitter node traversal
 pub struct SymbolExtractor;
 
@@ -75,36 +149,106 @@ impl SymbolExtractor {
 
     /// Extract symbols from source code
     pub fn extract(&self, source: &str, language: &str) -> Result<Vec<Symbol>> {
-        let lang = LANGUAGES
-            .get(language)
-            .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language))?;
-
-        let mut parser = Parser::new();
-        parser.set_language(lang)?;
+        self.extract_in_module(source, language, None)
+    }
 
-        let tree = parser
-            .parse(source, None)
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse source"))?;
+    /// Extract symbols from a file, qualifying scopes with its module path
+    pub fn extract_for_path(
+        &self,
+        source: &str,
+        language: &str,
+        rel_path: &Path,
+    ) -> Result<Vec<Symbol>> {
+        let module = module_path(rel_path, language);
+        self.extract_in_module(source, language, module.as_deref())
+    }
 
+    fn extract_in_module(
+        &self,
+        source: &str,
+        language: &str,
+        module: Option<&str>,
+    ) -> Result<Vec<Symbol>> {
+        let tree = parse(source, language)?;
         let source_bytes = source.as_bytes();
         let mut symbols = Vec::new();
-
-        self.traverse_node(tree.root_node(), source_bytes, language, &mut symbols);
+        let mut scope: Vec<String> = module
+            .map(str::to_string)
+            .or_else(|| package_name(tree.root_node(), source_bytes, language))
+            .into_iter()
+            .collect();
+
+        self.traverse_node(
+            tree.root_node(),
+            source_bytes,
+            language,
+            &mut scope,
+            &mut symbols,
+        );
 
         Ok(symbols)
     }
 
-    /// Traverse the AST and extract symbols
-    fn traverse_node(&self, node: Node, source: &[u8], lang: &str, symbols: &mut Vec<Symbol>) {
+    /// Extract the nodes that scope the symbols nested inside them, outermost
+    /// first. Most are symbols themselves; Rust `impl` blocks are not.
+    pub fn extract_containers(&self, source: &str, language: &str) -> Result<Vec<Container>> {
+        let tree = parse(source, language)?;
+        let mut containers = Vec::new();
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            if let Some(name) = container_name(node, source.as_bytes(), language) {
+                containers.push(Container {
+                    name,
+                    node_kind: node.kind().to_string(),
+                    line: node.start_position().row + 1,
+                    end_line: node.end_position().row + 1,
+                    byte_start: node.start_byte(),
+                    byte_end: node.end_byte(),
+                });
+            }
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.children(&mut cursor).collect();
+            stack.extend(children.into_iter().rev());
+        }
+        Ok(containers)
+    }
+
+    /// Traverse the AST and extract symbols, tracking enclosing containers
+    fn traverse_node(
+        &self,
+        node: Node,
+        source: &[u8],
+        lang: &str,
+        scope: &mut Vec<String>,
+        symbols: &mut Vec<Symbol>,
+    ) {
         // Extract symbol based on node type and language
-        if let Some(symbol) = self.extract_symbol_from_node(node, source, lang) {
+        if let Some(mut symbol) = self.extract_symbol_from_node(node, source, lang) {
+            let receiver = go_receiver_type(node, source, lang);
+            let segments: Vec<&str> = scope
+                .iter()
+                .map(String::as_str)
+                .chain(receiver.as_deref())
+                .collect();
+            if !segments.is_empty() {
+                symbol.scope = Some(segments.join(scope_separator(lang)));
+            }
             symbols.push(symbol);
         }
 
+        let container = container_name(node, source, lang);
+        if let Some(name) = &container {
+            scope.push(name.clone());
+        }
+
         // Recursively traverse children
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            self.traverse_node(child, source, lang, symbols);
+            self.traverse_node(child, source, lang, scope, symbols);
+        }
+
+        if container.is_some() {
+            scope.pop();
         }
     }
 
@@ -240,6 +384,125 @@ impl SymbolExtractor {
     }
 }
 
+fn parse(source: &str, language: &str) -> Result<Tree> {
+    let lang = LANGUAGES
+        .get(language)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported language: {}", language))?;
+
+    let mut parser = Parser::new();
+    parser.set_language(lang)?;
+
+    parser
+        .parse(source, None)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse source"))
+}
+
+/// Name of a node that scopes the symbols nested inside it
+fn container_name(node: Node, source: &[u8], lang: &str) -> Option<String> {
+    let kind = node.kind();
+    let name_node = match lang {
+        "rust" => match kind {
+            // `impl<T> Trait for Foo<T>` scopes its methods under `Foo`
+            "impl_item" => {
+                return node
+                    .child_by_field_name("type")
+                    .map(|t| type_name(t, source))
+            }
+            "mod_item" if node.child_by_field_name("body").is_some() => {
+                node.child_by_field_name("name")
+            }
+            "trait_item" => node.child_by_field_name("name"),
+            _ => None,
+        },
+        "python" => match kind {
+            "class_definition" => node.child_by_field_name("name"),
+            _ => None,
+        },
+        "typescript" | "javascript" => match kind {
+            "class_declaration"
+            | "abstract_class_declaration"
+            | "class"
+            | "interface_declaration"
+            | "internal_module" => node.child_by_field_name("name"),
+            _ => None,
+        },
+        "java" => match kind {
+            "class_declaration"
+            | "interface_declaration"
+            | "enum_declaration"
+            | "record_declaration"
+            | "annotation_type_declaration" => node.child_by_field_name("name"),
+            _ => None,
+        },
+        "cpp" => match kind {
+            "namespace_definition" | "class_specifier" | "struct_specifier" => {
+                node.child_by_field_name("name")
+            }
+            _ => None,
+        },
+        "ruby" => match kind {
+            "class" | "module" => node.child_by_field_name("name"),
+            _ => None,
+        },
+        _ => None,
+    }?;
+    name_node.utf8_text(source).ok().map(str::to_string)
+}
+
+/// Base name of a type, without generics or path (`a::Foo<T>` -> `Foo`)
+fn type_name(node: Node, source: &[u8]) -> String {
+    match node.kind() {
+        "generic_type" => {
+            if let Some(inner) = node.child_by_field_name("type") {
+                return type_name(inner, source);
+            }
+        }
+        "scoped_type_identifier" => {
+            if let Some(name) = node.child_by_field_name("name") {
+                return type_name(name, source);
+            }
+        }
+        _ => {}
+    }
+    node.utf8_text(source).unwrap_or_default().to_string()
+}
+
+/// Receiver type of a Go method (`func (s *Server) Run()` -> `Server`)
+fn go_receiver_type(node: Node, source: &[u8], lang: &str) -> Option<String> {
+    if lang != "go" || node.kind() != "method_declaration" {
+        return None;
+    }
+    let receiver = node.child_by_field_name("receiver")?;
+    let mut cursor = receiver.walk();
+    let param = receiver
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "parameter_declaration")?;
+    let mut ty = param.child_by_field_name("type")?;
+    while ty.kind() == "pointer_type" {
+        let mut cursor = ty.walk();
+        ty = ty.named_children(&mut cursor).next()?;
+    }
+    Some(type_name(ty, source))
+}
+
+/// Package declared at the top of Go and Java files
+fn package_name(root: Node, source: &[u8], lang: &str) -> Option<String> {
+    let decl_kind = match lang {
+        "go" => "package_clause",
+        "java" => "package_declaration",
+        _ => return None,
+    };
+    let mut cursor = root.walk();
+    let decl = root
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == decl_kind)?;
+    let mut cursor = decl.walk();
+    let name = decl
+        .named_children(&mut cursor)
+        .find(|child| !child.kind().contains("comment") && child.kind() != "annotation")?;
+    name.utf8_text(source).ok().map(str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,6 +579,100 @@ class Calculator:
         assert_eq!(class.kind, SymbolKind::Class);
     }
 
+    #[test]
+    fn test_rust_scope_includes_module_and_impl() {
+        let source = r#"
+pub struct IndexBuilder;
+
+impl<'a> Default for IndexBuilder {
+    fn default() -> Self { IndexBuilder }
+}
+
+impl IndexBuilder {
+    pub fn build(&self) {}
+}
+
+mod inner {
+    pub fn helper() {}
+}
+"#;
+        let symbols = SymbolExtractor::new()
+            .extract_for_path(source, "rust", Path::new("src/index.rs"))
+            .unwrap();
+        let qualified: Vec<String> = symbols
+            .iter()
+            .map(|s| qualify(s.scope.as_deref(), &s.name, "rust"))
+            .collect();
+        assert!(qualified.contains(&"crate::index::IndexBuilder".to_string()));
+        assert!(qualified.contains(&"crate::index::IndexBuilder::default".to_string()));
+        assert!(qualified.contains(&"crate::index::IndexBuilder::build".to_string()));
+        assert!(qualified.contains(&"crate::index::inner::helper".to_string()));
+    }
+
+    #[test]
+    fn test_rust_containers_include_impl_blocks() {
+        let source = "pub struct Store;\n\nimpl Store {\n    fn get(&self) {}\n}\n\nmod inner {\n    trait Load {}\n}\n";
+        let containers = SymbolExtractor::new()
+            .extract_containers(source, "rust")
+            .unwrap();
+        let summary: Vec<(&str, &str, usize, usize)> = containers
+            .iter()
+            .map(|c| (c.name.as_str(), c.node_kind.as_str(), c.line, c.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Store", "impl_item", 3, 5),
+                ("inner", "mod_item", 7, 9),
+                ("Load", "trait_item", 8, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_and_java_scopes() {
+        let source = "class Calculator:\n    def add(self, a, b):\n        return a + b\n";
+        let symbols = SymbolExtractor::new()
+            .extract_for_path(source, "python", Path::new("src/pkg/calc.py"))
+            .unwrap();
+        let add = symbols.iter().find(|s| s.name == "add").unwrap();
+        assert_eq!(
+            qualify(add.scope.as_deref(), &add.name, "python"),
+            "pkg.calc.Calculator.add"
+        );
+
+        let java = "package com.acme;\nclass Outer {\n  class Inner {\n    void run() {}\n  }\n}\n";
+        let symbols = SymbolExtractor::new().extract(java, "java").unwrap();
+        let run = symbols.iter().find(|s| s.name == "run").unwrap();
+        assert_eq!(run.scope.as_deref(), Some("com.acme.Outer.Inner"));
+
+        let go = "package store\nfunc (s *Store) Save() {}\nfunc Open() {}\n";
+        let symbols = SymbolExtractor::new().extract(go, "go").unwrap();
+        let save = symbols.iter().find(|s| s.name == "Save").unwrap();
+        assert_eq!(
+            qualify(save.scope.as_deref(), &save.name, "go"),
+            "store.Store.Save"
+        );
+    }
+
+    #[test]
+    fn test_module_path() {
+        assert_eq!(
+            module_path(Path::new("crates/core/src/index/mod.rs"), "rust").as_deref(),
+            Some("crate::index")
+        );
+        assert_eq!(
+            module_path(Path::new("src/lib.rs"), "rust").as_deref(),
+            Some("crate")
+        );
+        assert_eq!(module_path(Path::new("build.rs"), "rust"), None);
+        assert_eq!(
+            module_path(Path::new("pkg/__init__.py"), "python").as_deref(),
+            Some("pkg")
+        );
+        assert_eq!(module_path(Path::new("main.go"), "go"), None);
+    }
+
     #[test]
     fn test_symbol_kind_display() {
         assert_eq!(SymbolKind::Function.to_string(), "function");
@@ -4,11 +4,18 @@
 
 use anyhow::{Context, Result};
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use crate::config::Config;
 use crate::indexer::scanner::FileScanner;
-use cgrep::output::print_json;
+use crate::output::print_json;
+use crate::query::result_ids::{
+    anchored_id, content_hash, FileAnchors, ResultIdStore, ResultLocation,
+};
+use crate::query::scope::{ContextScope, ScopedContext, SymbolSpans};
+use crate::session::{delivered_ranges, AlreadySent, SessionTracker};
+use crate::utils::find_index_root;
 
 #[derive(Debug, Serialize)]
 struct AgentExpandMeta {
@@ -16,14 +23,21 @@ struct AgentExpandMeta {
     stage: &'static str,
     requested_ids: usize,
     resolved_ids: usize,
+    unresolved_ids: usize,
     context: usize,
+    scope: ContextScope,
     search_root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct AgentExpandResult {
     id: String,
     path: String,
+    /// Qualified name of the symbol the ID is anchored to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
     line: usize,
     start_line: usize,
     end_line: usize,
@@ -34,67 +48,310 @@ struct AgentExpandResult {
     context_after: Vec<String>,
 }
 
+/// Why an ID could not be expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedReason {
+    /// Never emitted by a search here, or expired from the store
+    Unknown,
+    /// The file it pointed into no longer exists
+    Deleted,
+    /// The file was edited and the result line is gone
+    Changed,
+}
+
+#[derive(Debug, Serialize)]
+struct UnresolvedId {
+    id: String,
+    reason: UnresolvedReason,
+}
+
 #[derive(Debug, Serialize)]
-struct AgentExpandPayload {
+pub struct AgentExpandPayload {
     meta: AgentExpandMeta,
     results: Vec<AgentExpandResult>,
+    unresolved: Vec<UnresolvedId>,
+    /// IDs whose windows the session already received
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    already_sent: Vec<AlreadySent>,
 }
 
-/// Expand stable result IDs into richer context windows for agent workflows.
-pub fn run_expand(ids: &[String], path: Option<&str>, context: usize, compact: bool) -> Result<()> {
-    let search_root = resolve_search_root(path)?;
-    let scanner = FileScanner::new(&search_root);
+/// Resolve stable result IDs under `search_root` into context windows.
+///
+/// IDs recorded by an earlier json2 search are looked up directly and only
+/// their files are read; without an index the tree is scanned instead.
+/// `scope` widens the window to the enclosing symbol where there is one.
+/// With a `session`, windows it already received are returned as references.
+pub fn expand(
+    ids: &[String],
+    search_root: &Path,
+    context: usize,
+    scope: ContextScope,
+    session: Option<&str>,
+) -> Result<AgentExpandPayload> {
+    let mut wanted: Vec<&str> = Vec::new();
+    let mut seen = HashSet::new();
+    for id in ids {
+        if seen.insert(id.as_str()) {
+            wanted.push(id);
+        }
+    }
+
+    let index_root = find_index_root(search_root).map(|found| found.root);
+    let store = match &index_root {
+        Some(root) => ResultIdStore::open_existing(root)?,
+        None => None,
+    };
+    let (mut results, unresolved) = match (store, &index_root) {
+        (Some(store), Some(index_root)) => {
+            expand_recorded(&store, &wanted, index_root, search_root, context, scope)?
+        }
+        _ => expand_by_scan(&wanted, search_root, context, scope)?,
+    };
+
+    results.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    let resolved_ids = results.len();
+
+    let mut already_sent = Vec::new();
+    if let Some(session_id) = session {
+        let index_root = index_root.as_deref().unwrap_or(search_root);
+        let ttl_ms = Config::load_for_dir(index_root).cache.session_ttl_ms();
+        let mut tracker = SessionTracker::open(search_root, index_root, session_id, ttl_ms)?;
+        results.retain(|result| {
+            let ranges = delivered_ranges(
+                result.line,
+                result.start_line,
+                result.context_before.len(),
+                result.context_after.len(),
+            );
+            match tracker.check(Some(&result.id), &result.path, result.line, ranges) {
+                Some(sent) => {
+                    already_sent.push(sent);
+                    false
+                }
+                None => true,
+            }
+        });
+        tracker.finish()?;
+    }
+
+    Ok(AgentExpandPayload {
+        meta: AgentExpandMeta {
+            schema_version: "1",
+            stage: "expand",
+            requested_ids: wanted.len(),
+            resolved_ids,
+            unresolved_ids: unresolved.len(),
+            context,
+            scope,
+            search_root: search_root.display().to_string(),
+            session: session.map(str::to_string),
+        },
+        results,
+        unresolved,
+        already_sent,
+    })
+}
+
+/// Resolve IDs through the result ID store, reading each referenced file once
+fn expand_recorded(
+    store: &ResultIdStore,
+    ids: &[&str],
+    index_root: &Path,
+    search_root: &Path,
+    context: usize,
+    scope: ContextScope,
+) -> Result<(Vec<AgentExpandResult>, Vec<UnresolvedId>)> {
+    let mut located: Vec<(&str, Option<ResultLocation>)> = Vec::with_capacity(ids.len());
+    let mut files: HashMap<String, Option<String>> = HashMap::new();
+    for id in ids {
+        let location = store.get(id)?;
+        if let Some(location) = &location {
+            files
+                .entry(location.path.clone())
+                .or_insert_with(|| std::fs::read_to_string(index_root.join(&location.path)).ok());
+        }
+        located.push((id, location));
+    }
+
+    // Edited files are parsed once to find their recorded lines again
+    let mut anchors: HashMap<&str, FileAnchors<'_>> = HashMap::new();
+    for (_, location) in &located {
+        let Some(location) = location else {
+            continue;
+        };
+        if let Some(Some(content)) = files.get(&location.path) {
+            if content_hash(content.as_bytes()) != location.file_hash {
+                anchors
+                    .entry(location.path.as_str())
+                    .or_insert_with(|| FileAnchors::parse(Path::new(&location.path), content));
+            }
+        }
+    }
+
+    let mut spans: HashMap<&str, SymbolSpans> = HashMap::new();
+    let mut results = Vec::new();
+    let mut unresolved = Vec::new();
+    for (id, location) in &located {
+        let unresolved_as = |reason| UnresolvedId {
+            id: id.to_string(),
+            reason,
+        };
+        let Some(location) = location else {
+            unresolved.push(unresolved_as(UnresolvedReason::Unknown));
+            continue;
+        };
+        let Some(Some(content)) = files.get(&location.path) else {
+            unresolved.push(unresolved_as(UnresolvedReason::Deleted));
+            continue;
+        };
+        let line = match anchors.get(location.path.as_str()) {
+            None => Some(location.line),
+            Some(file) => file.find(
+                location.anchor.symbol.as_deref(),
+                &location.line_hash,
+                location.anchor.occurrence,
+            ),
+        };
+        let Some(line) = line else {
+            unresolved.push(unresolved_as(UnresolvedReason::Changed));
+            continue;
+        };
+
+        let display_path = index_root
+            .join(&location.path)
+            .strip_prefix(search_root)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| location.path.clone());
+        let lines: Vec<&str> = content.lines().collect();
+        let scoped = (scope != ContextScope::Lines).then(|| {
+            let spans = spans
+                .entry(location.path.as_str())
+                .or_insert_with(|| SymbolSpans::parse(Path::new(&location.path), content));
+            (&*spans, scope)
+        });
+        results.push(expand_line(
+            id,
+            display_path,
+            location.anchor.symbol.clone(),
+            &lines,
+            line,
+            context,
+            scoped,
+        ));
+    }
+
+    Ok((results, unresolved))
+}
+
+/// Expand one line; `scoped` returns its enclosing symbol instead of the
+/// `context` window when there is one
+fn expand_line(
+    id: &str,
+    path: String,
+    symbol: Option<String>,
+    lines: &[&str],
+    line_num: usize,
+    context: usize,
+    scoped: Option<(&SymbolSpans, ContextScope)>,
+) -> AgentExpandResult {
+    let scoped = scoped.and_then(|(spans, scope)| {
+        let span = spans.enclosing(line_num)?;
+        ScopedContext::new(lines, line_num, span, scope)
+    });
+    let (start_line, context_before, context_after) = match scoped {
+        Some(scoped) => (scoped.start_line, scoped.before, scoped.after),
+        None => {
+            let (before, after) = context_from_lines(lines, line_num, context);
+            (line_num.saturating_sub(before.len()), before, after)
+        }
+    };
+    AgentExpandResult {
+        id: id.to_string(),
+        path,
+        symbol,
+        line: line_num,
+        start_line,
+        end_line: line_num + context_after.len(),
+        snippet: line_to_snippet(lines[line_num - 1]),
+        context_before,
+        context_after,
+    }
+}
+
+/// Anchor every line under `search_root` looking for the IDs (no index to ask)
+fn expand_by_scan(
+    ids: &[&str],
+    search_root: &Path,
+    context: usize,
+    scope: ContextScope,
+) -> Result<(Vec<AgentExpandResult>, Vec<UnresolvedId>)> {
+    let scanner = FileScanner::new(search_root);
     let files = scanner.scan()?;
-    let wanted: HashSet<String> = ids.iter().cloned().collect();
+    let wanted: HashSet<&str> = ids.iter().copied().collect();
+    let mut found: HashSet<String> = HashSet::new();
     let mut results: Vec<AgentExpandResult> = Vec::new();
 
     for file in files {
         let rel_path = file
             .path
-            .strip_prefix(&search_root)
+            .strip_prefix(search_root)
             .unwrap_or(&file.path)
-            .display()
-            .to_string();
+            .to_string_lossy()
+            .replace('\\', "/");
 
+        let anchors = FileAnchors::parse(Path::new(&rel_path), &file.content);
         let lines: Vec<&str> = file.content.lines().collect();
+        let mut spans: Option<SymbolSpans> = None;
         for (idx, line) in lines.iter().enumerate() {
             let line_num = idx + 1;
-            let snippet = line_to_snippet(line);
-            let id = stable_result_id(&rel_path, line_num, &snippet);
-            if !wanted.contains(&id) {
+            let Some(anchor) = anchors.anchor(line_num) else {
+                continue;
+            };
+            let id = anchored_id(&rel_path, &anchor, line);
+            if !wanted.contains(id.as_str()) {
                 continue;
             }
-
-            let (context_before, context_after) = context_from_lines(&lines, line_num, context);
-            let start_line = line_num.saturating_sub(context_before.len());
-            let end_line = line_num + context_after.len();
-
-            results.push(AgentExpandResult {
-                id,
-                path: rel_path.clone(),
-                line: line_num,
-                start_line,
-                end_line,
-                snippet,
-                context_before,
-                context_after,
+            let scoped = (scope != ContextScope::Lines).then(|| {
+                let spans = spans
+                    .get_or_insert_with(|| SymbolSpans::parse(Path::new(&rel_path), &file.content));
+                (&*spans, scope)
             });
+            results.push(expand_line(
+                &id,
+                rel_path.clone(),
+                anchor.symbol,
+                &lines,
+                line_num,
+                context,
+                scoped,
+            ));
+            found.insert(id);
         }
     }
 
-    results.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    let unresolved = ids
+        .iter()
+        .filter(|id| !found.contains(**id))
+        .map(|id| UnresolvedId {
+            id: id.to_string(),
+            reason: UnresolvedReason::Unknown,
+        })
+        .collect();
+    Ok((results, unresolved))
+}
 
-    let payload = AgentExpandPayload {
-        meta: AgentExpandMeta {
-            schema_version: "1",
-            stage: "expand",
-            requested_ids: wanted.len(),
-            resolved_ids: results.len(),
-            context,
-            search_root: search_root.display().to_string(),
-        },
-        results,
-    };
+/// Expand stable result IDs into richer context windows for agent workflows.
+pub fn run_expand(
+    ids: &[String],
+    path: Option<&str>,
+    context: usize,
+    scope: ContextScope,
+    session: Option<&str>,
+    compact: bool,
+) -> Result<()> {
+    let search_root = resolve_search_root(path)?;
+    let payload = expand(ids, &search_root, context, scope, session)?;
     print_json(&payload, compact)?;
 
     Ok(())
@@ -142,12 +399,6 @@ fn line_to_snippet(line: &str) -> String {
     }
 }
 
-fn stable_result_id(path: &str, line: usize, snippet: &str) -> String {
-    let payload = format!("{}:{}:{}", path, line, snippet);
-    let hash = blake3::hash(payload.as_bytes());
-    hash.to_hex()[..16].to_string()
-}
-
 fn context_from_lines(
     lines: &[&str],
     line_num: usize,
@@ -176,12 +427,87 @@ fn context_from_lines(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::indexer::index::{IndexBuilder, DEFAULT_WRITER_BUDGET_BYTES};
+    use crate::output::OutputFormat;
+    use crate::query::search::{self, SearchOptions};
+    use tempfile::TempDir;
+
+    fn locate_ids(root: &Path, query: &str) -> Vec<(String, String)> {
+        let options = SearchOptions {
+            query: query.to_string(),
+            path: Some(root.display().to_string()),
+            max_results: 10,
+            ..SearchOptions::default()
+        };
+        let report = search::execute(&options, OutputFormat::Json2).expect("search");
+        let payload = report.to_json2(&options, false).expect("json2");
+        payload["results"]
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|r| {
+                (
+                    r["path"].as_str().expect("path").to_string(),
+                    r["id"].as_str().expect("id").to_string(),
+                )
+            })
+            .collect()
+    }
 
     #[test]
-    fn stable_result_id_is_deterministic() {
-        let a = stable_result_id("src/lib.rs", 10, "fn alpha() {}");
-        let b = stable_result_id("src/lib.rs", 10, "fn alpha() {}");
-        assert_eq!(a, b);
-        assert_eq!(a.len(), 16);
+    fn expand_follows_edited_files_and_reports_stale_ids() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        std::fs::write(root.join("a.rs"), "fn keep() {}\n\nfn expand_me() {}\n").expect("write a");
+        std::fs::write(root.join("b.rs"), "fn expand_me_too() {}\n").expect("write b");
+        std::fs::write(root.join("c.rs"), "// expand_me here\n").expect("write c");
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("index");
+
+        let ids: HashMap<String, String> = locate_ids(root, "expand_me").into_iter().collect();
+        assert_eq!(ids.len(), 3, "{:?}", ids);
+        assert!(ResultIdStore::path_for(root).exists());
+
+        // Lines added above a result move it; rewriting the line itself does not resolve
+        std::fs::write(
+            root.join("a.rs"),
+            "use std::fmt;\n\nfn keep() {}\n\nfn expand_me() {}\nfn new() {}\n",
+        )
+        .expect("edit a");
+        std::fs::write(root.join("b.rs"), "fn expand_me_too(x: u32) {}\n").expect("edit b");
+        std::fs::remove_file(root.join("c.rs")).expect("remove c");
+
+        let requested = vec![
+            ids["a.rs"].clone(),
+            ids["b.rs"].clone(),
+            ids["c.rs"].clone(),
+            "0123456789abcdef".to_string(),
+            ids["a.rs"].clone(),
+        ];
+        let payload = expand(&requested, root, 1, ContextScope::Lines, None).expect("expand");
+
+        assert_eq!(payload.meta.requested_ids, 4);
+        assert_eq!(payload.results.len(), 1);
+        assert_eq!(payload.results[0].path, "a.rs");
+        assert_eq!(payload.results[0].line, 5);
+        assert_eq!(payload.results[0].snippet, "fn expand_me() {}");
+        assert_eq!(payload.results[0].context_after, vec!["fn new() {}"]);
+
+        let reasons: Vec<(&str, UnresolvedReason)> = payload
+            .unresolved
+            .iter()
+            .map(|u| (u.id.as_str(), u.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (ids["b.rs"].as_str(), UnresolvedReason::Changed),
+                (ids["c.rs"].as_str(), UnresolvedReason::Deleted),
+                ("0123456789abcdef", UnresolvedReason::Unknown),
+            ]
+        );
     }
 }
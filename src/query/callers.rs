@@ -6,68 +6,87 @@ use anyhow::Result;
 use colored::Colorize;
 use regex::Regex;
 use serde::Serialize;
+use std::path::Path;
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::FileScanner;
+use crate::indexer::scanner::{FileScanner, ScannedFile};
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::parser::calls::CallExtractor;
+use crate::parser::languages::LANGUAGES;
 use crate::query::index_filter::{find_files_with_content, read_scanned_files};
-use cgrep::output::print_json;
-use cgrep::utils::get_root_with_index;
+use crate::utils::get_root_with_index;
 
 /// Caller result for JSON output
 #[derive(Debug, Serialize)]
-struct CallerResult {
-    path: String,
-    line: usize,
-    code: String,
+pub struct CallerResult {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub code: String,
+    /// Enclosing function of the call site
+    pub caller: Option<String>,
+    /// Call made on a receiver (`obj.f()`) rather than a bare call (`f()`)
+    pub is_method: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<String>,
 }
 
-/// Run the callers command
-pub fn run(function: &str, format: OutputFormat, compact: bool) -> Result<()> {
-    let root = get_root_with_index(std::env::current_dir()?);
-    let files = match find_files_with_content(&root, function)? {
+/// Call sites of `function` under `root`
+pub fn find(root: &Path, function: &str) -> Result<Vec<CallerResult>> {
+    let files = match find_files_with_content(root, function)? {
         Some(indexed_paths) => read_scanned_files(&indexed_paths),
         None => {
-            let scanner = FileScanner::new(&root);
+            let scanner = FileScanner::new(root);
             scanner.scan()?
         }
     };
 
-    // Pattern to match function calls
-    // Matches: functionName( or object.functionName( or object?.functionName(
-    let pattern = format!(r"\b{}\s*\(", regex::escape(function));
-    let re = Regex::new(&pattern)?;
-
+    let extractor = CallExtractor::new();
     let mut results: Vec<CallerResult> = Vec::new();
 
     for file in &files {
         let rel_path = file
             .path
-            .strip_prefix(&root)
+            .strip_prefix(root)
             .unwrap_or(&file.path)
             .display()
             .to_string();
+        let lines: Vec<&str> = file.content.lines().collect();
 
-        for (line_num, line) in file.content.lines().enumerate() {
-            if re.is_match(line) {
-                // Skip definition lines (function declarations)
-                let line_lower = line.to_lowercase();
-                if line_lower.contains("function ")
-                    || line_lower.contains("fn ")
-                    || line_lower.contains("def ")
-                    || line_lower.contains("func ")
-                {
-                    continue;
-                }
+        let calls = file
+            .language
+            .as_deref()
+            .filter(|lang| LANGUAGES.get(lang).is_some())
+            .and_then(|lang| extractor.extract(&file.content, lang).ok());
+        let Some(calls) = calls else {
+            results.extend(regex_call_sites(file, &rel_path, function)?);
+            continue;
+        };
 
-                results.push(CallerResult {
-                    path: rel_path.clone(),
-                    line: line_num + 1,
-                    code: line.trim().to_string(),
-                });
-            }
+        for call in calls.into_iter().filter(|call| call.callee == function) {
+            results.push(CallerResult {
+                path: rel_path.clone(),
+                line: call.line,
+                column: call.column,
+                code: lines
+                    .get(call.line.saturating_sub(1))
+                    .map(|line| line.trim().to_string())
+                    .unwrap_or_default(),
+                caller: call.caller,
+                is_method: call.is_method,
+                receiver: call.receiver,
+            });
         }
     }
 
+    Ok(results)
+}
+
+/// Run the callers command
+pub fn run(function: &str, format: OutputFormat, compact: bool) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let results = find(&root, function)?;
+
     match format {
         OutputFormat::Json | OutputFormat::Json2 => {
             print_json(&results, compact)?;
@@ -82,10 +101,14 @@ pub fn run(function: &str, format: OutputFormat, compact: bool) -> Result<()> {
                     function.yellow()
                 );
                 for result in &results {
+                    let caller = result.caller.as_deref().unwrap_or("<top-level>");
+                    let call_kind = if result.is_method { "method" } else { "call" };
                     println!(
-                        "  {}:{} {}",
+                        "  {}:{} {} [{}] {}",
                         result.path.cyan(),
                         result.line.to_string().yellow(),
+                        caller.green(),
+                        call_kind.blue(),
                         result.code.dimmed()
                     );
                 }
@@ -100,3 +123,42 @@ pub fn run(function: &str, format: OutputFormat, compact: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Fallback for languages without a tree-sitter grammar: match `name(` on raw lines
+fn regex_call_sites(
+    file: &ScannedFile,
+    rel_path: &str,
+    function: &str,
+) -> Result<Vec<CallerResult>> {
+    // Matches: functionName( or object.functionName( or object?.functionName(
+    let pattern = format!(r"\b{}\s*\(", regex::escape(function));
+    let re = Regex::new(&pattern)?;
+
+    let mut results = Vec::new();
+    for (line_num, line) in file.content.lines().enumerate() {
+        let Some(found) = re.find(line) else {
+            continue;
+        };
+        // Skip definition lines (function declarations)
+        let line_lower = line.to_lowercase();
+        if line_lower.contains("function ")
+            || line_lower.contains("fn ")
+            || line_lower.contains("def ")
+            || line_lower.contains("func ")
+        {
+            continue;
+        }
+
+        let prefix = line[..found.start()].trim_end();
+        results.push(CallerResult {
+            path: rel_path.to_string(),
+            line: line_num + 1,
+            column: line[..found.start()].chars().count() + 1,
+            code: line.trim().to_string(),
+            caller: None,
+            is_method: prefix.ends_with('.') || prefix.ends_with("->"),
+            receiver: None,
+        });
+    }
+    Ok(results)
+}
@@ -0,0 +1,526 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Transitive call graph built from AST call sites
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::indexer::scanner::{detect_language, FileScanner};
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::parser::calls::{CallExtractor, CallSite};
+use crate::parser::languages::LANGUAGES;
+use crate::query::graph::{render, GraphFormat};
+use crate::query::index_filter::{find_exact_symbol_records, find_files_with_content};
+use crate::utils::get_root_with_index;
+
+/// Pseudo-caller used for calls made outside any function
+const TOP_LEVEL: &str = "<top-level>";
+
+/// Traversal direction for call graphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CallDirection {
+    /// Follow callers (who reaches this function)
+    #[default]
+    Up,
+    /// Follow callees (what this function reaches)
+    Down,
+}
+
+/// A caller -> callee edge (always in call direction)
+#[derive(Debug, Clone, Serialize)]
+struct CallEdge {
+    from: String,
+    to: String,
+    /// First call site of this edge
+    path: String,
+    line: usize,
+    /// Number of call sites collapsed into this edge
+    calls: usize,
+}
+
+/// Call graph result for JSON output
+#[derive(Debug, Serialize)]
+pub struct CallGraph {
+    root: String,
+    direction: &'static str,
+    depth: usize,
+    nodes: Vec<String>,
+    edges: Vec<CallEdge>,
+    /// Caller -> callees adjacency list
+    adjacency: BTreeMap<String, Vec<String>>,
+    cycles: Vec<Vec<String>>,
+    /// True when the node budget stopped the traversal early
+    truncated: bool,
+}
+
+/// Neighbor of a node in traversal direction, with the call location
+struct Neighbor {
+    name: String,
+    path: String,
+    line: usize,
+}
+
+/// Lazily parsed call sites, cached per file
+struct CallSites {
+    root: PathBuf,
+    extractor: CallExtractor,
+    parsed: HashMap<PathBuf, Vec<CallSite>>,
+    all_files: Option<Vec<PathBuf>>,
+}
+
+impl CallSites {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            extractor: CallExtractor::new(),
+            parsed: HashMap::new(),
+            all_files: None,
+        }
+    }
+
+    /// Files that may mention `name`, narrowed through the index when present
+    fn files_mentioning(&mut self, name: &str) -> Result<Vec<PathBuf>> {
+        if let Some(paths) = find_files_with_content(&self.root, name)? {
+            return Ok(paths);
+        }
+        if self.all_files.is_none() {
+            let mut files = FileScanner::new(&self.root).list_files()?;
+            files.sort();
+            self.all_files = Some(files);
+        }
+        Ok(self.all_files.clone().unwrap_or_default())
+    }
+
+    fn calls_in(&mut self, path: &Path) -> &[CallSite] {
+        let extractor = &self.extractor;
+        self.parsed.entry(path.to_path_buf()).or_insert_with(|| {
+            let language = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(detect_language)
+                .filter(|lang| LANGUAGES.get(lang).is_some());
+            let (Some(language), Ok(content)) = (language, std::fs::read_to_string(path)) else {
+                return Vec::new();
+            };
+            extractor.extract(&content, &language).unwrap_or_default()
+        })
+    }
+
+    fn rel_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Functions that call `name`
+    fn callers_of(&mut self, name: &str) -> Result<Vec<Neighbor>> {
+        let mut neighbors = Vec::new();
+        for path in self.files_mentioning(name)? {
+            let rel_path = self.rel_path(&path);
+            for call in self.calls_in(&path) {
+                if call.callee != name {
+                    continue;
+                }
+                let caller = call
+                    .caller
+                    .clone()
+                    .unwrap_or_else(|| format!("{} {}", TOP_LEVEL, rel_path));
+                neighbors.push(Neighbor {
+                    name: caller,
+                    path: rel_path.clone(),
+                    line: call.line,
+                });
+            }
+        }
+        Ok(neighbors)
+    }
+
+    /// Functions called from the body of every definition of `name`
+    fn callees_of(&mut self, name: &str) -> Result<Vec<Neighbor>> {
+        let definitions = find_exact_symbol_records(&self.root, name)?
+            .into_iter()
+            .filter(|record| {
+                record.name == name && matches!(record.kind.as_str(), "function" | "method")
+            });
+
+        let mut neighbors = Vec::new();
+        for definition in definitions {
+            let path = self.root.join(&definition.path);
+            let rel_path = self.rel_path(&path);
+            let range = definition.byte_start.zip(definition.byte_end);
+            for call in self.calls_in(&path) {
+                let inside = match range {
+                    Some((start, end)) => call.byte_start >= start && call.byte_start < end,
+                    None => call.caller.as_deref() == Some(name),
+                };
+                if inside {
+                    neighbors.push(Neighbor {
+                        name: call.callee.clone(),
+                        path: rel_path.clone(),
+                        line: call.line,
+                    });
+                }
+            }
+        }
+        Ok(neighbors)
+    }
+}
+
+/// Breadth-first traversal bounded by depth and node budget
+pub fn build_graph(
+    root: &Path,
+    function: &str,
+    depth: usize,
+    direction: CallDirection,
+    max_nodes: usize,
+) -> Result<CallGraph> {
+    let mut sites = CallSites::new(root);
+    let mut depth_of: HashMap<String, usize> = HashMap::new();
+    let mut nodes = vec![function.to_string()];
+    let mut edges: Vec<CallEdge> = Vec::new();
+    let mut edge_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut truncated = false;
+    let mut queue = VecDeque::new();
+
+    depth_of.insert(function.to_string(), 0);
+    queue.push_back(function.to_string());
+
+    while let Some(node) = queue.pop_front() {
+        let node_depth = depth_of[&node];
+        if node_depth >= depth || node.starts_with(TOP_LEVEL) {
+            continue;
+        }
+
+        let neighbors = match direction {
+            CallDirection::Up => sites.callers_of(&node)?,
+            CallDirection::Down => sites.callees_of(&node)?,
+        };
+
+        for neighbor in neighbors {
+            if !depth_of.contains_key(&neighbor.name) {
+                if nodes.len() >= max_nodes {
+                    truncated = true;
+                    continue;
+                }
+                depth_of.insert(neighbor.name.clone(), node_depth + 1);
+                nodes.push(neighbor.name.clone());
+                queue.push_back(neighbor.name.clone());
+            }
+
+            let (from, to) = match direction {
+                CallDirection::Up => (neighbor.name, node.clone()),
+                CallDirection::Down => (node.clone(), neighbor.name),
+            };
+            let key = (from.clone(), to.clone());
+            if let Some(&idx) = edge_index.get(&key) {
+                edges[idx].calls += 1;
+            } else {
+                edge_index.insert(key, edges.len());
+                edges.push(CallEdge {
+                    from,
+                    to,
+                    path: neighbor.path,
+                    line: neighbor.line,
+                    calls: 1,
+                });
+            }
+        }
+    }
+
+    let mut adjacency: BTreeMap<String, Vec<String>> = nodes
+        .iter()
+        .map(|node| (node.clone(), Vec::new()))
+        .collect();
+    for edge in &edges {
+        if let Some(callees) = adjacency.get_mut(&edge.from) {
+            callees.push(edge.to.clone());
+        }
+    }
+    for callees in adjacency.values_mut() {
+        callees.sort();
+        callees.dedup();
+    }
+
+    let mut graph = CallGraph {
+        root: function.to_string(),
+        direction: match direction {
+            CallDirection::Up => "up",
+            CallDirection::Down => "down",
+        },
+        depth,
+        nodes,
+        edges,
+        adjacency,
+        cycles: Vec::new(),
+        truncated,
+    };
+    graph.cycles = find_cycles(&graph);
+    Ok(graph)
+}
+
+/// Children of each node in traversal direction, with the linking edge
+fn tree_children(graph: &CallGraph) -> HashMap<&str, Vec<&CallEdge>> {
+    let mut children: HashMap<&str, Vec<&CallEdge>> = HashMap::new();
+    for edge in &graph.edges {
+        let parent = if graph.direction == "up" {
+            edge.to.as_str()
+        } else {
+            edge.from.as_str()
+        };
+        children.entry(parent).or_default().push(edge);
+    }
+    children
+}
+
+fn child_name<'a>(graph: &CallGraph, edge: &'a CallEdge) -> &'a str {
+    if graph.direction == "up" {
+        &edge.from
+    } else {
+        &edge.to
+    }
+}
+
+/// Report each cycle reachable from the root once, in traversal order
+fn find_cycles(graph: &CallGraph) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        node: &'a str,
+        graph: &'a CallGraph,
+        children: &HashMap<&'a str, Vec<&'a CallEdge>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        stack.push(node);
+        for edge in children.get(node).into_iter().flatten() {
+            let child = child_name(graph, edge);
+            if let Some(pos) = stack.iter().position(|n| *n == child) {
+                let mut cycle: Vec<String> = stack[pos..].iter().map(|n| n.to_string()).collect();
+                cycle.push(child.to_string());
+                cycles.push(cycle);
+            } else if !done.contains(child) {
+                visit(child, graph, children, stack, done, cycles);
+            }
+        }
+        stack.pop();
+        done.insert(node);
+    }
+
+    let children = tree_children(graph);
+    let mut cycles = Vec::new();
+    visit(
+        &graph.root,
+        graph,
+        &children,
+        &mut Vec::new(),
+        &mut HashSet::new(),
+        &mut cycles,
+    );
+    cycles
+}
+
+fn print_tree(graph: &CallGraph) {
+    fn visit<'a>(
+        node: &'a str,
+        prefix: &str,
+        graph: &'a CallGraph,
+        children: &HashMap<&'a str, Vec<&'a CallEdge>>,
+        stack: &mut Vec<&'a str>,
+        expanded: &mut HashSet<&'a str>,
+    ) {
+        stack.push(node);
+        expanded.insert(node);
+        let edges = children.get(node).map(Vec::as_slice).unwrap_or_default();
+        for (i, edge) in edges.iter().enumerate() {
+            let last = i + 1 == edges.len();
+            let child = child_name(graph, edge);
+            let branch = if last { "└── " } else { "├── " };
+            let location = format!("{}:{}", edge.path, edge.line);
+            let marker = if stack.contains(&child) {
+                format!(" {}", "(cycle)".red())
+            } else if expanded.contains(child) && children.contains_key(child) {
+                format!(" {}", "(see above)".dimmed())
+            } else {
+                String::new()
+            };
+            println!(
+                "{}{}{}  {}{}",
+                prefix,
+                branch,
+                child.green(),
+                location.cyan(),
+                marker
+            );
+            if marker.is_empty() {
+                let next_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                visit(child, &next_prefix, graph, children, stack, expanded);
+            }
+        }
+        stack.pop();
+    }
+
+    let children = tree_children(graph);
+    let label = if graph.direction == "up" {
+        "callers"
+    } else {
+        "callees"
+    };
+    println!(
+        "\n{} {} ({}, depth {})\n",
+        "🔍".cyan(),
+        graph.root.yellow(),
+        label,
+        graph.depth
+    );
+    println!("{}", graph.root.yellow());
+    visit(
+        &graph.root,
+        "",
+        graph,
+        &children,
+        &mut Vec::new(),
+        &mut HashSet::new(),
+    );
+
+    println!(
+        "\n{} {} nodes, {} edges",
+        "✓".green(),
+        graph.nodes.len().to_string().cyan(),
+        graph.edges.len().to_string().cyan()
+    );
+    if !graph.cycles.is_empty() {
+        println!("{} {} cycles detected", "↻".red(), graph.cycles.len());
+    }
+    if graph.truncated {
+        println!(
+            "{} node budget reached; raise --max-nodes to see more",
+            "…".yellow()
+        );
+    }
+}
+
+fn render_graph(graph: &CallGraph, format: GraphFormat) -> String {
+    let nodes: Vec<&str> = graph.nodes.iter().map(String::as_str).collect();
+    let edges: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+    render(format, "callgraph", Some(&graph.root), &nodes, &edges)
+}
+
+/// Run the callgraph command
+pub fn run(
+    function: &str,
+    depth: usize,
+    direction: CallDirection,
+    max_nodes: usize,
+    graph_format: Option<GraphFormat>,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let graph = build_graph(&root, function, depth, direction, max_nodes.max(1))?;
+
+    if let Some(graph_format) = graph_format {
+        print!("{}", render_graph(&graph, graph_format));
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Json | OutputFormat::Json2 => {
+            print_json(&graph, compact)?;
+        }
+        OutputFormat::Text => print_tree(&graph),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, name: &str, content: &str) {
+        std::fs::write(root.join(name), content).expect("write file");
+    }
+
+    fn edge_pairs(graph: &CallGraph) -> Vec<(String, String)> {
+        let mut pairs: Vec<(String, String)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.clone(), e.to.clone()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn upward_graph_follows_callers_transitively() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        write(
+            root,
+            "lib.rs",
+            "fn leaf() {}\nfn mid() { leaf(); }\nfn top() { mid(); mid(); }\n",
+        );
+
+        let graph = build_graph(root, "leaf", 5, CallDirection::Up, 100).expect("graph");
+        assert_eq!(graph.nodes, vec!["leaf", "mid", "top"]);
+        assert_eq!(
+            edge_pairs(&graph),
+            vec![
+                ("mid".to_string(), "leaf".to_string()),
+                ("top".to_string(), "mid".to_string())
+            ]
+        );
+        let top_edge = graph.edges.iter().find(|e| e.from == "top").expect("edge");
+        assert_eq!(top_edge.calls, 2);
+        assert_eq!(graph.adjacency["mid"], vec!["leaf"]);
+
+        let shallow = build_graph(root, "leaf", 1, CallDirection::Up, 100).expect("graph");
+        assert_eq!(shallow.nodes, vec!["leaf", "mid"]);
+    }
+
+    #[test]
+    fn downward_graph_detects_cycles() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        write(
+            root,
+            "cycle.py",
+            "def ping(n):\n    pong(n)\n\ndef pong(n):\n    ping(n - 1)\n    log(n)\n",
+        );
+
+        let graph = build_graph(root, "ping", 5, CallDirection::Down, 100).expect("graph");
+        assert_eq!(graph.nodes, vec!["ping", "pong", "log"]);
+        assert_eq!(graph.cycles, vec![vec!["ping", "pong", "ping"]]);
+        assert!(!graph.truncated);
+
+        let dot = render_graph(&graph, GraphFormat::Dot);
+        assert!(dot.contains("\"pong\" -> \"ping\";"));
+        let mermaid = render_graph(&graph, GraphFormat::Mermaid);
+        assert!(mermaid.contains("n1 --> n0"));
+    }
+
+    #[test]
+    fn node_budget_truncates_graph() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        write(
+            root,
+            "fan.js",
+            "function a() { target(); }\nfunction b() { target(); }\nfunction c() { target(); }\n",
+        );
+
+        let graph = build_graph(root, "target", 3, CallDirection::Up, 2).expect("graph");
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.truncated);
+    }
+}
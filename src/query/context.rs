@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! One-shot agent context: locate, then expand the best hits within a budget.
+//!
+//! `cgrep agent context "<task>"` runs the `agent locate` search and walks its
+//! hits in rank order, adding each hit's enclosing symbol until the token
+//! budget is spent. A symbol that does not fit shrinks to its signature plus
+//! a few lines around the hit, then to the lines alone. Overlapping ranges in
+//! a file are merged by [`ContextPackBuilder`]; hits that still do not fit are
+//! listed in `meta.dropped` with their result IDs for `agent expand`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::daemon;
+use crate::hybrid::ContextPackBuilder;
+use crate::output::{print_json, OutputFormat};
+use crate::query::scope::SymbolSpans;
+use crate::query::search::{self, resolve_search_root, SearchOptions};
+use crate::tokens::count_tokens;
+
+/// Token budget when none is given
+pub const DEFAULT_BUDGET_TOKENS: usize = 4_000;
+
+/// Lines kept on each side of a hit whose symbol does not fit
+const FALLBACK_LINES: usize = 3;
+
+#[derive(Debug, Serialize)]
+struct AgentContextMeta {
+    schema_version: &'static str,
+    stage: &'static str,
+    query: String,
+    search_mode: String,
+    budget_tokens: usize,
+    tokens_used: usize,
+    candidates: usize,
+    included: usize,
+    elapsed_ms: f64,
+    search_root: String,
+    /// Hits left out, best first
+    dropped: Vec<DroppedHit>,
+}
+
+/// Why a hit was left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum DropReason {
+    /// Even its smallest range would exceed the remaining budget
+    Budget,
+    /// Its file could not be read
+    Unreadable,
+}
+
+#[derive(Debug, Serialize)]
+struct DroppedHit {
+    id: String,
+    path: String,
+    line: usize,
+    /// Tokens its smallest range would have added
+    tokens: usize,
+    reason: DropReason,
+}
+
+#[derive(Debug, Serialize)]
+struct ContextFile {
+    path: String,
+    blocks: Vec<ContextFileBlock>,
+}
+
+#[derive(Debug, Serialize)]
+struct ContextFileBlock {
+    start_line: usize,
+    end_line: usize,
+    /// Result IDs of the hits inside this block
+    ids: Vec<String>,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentContextPayload {
+    meta: AgentContextMeta,
+    results: Vec<ContextFile>,
+}
+
+/// Ranges picked so far in one file
+struct FileSelection {
+    content: String,
+    spans: Option<SymbolSpans>,
+    ranges: Vec<(usize, usize)>,
+    /// Tokens of the packed ranges, path included
+    tokens: usize,
+    hits: Vec<(String, usize)>,
+}
+
+impl FileSelection {
+    /// Tokens the file would cost with `ranges` packed
+    fn cost(&self, path: &str, ranges: &[(usize, usize)]) -> usize {
+        if ranges.is_empty() {
+            return 0;
+        }
+        let lines: Vec<&str> = self.content.lines().collect();
+        let pack = ContextPackBuilder::pack_ranges(path.to_string(), ranges.to_vec(), &lines);
+        count_tokens(path)
+            + pack
+                .blocks
+                .iter()
+                .map(|block| count_tokens(&block.text))
+                .sum::<usize>()
+    }
+
+    /// Ranges to try for a hit, widest first
+    fn candidate_ranges(&mut self, path: &str, line: usize) -> Vec<Vec<(usize, usize)>> {
+        let window = (
+            line.saturating_sub(FALLBACK_LINES).max(1),
+            line + FALLBACK_LINES,
+        );
+        let content = &self.content;
+        let span = self
+            .spans
+            .get_or_insert_with(|| SymbolSpans::parse(Path::new(path), content))
+            .enclosing(line);
+        match span {
+            Some(span) => vec![
+                vec![(span.start_line, span.end_line)],
+                vec![(span.start_line, span.signature_end_line), window],
+                vec![window],
+            ],
+            None => vec![vec![window]],
+        }
+    }
+}
+
+/// Locate hits for `options` and pack the best of them into `budget_tokens`
+pub fn build(options: &SearchOptions, budget_tokens: usize) -> Result<AgentContextPayload> {
+    let start = Instant::now();
+    let search_root = resolve_search_root(options.path.as_deref())?;
+    let report = match daemon::search(options, OutputFormat::Json2) {
+        Some(report) => report,
+        None => search::execute(options, OutputFormat::Json2)?,
+    };
+
+    let hits: Vec<(String, String, usize)> = report
+        .outcome
+        .results
+        .iter()
+        .filter_map(|result| Some((result.result_id.clone()?, result.path.clone(), result.line?)))
+        .collect();
+
+    let mut files: HashMap<String, Option<FileSelection>> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    let mut used = 0usize;
+    let mut included = 0usize;
+    let mut dropped = Vec::new();
+
+    for (id, path, line) in &hits {
+        let selection = files.entry(path.clone()).or_insert_with(|| {
+            let content = std::fs::read_to_string(search_root.join(path)).ok()?;
+            Some(FileSelection {
+                content,
+                spans: None,
+                ranges: Vec::new(),
+                tokens: 0,
+                hits: Vec::new(),
+            })
+        });
+        let Some(selection) = selection else {
+            dropped.push(DroppedHit {
+                id: id.clone(),
+                path: path.clone(),
+                line: *line,
+                tokens: 0,
+                reason: DropReason::Unreadable,
+            });
+            continue;
+        };
+
+        let mut smallest = usize::MAX;
+        let mut fitted = false;
+        for ranges in selection.candidate_ranges(path, *line) {
+            let mut next = selection.ranges.clone();
+            next.extend(ranges);
+            let tokens = selection.cost(path, &next);
+            let extra = tokens.saturating_sub(selection.tokens);
+            smallest = smallest.min(extra);
+            if used + extra <= budget_tokens {
+                used += extra;
+                selection.ranges = next;
+                selection.tokens = tokens;
+                fitted = true;
+                break;
+            }
+        }
+
+        if fitted {
+            if selection.hits.is_empty() {
+                order.push(path.clone());
+            }
+            selection.hits.push((id.clone(), *line));
+            included += 1;
+        } else {
+            dropped.push(DroppedHit {
+                id: id.clone(),
+                path: path.clone(),
+                line: *line,
+                tokens: smallest,
+                reason: DropReason::Budget,
+            });
+        }
+    }
+
+    let results = order
+        .into_iter()
+        .filter_map(|path| {
+            let selection = files.remove(&path)??;
+            let lines: Vec<&str> = selection.content.lines().collect();
+            let pack = ContextPackBuilder::pack_ranges(path, selection.ranges, &lines);
+            let blocks = pack
+                .blocks
+                .into_iter()
+                .map(|block| ContextFileBlock {
+                    ids: selection
+                        .hits
+                        .iter()
+                        .filter(|(_, line)| (block.start_line..=block.end_line).contains(line))
+                        .map(|(id, _)| id.clone())
+                        .collect(),
+                    start_line: block.start_line,
+                    end_line: block.end_line,
+                    text: block.text,
+                })
+                .collect();
+            Some(ContextFile {
+                path: pack.path,
+                blocks,
+            })
+        })
+        .collect();
+
+    Ok(AgentContextPayload {
+        meta: AgentContextMeta {
+            schema_version: "1",
+            stage: "context",
+            query: options.query.clone(),
+            search_mode: report.search_mode.to_string(),
+            budget_tokens,
+            tokens_used: used,
+            candidates: hits.len(),
+            included,
+            elapsed_ms: start.elapsed().as_secs_f64() * 1000.0,
+            search_root: search_root.display().to_string(),
+            dropped,
+        },
+        results,
+    })
+}
+
+/// Run `cgrep agent context`
+pub fn run(options: &SearchOptions, budget_tokens: usize, compact: bool) -> Result<()> {
+    let payload = build(options, budget_tokens)?;
+    print_json(&payload, compact)
+}
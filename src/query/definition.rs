@@ -5,92 +5,112 @@
 use anyhow::Result;
 use colored::Colorize;
 use serde::Serialize;
+use std::path::Path;
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::FileScanner;
-use crate::parser::symbols::{SymbolExtractor, SymbolKind};
-use crate::query::index_filter::{find_files_with_symbol, read_scanned_files};
-use cgrep::output::print_json;
-use cgrep::utils::get_root_with_index;
+use crate::indexer::symbol_table::SymbolRecord;
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::query::index_filter::{find_exact_symbol_records, find_symbol_records};
+use crate::utils::get_root_with_index;
 
 /// Definition result for JSON output
 #[derive(Debug, Serialize)]
-struct DefinitionResult {
-    name: String,
-    kind: String,
-    path: String,
-    line: usize,
-    column: usize,
+pub struct DefinitionResult {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qualified_name: Option<String>,
+    pub kind: String,
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
 }
 
-/// Run the definition command
-pub fn run(name: &str, format: OutputFormat, compact: bool) -> Result<()> {
-    let root = get_root_with_index(std::env::current_dir()?);
-    let extractor = SymbolExtractor::new();
+/// Symbol kinds that count as definitions (skips variable/property references)
+fn is_definition_kind(kind: &str) -> bool {
+    matches!(
+        kind,
+        "function"
+            | "method"
+            | "class"
+            | "interface"
+            | "type"
+            | "struct"
+            | "enum"
+            | "trait"
+            | "constant"
+            | "module"
+    )
+}
 
-    let files = match find_files_with_symbol(&root, name)? {
-        Some(indexed_paths) => read_scanned_files(&indexed_paths),
-        None => {
-            let scanner = FileScanner::new(&root);
-            scanner.scan()?
-        }
-    };
-    let name_lower = name.to_lowercase();
-
-    // Priority: exact match > contains
-    let mut exact_matches = Vec::new();
-    let mut partial_matches = Vec::new();
-
-    for file in &files {
-        if let Some(ref file_lang) = file.language {
-            if let Ok(symbols) = extractor.extract(&file.content, file_lang) {
-                for symbol in symbols {
-                    // Skip variable/property references, focus on definitions
-                    if matches!(
-                        symbol.kind,
-                        SymbolKind::Function
-                            | SymbolKind::Class
-                            | SymbolKind::Interface
-                            | SymbolKind::Type
-                            | SymbolKind::Struct
-                            | SymbolKind::Enum
-                            | SymbolKind::Trait
-                    ) {
-                        if symbol.name.to_lowercase() == name_lower {
-                            exact_matches.push((file.path.clone(), symbol));
-                        } else if symbol.name.to_lowercase().contains(&name_lower) {
-                            partial_matches.push((file.path.clone(), symbol));
-                        }
-                    }
-                }
-            }
-        }
+/// Split `Type::method` or `pkg.Class.method` into qualifier and name
+fn split_qualified(name: &str) -> (Option<&str>, &str) {
+    if let Some((qualifier, leaf)) = name.rsplit_once("::") {
+        return (Some(qualifier), leaf);
     }
+    match name.rsplit_once('.') {
+        Some((qualifier, leaf)) => (Some(qualifier), leaf),
+        None => (None, name),
+    }
+}
+
+/// Compare qualified names regardless of `::`/`.` separators
+fn normalize_qualified(name: &str) -> String {
+    name.replace("::", ".").to_lowercase()
+}
+
+/// Whether the record's qualified name ends with the requested path on a
+/// segment boundary (`Foo::build` matches `crate::index::Foo::build`)
+fn matches_qualified(record: &SymbolRecord, query: &str) -> bool {
+    let qualified = normalize_qualified(&record.qualified_name());
+    qualified == query || qualified.ends_with(&format!(".{}", query))
+}
 
-    let matches = if !exact_matches.is_empty() {
-        exact_matches
-    } else {
-        partial_matches
+/// Definitions of `name` under `root`; exact name matches win over partial ones
+pub fn find(root: &Path, name: &str) -> Result<Vec<DefinitionResult>> {
+    let (qualifier, leaf) = split_qualified(name);
+    let qualified_query = normalize_qualified(name);
+    let is_match = |record: &SymbolRecord| {
+        // Qualified lookups resolve exactly
+        is_definition_kind(&record.kind)
+            && (qualifier.is_none() || matches_qualified(record, &qualified_query))
     };
 
-    // Collect results
-    let results: Vec<DefinitionResult> = matches
-        .iter()
-        .map(|(path, symbol)| {
-            let rel_path = path
-                .strip_prefix(&root)
-                .unwrap_or(path)
-                .display()
-                .to_string();
+    // Priority: exact match (indexed lookup) > contains (full scan, unqualified only)
+    let mut matches: Vec<SymbolRecord> = find_exact_symbol_records(root, leaf)?
+        .into_iter()
+        .filter(is_match)
+        .collect();
+    if matches.is_empty() && qualifier.is_none() {
+        matches = find_symbol_records(root, leaf)?
+            .into_iter()
+            .filter(is_match)
+            .collect();
+    }
+
+    Ok(matches
+        .into_iter()
+        .map(|symbol| {
+            let path = root.join(&symbol.path);
             DefinitionResult {
-                name: symbol.name.clone(),
-                kind: symbol.kind.to_string(),
-                path: rel_path,
+                qualified_name: symbol.scope.as_ref().map(|_| symbol.qualified_name()),
+                path: path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string(),
+                name: symbol.name,
+                kind: symbol.kind,
                 line: symbol.line,
                 column: symbol.column,
             }
         })
-        .collect();
+        .collect())
+}
+
+/// Run the definition command
+pub fn run(name: &str, format: OutputFormat, compact: bool) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let results = find(&root, name)?;
 
     match format {
         OutputFormat::Json | OutputFormat::Json2 => {
@@ -108,21 +128,24 @@ pub fn run(name: &str, format: OutputFormat, compact: bool) -> Result<()> {
                 name.yellow()
             );
 
-            for (path, symbol) in &matches {
-                let rel_path = path.strip_prefix(&root).unwrap_or(path).display();
+            for symbol in &results {
                 let kind_str = format!("[{}]", symbol.kind);
 
                 println!(
                     "  {} {} {}:{}:{}",
                     kind_str.blue(),
-                    symbol.name.green(),
-                    rel_path.to_string().cyan(),
+                    symbol
+                        .qualified_name
+                        .as_deref()
+                        .unwrap_or(&symbol.name)
+                        .green(),
+                    symbol.path.cyan(),
                     symbol.line.to_string().yellow(),
                     symbol.column.to_string().yellow()
                 );
 
                 // Show context from file
-                if let Ok(content) = std::fs::read_to_string(path) {
+                if let Ok(content) = std::fs::read_to_string(root.join(&symbol.path)) {
                     let lines: Vec<&str> = content.lines().collect();
                     let start = symbol.line.saturating_sub(1);
                     let end = (start + 3).min(lines.len());
@@ -145,3 +168,67 @@ pub fn run(name: &str, format: OutputFormat, compact: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::index::DEFAULT_WRITER_BUDGET_BYTES;
+    use crate::indexer::IndexBuilder;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        std::fs::write(path, content).expect("write");
+    }
+
+    #[test]
+    fn qualified_lookups_resolve_methods() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        write(
+            root,
+            "src/com/acme/Outer.java",
+            "package com.acme;\n\nclass Outer {\n    void run() {}\n}\n",
+        );
+        write(
+            root,
+            "store/store.go",
+            "package store\n\ntype Store struct{}\n\nfunc (s *Store) Save() {}\n",
+        );
+        write(
+            root,
+            "src/widget.ts",
+            "export class Widget {\n  render(): void {}\n}\n",
+        );
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+
+        let found = |name: &str| {
+            find(root, name)
+                .expect("find")
+                .into_iter()
+                .map(|d| (d.kind, d.path, d.line))
+                .collect::<Vec<_>>()
+        };
+        let java = vec![(
+            "method".to_string(),
+            "src/com/acme/Outer.java".to_string(),
+            4,
+        )];
+        assert_eq!(found("Outer.run"), java);
+        assert_eq!(found("com.acme.Outer.run"), java);
+        assert!(found("Inner.run").is_empty());
+        assert_eq!(
+            found("Store.Save"),
+            vec![("method".to_string(), "store/store.go".to_string(), 5)]
+        );
+        assert_eq!(
+            found("Widget.render"),
+            vec![("method".to_string(), "src/widget.ts".to_string(), 2)]
+        );
+    }
+}
@@ -4,94 +4,168 @@
 
 use anyhow::Result;
 use colored::Colorize;
-use regex::Regex;
 use serde::Serialize;
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::FileScanner;
+use crate::indexer::deps::DependencyGraph;
+use crate::indexer::resolve::{normalize, ImportResolver};
+use crate::indexer::scanner::{FileScanner, ScannedFile};
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::parser::imports::ImportExtractor;
+use crate::parser::languages::LANGUAGES;
 use crate::query::index_filter::{find_files_with_content, read_scanned_files};
-use cgrep::output::print_json;
-use cgrep::utils::get_root_with_index;
+use crate::utils::get_root_with_index;
 
 /// Dependent result for JSON output
 #[derive(Debug, Serialize)]
-struct DependentResult {
-    path: String,
-    line: usize,
-    import_line: String,
+pub struct DependentResult {
+    pub path: String,
+    pub line: usize,
+    pub import_line: String,
 }
 
-/// Run the dependents command
-pub fn run(file: &str, format: OutputFormat, compact: bool) -> Result<()> {
-    let root = get_root_with_index(std::env::current_dir()?);
-    let target_path = Path::new(file);
-    let target_stem = target_path
-        .file_stem()
+/// Resolve the user-supplied file to a path relative to the index root
+pub(crate) fn target_rel_path(root: &Path, file: &str) -> Result<PathBuf> {
+    let absolute = std::env::current_dir()?.join(file);
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let rel = absolute
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| PathBuf::from(file));
+    Ok(normalize(&rel).unwrap_or(rel))
+}
+
+/// Files that could import the target, narrowed through the index when present.
+///
+/// Importers name either the file stem or, for `mod.rs`/`__init__.py`/`index.ts`
+/// and Go packages, the containing directory. Rust targets are not narrowed:
+/// `use super::Thing` and `use my_crate::Thing` name neither.
+fn candidate_files(root: &Path, target: &Path) -> Result<Vec<ScannedFile>> {
+    if target.extension().is_some_and(|ext| ext == "rs") {
+        return FileScanner::new(root).scan();
+    }
+
+    let mut terms = Vec::new();
+    if let Some(stem) = target.file_stem().and_then(|s| s.to_str()) {
+        terms.push(stem.to_string());
+    }
+    if let Some(dir) = target
+        .parent()
+        .and_then(|p| p.file_name())
         .and_then(|s| s.to_str())
-        .unwrap_or(file);
+    {
+        terms.push(dir.to_string());
+    }
 
-    let files = match find_files_with_content(&root, target_stem)? {
-        Some(indexed_paths) => read_scanned_files(&indexed_paths),
-        None => {
-            let scanner = FileScanner::new(&root);
-            scanner.scan()?
+    let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+    for term in &terms {
+        match find_files_with_content(root, term)? {
+            Some(found) => paths.extend(found),
+            None => return FileScanner::new(root).scan(),
         }
-    };
-
-    // Patterns to match imports
-    let patterns = [
-        // JavaScript/TypeScript: import ... from 'path' or require('path')
-        format!(
-            r#"(?:import|from|require)\s*[\(\s]?['"](?:[./]*{})['"]"#,
-            regex::escape(target_stem)
-        ),
-        // Python: import path or from path import
-        format!(
-            r"(?:import|from)\s+{}(?:\s|$|,)",
-            regex::escape(target_stem)
-        ),
-        // Rust: use path or mod path
-        format!(
-            r"(?:use|mod)\s+(?:crate::)?{}(?:::|;|\s)",
-            regex::escape(target_stem)
-        ),
-        // Go: import "path"
-        format!(
-            r#"import\s+[\(\s]*['"](?:[./]*{})['"]"#,
-            regex::escape(target_stem)
-        ),
-    ];
-
-    let regexes: Vec<Regex> = patterns.iter().filter_map(|p| Regex::new(p).ok()).collect();
+    }
+    Ok(read_scanned_files(&paths.into_iter().collect::<Vec<_>>()))
+}
 
+/// Direct importers of `target` from the stored dependency graph
+fn from_graph(root: &Path, graph: &DependencyGraph, target: &str) -> Vec<DependentResult> {
+    graph
+        .walk(target, true, Some(1))
+        .into_iter()
+        .map(|reached| {
+            let import_line = std::fs::read_to_string(root.join(&reached.path))
+                .ok()
+                .and_then(|content| {
+                    content
+                        .lines()
+                        .nth(reached.line.saturating_sub(1))
+                        .map(|line| line.trim().to_string())
+                })
+                .unwrap_or_default();
+            DependentResult {
+                path: reached.path,
+                line: reached.line,
+                import_line,
+            }
+        })
+        .collect()
+}
+
+/// Files under `root` that import `file` (resolved against the working directory)
+///
+/// Answers from `.cgrep/deps.json` when the index has recorded the target, and
+/// re-parses candidate files otherwise.
+pub fn find(root: &Path, file: &str) -> Result<Vec<DependentResult>> {
+    let target = target_rel_path(root, file)?;
+    let target_key = target.to_string_lossy();
+    if let Some(graph) = DependencyGraph::load(root)?.filter(|graph| graph.contains(&target_key)) {
+        return Ok(from_graph(root, &graph, &target_key));
+    }
+
+    let files = candidate_files(root, &target)?;
+
+    let extractor = ImportExtractor::new();
+    let mut resolver = ImportResolver::new(root);
     let mut results: Vec<DependentResult> = Vec::new();
 
     for scanned_file in &files {
         let rel_path = scanned_file
             .path
-            .strip_prefix(&root)
+            .strip_prefix(root)
             .unwrap_or(&scanned_file.path);
-
-        // Skip the target file itself
-        if rel_path.to_string_lossy().contains(target_stem) {
+        if rel_path == target {
             continue;
         }
+        let Some(lang) = scanned_file
+            .language
+            .as_deref()
+            .filter(|lang| LANGUAGES.get(lang).is_some())
+        else {
+            continue;
+        };
+        let Ok(imports) = extractor.extract(&scanned_file.content, lang) else {
+            continue;
+        };
 
-        for (line_num, line) in scanned_file.content.lines().enumerate() {
-            for re in &regexes {
-                if re.is_match(line) {
-                    results.push(DependentResult {
-                        path: rel_path.display().to_string(),
-                        line: line_num + 1,
-                        import_line: line.trim().to_string(),
-                    });
-                    break;
-                }
+        let lines: Vec<&str> = scanned_file.content.lines().collect();
+        let mut matched_lines = BTreeSet::new();
+        for import in imports {
+            if matched_lines.contains(&import.line) {
+                continue;
+            }
+            if resolver
+                .resolve(rel_path, lang, &import)
+                .iter()
+                .any(|resolved| resolved == &target)
+            {
+                matched_lines.insert(import.line);
             }
         }
+
+        for line in matched_lines {
+            results.push(DependentResult {
+                path: rel_path.display().to_string(),
+                line,
+                import_line: lines
+                    .get(line.saturating_sub(1))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_default(),
+            });
+        }
     }
 
+    results.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    Ok(results)
+}
+
+/// Run the dependents command
+pub fn run(file: &str, format: OutputFormat, compact: bool) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let results = find(&root, file)?;
+
     match format {
         OutputFormat::Json | OutputFormat::Json2 => {
             print_json(&results, compact)?;
@@ -124,3 +198,55 @@ pub fn run(file: &str, format: OutputFormat, compact: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::index::DEFAULT_WRITER_BUDGET_BYTES;
+    use crate::indexer::IndexBuilder;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        std::fs::write(path, content).expect("write");
+    }
+
+    #[test]
+    fn indexed_super_importers_are_found() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"demo\"\n");
+        write(root, "src/lib.rs", "pub mod query;\n");
+        write(
+            root,
+            "src/query/mod.rs",
+            "pub mod search;\n\npub struct Thing;\n",
+        );
+        write(
+            root,
+            "src/query/search.rs",
+            "use super::Thing;\n\npub fn run(_: Thing) {}\n",
+        );
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("index");
+        assert!(DependencyGraph::path_for(root).exists());
+
+        let target = root.join("src/query/mod.rs");
+        let results = find(root, target.to_str().expect("utf8")).expect("dependents");
+        let found: Vec<(&str, usize, &str)> = results
+            .iter()
+            .map(|r| (r.path.as_str(), r.line, r.import_line.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("src/lib.rs", 1, "pub mod query;"),
+                ("src/query/search.rs", 1, "use super::Thing;")
+            ]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Query the file dependency graph recorded by the indexer
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use crate::errors::IndexNotFoundError;
+use crate::indexer::deps::{DependencyGraph, ReachedFile};
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::query::dependents::target_rel_path;
+use crate::query::graph::{render, GraphFormat};
+use crate::utils::{get_root_with_index, INDEX_DIR};
+
+/// Deps result for JSON output
+#[derive(Debug, Serialize)]
+pub struct DepsReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    direction: &'static str,
+    transitive: bool,
+    results: Vec<ReachedFile>,
+    /// Import cycles touching the reported files (all cycles without a file)
+    cycles: Vec<Vec<String>>,
+}
+
+fn load_graph(root: &Path) -> Result<DependencyGraph> {
+    let index_path = root.join(INDEX_DIR);
+    if !index_path.exists() {
+        return Err(IndexNotFoundError {
+            index_path: index_path.display().to_string(),
+        }
+        .into());
+    }
+    DependencyGraph::load(root)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Dependency graph not found in '{}'\n\n\
+             Suggestion: Run 'cgrep index' to record file dependencies.",
+            index_path.display()
+        )
+    })
+}
+
+/// Query forward or reverse dependencies of `file` (or the whole graph)
+fn query(
+    graph: &DependencyGraph,
+    file: Option<&str>,
+    reverse: bool,
+    transitive: bool,
+) -> Result<DepsReport> {
+    let direction = if reverse { "reverse" } else { "forward" };
+    let Some(file) = file else {
+        return Ok(DepsReport {
+            file: None,
+            direction,
+            transitive,
+            results: Vec::new(),
+            cycles: graph.cycles(),
+        });
+    };
+
+    if !graph.contains(file) {
+        anyhow::bail!(
+            "File not found in dependency graph: {}\n\n\
+             Suggestion: Run 'cgrep index' if the file was added recently.",
+            file
+        );
+    }
+
+    let max_depth = (!transitive).then_some(1);
+    let results = graph.walk(file, reverse, max_depth);
+    let involved: HashSet<&str> = results
+        .iter()
+        .map(|r| r.path.as_str())
+        .chain(std::iter::once(file))
+        .collect();
+    let cycles = graph
+        .cycles()
+        .into_iter()
+        .filter(|cycle| cycle.iter().any(|path| involved.contains(path.as_str())))
+        .collect();
+
+    Ok(DepsReport {
+        file: Some(file.to_string()),
+        direction,
+        transitive,
+        results,
+        cycles,
+    })
+}
+
+/// Render the queried files (or the whole graph) as DOT or Mermaid
+fn render_report(graph: &DependencyGraph, report: &DepsReport, format: GraphFormat) -> String {
+    let Some(file) = report.file.as_deref() else {
+        let nodes: Vec<&str> = graph
+            .edges()
+            .flat_map(|(from, edge)| [from, edge.path.as_str()])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let edges: Vec<(&str, &str)> = graph
+            .edges()
+            .map(|(from, edge)| (from, edge.path.as_str()))
+            .collect();
+        return render(format, "deps", None, &nodes, &edges);
+    };
+
+    let nodes: Vec<&str> = std::iter::once(file)
+        .chain(report.results.iter().map(|r| r.path.as_str()))
+        .collect();
+    let included: HashSet<&str> = nodes.iter().copied().collect();
+    let edges: Vec<(&str, &str)> = graph
+        .edges()
+        .map(|(from, edge)| (from, edge.path.as_str()))
+        .filter(|(from, to)| included.contains(from) && included.contains(to))
+        .filter(|(from, to)| {
+            // Direct queries only draw the edges that made the results
+            report.transitive
+                || if report.direction == "reverse" {
+                    *to == file
+                } else {
+                    *from == file
+                }
+        })
+        .collect();
+    render(format, "deps", Some(file), &nodes, &edges)
+}
+
+fn print_text(graph: &DependencyGraph, report: &DepsReport) {
+    match report.file.as_deref() {
+        Some(file) => {
+            let heading = if report.direction == "reverse" {
+                "Files that depend on"
+            } else {
+                "Dependencies of"
+            };
+            if report.results.is_empty() {
+                println!(
+                    "{} No {}: {}",
+                    "✗".red(),
+                    heading.to_lowercase(),
+                    file.yellow()
+                );
+            } else {
+                println!("\n{} {}: {}\n", "🔍".cyan(), heading, file.yellow());
+                for result in &report.results {
+                    let depth = if report.transitive {
+                        format!("[{}] ", result.depth)
+                    } else {
+                        String::new()
+                    };
+                    // The import statement lives in the importing file
+                    if report.direction == "reverse" {
+                        println!(
+                            "  {}{}:{}",
+                            depth.dimmed(),
+                            result.path.cyan(),
+                            result.line.to_string().yellow()
+                        );
+                    } else if result.via == file {
+                        println!(
+                            "  {}{} {}",
+                            depth.dimmed(),
+                            result.path.cyan(),
+                            format!("line {}", result.line).dimmed()
+                        );
+                    } else {
+                        println!(
+                            "  {}{} {}",
+                            depth.dimmed(),
+                            result.path.cyan(),
+                            format!("via {}:{}", result.via, result.line).dimmed()
+                        );
+                    }
+                }
+                println!(
+                    "\n{} Found {} files",
+                    "✓".green(),
+                    report.results.len().to_string().cyan()
+                );
+            }
+        }
+        None => {
+            println!(
+                "{} {} files, {} import edges",
+                "✓".green(),
+                graph.file_count().to_string().cyan(),
+                graph.edges().count().to_string().cyan()
+            );
+        }
+    }
+
+    for cycle in &report.cycles {
+        println!(
+            "{} Import cycle ({} files): {}",
+            "↻".red(),
+            cycle.len(),
+            cycle.join(", ")
+        );
+    }
+}
+
+/// Root-relative path of a `file` argument
+fn target(root: &Path, file: Option<&str>) -> Result<Option<String>> {
+    Ok(file
+        .map(|file| target_rel_path(root, file))
+        .transpose()?
+        .map(|path| path.to_string_lossy().to_string()))
+}
+
+/// Dependencies of `file` (relative to the current directory, or absolute),
+/// or of the whole graph
+pub fn find(
+    root: &Path,
+    file: Option<&str>,
+    reverse: bool,
+    transitive: bool,
+) -> Result<DepsReport> {
+    let graph = load_graph(root)?;
+    query(&graph, target(root, file)?.as_deref(), reverse, transitive)
+}
+
+/// Run the deps command
+pub fn run(
+    file: Option<&str>,
+    reverse: bool,
+    transitive: bool,
+    graph_format: Option<GraphFormat>,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let graph = load_graph(&root)?;
+    let report = query(&graph, target(&root, file)?.as_deref(), reverse, transitive)?;
+
+    if let Some(graph_format) = graph_format {
+        print!("{}", render_report(&graph, &report, graph_format));
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Json | OutputFormat::Json2 => {
+            print_json(&report, compact)?;
+        }
+        OutputFormat::Text => print_text(&graph, &report),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layered_graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge("app.rs", "service.rs", 1);
+        graph.add_edge("service.rs", "store.rs", 2);
+        graph.add_edge("store.rs", "service.rs", 5);
+        graph.add_file("unrelated.rs");
+        graph
+    }
+
+    #[test]
+    fn reverse_transitive_query_reports_cycles() {
+        let graph = layered_graph();
+        let report = query(&graph, Some("store.rs"), true, true).expect("query");
+        let paths: Vec<&str> = report.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["service.rs", "app.rs"]);
+        assert_eq!(report.cycles, vec![vec!["service.rs", "store.rs"]]);
+
+        let direct = query(&graph, Some("app.rs"), false, false).expect("query");
+        assert_eq!(direct.results.len(), 1);
+        assert!(query(&graph, Some("missing.rs"), false, false).is_err());
+    }
+
+    #[test]
+    fn dot_rendering_limits_direct_edges() {
+        let graph = layered_graph();
+        let report = query(&graph, Some("service.rs"), false, false).expect("query");
+        let dot = render_report(&graph, &report, GraphFormat::Dot);
+        assert!(dot.contains("\"service.rs\" -> \"store.rs\";"));
+        assert!(!dot.contains("\"store.rs\" -> \"service.rs\";"));
+
+        let whole = query(&graph, None, false, false).expect("query");
+        let mermaid = render_report(&graph, &whole, GraphFormat::Mermaid);
+        assert_eq!(mermaid.matches("-->").count(), 3);
+    }
+}
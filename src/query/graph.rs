@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! DOT and Mermaid rendering for graph-shaped command output
+
+use std::collections::HashMap;
+
+/// Graph rendering format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+/// Render nodes and `(from, to)` edges; `root` is highlighted when present
+pub fn render(
+    format: GraphFormat,
+    name: &str,
+    root: Option<&str>,
+    nodes: &[&str],
+    edges: &[(&str, &str)],
+) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(name, root, nodes, edges),
+        GraphFormat::Mermaid => render_mermaid(root, nodes, edges),
+    }
+}
+
+fn escape_quoted(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn render_dot(name: &str, root: Option<&str>, nodes: &[&str], edges: &[(&str, &str)]) -> String {
+    let mut out = format!("digraph {} {{\n  rankdir=LR;\n", name);
+    for node in nodes {
+        let style = if Some(*node) == root {
+            " [style=bold]"
+        } else {
+            ""
+        };
+        out.push_str(&format!("  \"{}\"{};\n", escape_quoted(node), style));
+    }
+    for (from, to) in edges {
+        out.push_str(&format!(
+            "  \"{}\" -> \"{}\";\n",
+            escape_quoted(from),
+            escape_quoted(to)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(root: Option<&str>, nodes: &[&str], edges: &[(&str, &str)]) -> String {
+    let ids: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+    let mut out = String::from("flowchart LR\n");
+    for (i, node) in nodes.iter().enumerate() {
+        out.push_str(&format!("  n{}[\"{}\"]\n", i, node.replace('"', "#quot;")));
+    }
+    for (from, to) in edges {
+        if let (Some(from), Some(to)) = (ids.get(from), ids.get(to)) {
+            out.push_str(&format!("  n{} --> n{}\n", from, to));
+        }
+    }
+    if let Some(root) = root.and_then(|root| ids.get(root)) {
+        out.push_str(&format!("  style n{} stroke-width:3px\n", root));
+    }
+    out
+}
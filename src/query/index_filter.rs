@@ -3,17 +3,61 @@
 //! Index-backed helpers for narrowing file scans.
 
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tantivy::{
     collector::DocSetCollector,
     query::{BooleanQuery, Occur, Query, TermQuery},
     schema::{Field, FieldType, IndexRecordOption, Term, Value},
-    Index, ReloadPolicy, TantivyDocument,
+    Index, IndexReader, ReloadPolicy, TantivyDocument,
 };
 
-use crate::indexer::scanner::{detect_language, ScannedFile};
-use cgrep::utils::INDEX_DIR;
+use crate::indexer::scanner::{detect_language, FileScanner, ScannedFile};
+use crate::indexer::symbol_table::{SymbolRecord, SymbolTable};
+use crate::indexer::tokenizer;
+use crate::parser::symbols::SymbolExtractor;
+use crate::utils::INDEX_DIR;
+
+/// Indexes opened by this process, keyed by index directory.
+static OPEN_INDEXES: Lazy<Mutex<HashMap<PathBuf, (Index, IndexReader)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Open the tantivy index at `index_path`, reusing an earlier open in this process.
+///
+/// Long-running front-ends (`cgrep mcp`) answer many queries from one reader;
+/// the reader is reloaded on every call so it sees commits made since.
+pub fn open_index(index_path: &Path) -> Result<(Index, IndexReader)> {
+    let mut open = OPEN_INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((index, reader)) = open.get(index_path) {
+        if reader.reload().is_ok() {
+            return Ok((index.clone(), reader.clone()));
+        }
+    }
+
+    let index = Index::open_in_dir(index_path).context("Failed to open index")?;
+    tokenizer::register(&index);
+    let reader: IndexReader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()
+        .context("Failed to create index reader")?;
+    open.insert(index_path.to_path_buf(), (index.clone(), reader.clone()));
+    Ok((index, reader))
+}
+
+/// Open the index under `root` ahead of the first query, for long-running servers.
+pub fn preload_index(root: &Path) {
+    let index_path = root.join(INDEX_DIR);
+    if index_path.exists() {
+        if let Err(err) = open_index(&index_path) {
+            eprintln!("Warning: failed to open index: {:#}", err);
+        }
+    } else {
+        eprintln!("Warning: no index found; falling back to scanning. Run: cgrep index");
+    }
+}
 
 /// Find files that likely contain a symbol name using the index.
 pub fn find_files_with_symbol(root: &Path, symbol_name: &str) -> Result<Option<Vec<PathBuf>>> {
@@ -25,6 +69,64 @@ pub fn find_files_with_content(root: &Path, term: &str) -> Result<Option<Vec<Pat
     find_files_with_field(root, "content", term)
 }
 
+/// Find symbols whose name contains `name` (case-insensitive).
+///
+/// Answers from the persisted symbol table when one exists; otherwise parses
+/// candidate files, narrowed through the index when available.
+pub fn find_symbol_records(root: &Path, name: &str) -> Result<Vec<SymbolRecord>> {
+    if let Some(table) = SymbolTable::open_existing(root)? {
+        return table.find_by_name(name);
+    }
+    let name_lower = name.to_lowercase();
+    parse_symbol_records(root, name, |symbol| {
+        symbol.to_lowercase().contains(&name_lower)
+    })
+}
+
+/// Find symbols named exactly `name` (case-insensitive).
+///
+/// Uses the symbol table's name index, so definition lookups avoid the
+/// substring scan of [`find_symbol_records`].
+pub fn find_exact_symbol_records(root: &Path, name: &str) -> Result<Vec<SymbolRecord>> {
+    if let Some(table) = SymbolTable::open_existing(root)? {
+        return table.find_exact(name);
+    }
+    let name_lower = name.to_lowercase();
+    parse_symbol_records(root, name, |symbol| symbol.to_lowercase() == name_lower)
+}
+
+/// Parse candidate files for symbols whose name passes `matches`
+fn parse_symbol_records(
+    root: &Path,
+    name: &str,
+    matches: impl Fn(&str) -> bool,
+) -> Result<Vec<SymbolRecord>> {
+    let files = match find_files_with_symbol(root, name)? {
+        Some(indexed_paths) => read_scanned_files(&indexed_paths),
+        None => FileScanner::new(root).scan()?,
+    };
+
+    let extractor = SymbolExtractor::new();
+    let mut records = Vec::new();
+    for file in &files {
+        let Some(lang) = file.language.as_deref() else {
+            continue;
+        };
+        let rel_path = file.path.strip_prefix(root).unwrap_or(&file.path);
+        let Ok(symbols) = extractor.extract_for_path(&file.content, lang, rel_path) else {
+            continue;
+        };
+        let path = file.path.to_string_lossy();
+        records.extend(
+            symbols
+                .iter()
+                .filter(|symbol| matches(&symbol.name))
+                .map(|symbol| SymbolRecord::from_symbol(&path, lang, symbol)),
+        );
+    }
+    Ok(records)
+}
+
 /// Read a list of files into scanned-file structs.
 pub fn read_scanned_files(paths: &[PathBuf]) -> Vec<ScannedFile> {
     let mut scanned = Vec::with_capacity(paths.len());
@@ -54,8 +156,8 @@ fn find_files_with_field(
         return Ok(None);
     }
 
-    let index = match Index::open_in_dir(&index_path) {
-        Ok(index) => index,
+    let (index, reader) = match open_index(&index_path) {
+        Ok(opened) => opened,
         Err(_) => return Ok(None),
     };
 
@@ -74,13 +176,8 @@ fn find_files_with_field(
         return Ok(None);
     }
 
-    let query = build_or_query(field, &tokens);
-    let reader = index
-        .reader_builder()
-        .reload_policy(ReloadPolicy::Manual)
-        .try_into()
-        .context("Failed to create index reader")?;
-
+    // A file holding the name holds every sub-word of it
+    let query = build_and_query(field, &tokens);
     let searcher = reader.searcher();
     let docset = searcher.search(&query, &DocSetCollector)?;
 
@@ -98,20 +195,21 @@ fn find_files_with_field(
     Ok(Some(paths))
 }
 
-fn build_or_query(field: Field, tokens: &[String]) -> BooleanQuery {
+fn build_and_query(field: Field, tokens: &[String]) -> BooleanQuery {
     let subqueries = tokens
         .iter()
         .map(|token| {
             let term = Term::from_field_text(field, token);
             let query = TermQuery::new(term, IndexRecordOption::Basic);
-            (Occur::Should, Box::new(query) as Box<dyn Query>)
+            (Occur::Must, Box::new(query) as Box<dyn Query>)
         })
         .collect();
 
     BooleanQuery::new(subqueries)
 }
 
-fn tokenize_for_field(index: &Index, field: Field, text: &str) -> Result<Vec<String>> {
+/// Distinct query terms the analyzer of `field` produces for `text`
+pub(crate) fn tokenize_for_field(index: &Index, field: Field, text: &str) -> Result<Vec<String>> {
     let schema = index.schema();
     let field_entry = schema.get_field_entry(field);
 
@@ -126,8 +224,7 @@ fn tokenize_for_field(index: &Index, field: Field, text: &str) -> Result<Vec<Str
         return Ok(Vec::new());
     };
 
-    let mut analyzer = index
-        .tokenizers()
+    let mut analyzer = tokenizer::query_tokenizers()
         .get(&tokenizer_name)
         .ok_or_else(|| anyhow::anyhow!("Tokenizer not found: {}", tokenizer_name))?;
 
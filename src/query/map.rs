@@ -0,0 +1,733 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Repository map: a ranked skeleton of the most important symbols.
+//!
+//! Every identifier in a file that names a defined symbol is a reference
+//! from the innermost symbol around it (or from the file, at top level) to
+//! each definition of that name. Identifiers after `::` or `.` only count
+//! for definitions they resolve to: in the same file, in an imported file,
+//! or under the qualifier's name. Symbols are ranked by PageRank over this
+//! graph, test code heavily down-weighted, and the best of them are emitted
+//! as one-line signatures, grouped by file, until the token budget is filled.
+
+use anyhow::Result;
+use colored::Colorize;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::indexer::deps::DependencyGraph;
+use crate::indexer::scanner::FileScanner;
+use crate::indexer::symbol_table::{SymbolRecord, SymbolTable};
+use crate::output::{print_json, OutputFormat};
+use crate::parser::symbols::SymbolExtractor;
+use crate::query::dependents::target_rel_path;
+use crate::tokens::count_tokens;
+use crate::utils::get_root_with_index;
+
+/// Token budget when none is given
+pub const DEFAULT_BUDGET_TOKENS: usize = 1_024;
+
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 30;
+const MAX_SIGNATURE_CHARS: usize = 120;
+/// Rank multiplier for symbols in test code
+const TEST_WEIGHT: f64 = 0.01;
+
+static IDENTIFIER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("identifier regex"));
+
+#[derive(Debug, Serialize)]
+struct MapMeta {
+    schema_version: &'static str,
+    root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    budget_tokens: usize,
+    tokens_used: usize,
+    files_total: usize,
+    symbols_total: usize,
+    symbols_included: usize,
+    elapsed_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct MapSymbol {
+    name: String,
+    kind: String,
+    line: usize,
+    end_line: usize,
+    /// Declaration line without the body
+    signature: String,
+    score: f64,
+    /// Selected symbols of the same file enclosing this one
+    #[serde(skip)]
+    depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct MapFile {
+    path: String,
+    /// Score of the file's best symbol
+    score: f64,
+    symbols: Vec<MapSymbol>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepoMap {
+    meta: MapMeta,
+    results: Vec<MapFile>,
+}
+
+/// Symbols worth a line in the map
+fn is_mappable(kind: &str) -> bool {
+    matches!(
+        kind,
+        "function"
+            | "method"
+            | "class"
+            | "struct"
+            | "interface"
+            | "trait"
+            | "enum"
+            | "type"
+            | "constant"
+    )
+}
+
+/// Functions only count where they are called; a method named `usize` is
+/// not referenced by every `usize` type
+fn is_callable(kind: &str) -> bool {
+    matches!(kind, "function" | "method")
+}
+
+/// Whether a symbol belongs to test code: a `tests` module or class, or a
+/// test file or directory
+fn is_test_symbol(record: &SymbolRecord) -> bool {
+    let in_test_scope = record.scope.as_deref().is_some_and(|scope| {
+        scope
+            .split(['.', ':'])
+            .any(|segment| matches!(segment, "test" | "tests"))
+    });
+    let path = Path::new(&record.path);
+    let in_test_dir = path.components().any(|component| {
+        matches!(
+            component.as_os_str().to_str(),
+            Some("test" | "tests" | "__tests__" | "spec")
+        )
+    });
+    let file = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let test_file = file.starts_with("test_")
+        || file.contains("_test.")
+        || file.contains(".test.")
+        || file.contains(".spec.");
+    in_test_scope || in_test_dir || test_file
+}
+
+/// Identifier right before a trailing `::` or `.` of `before`, when there is one
+fn qualifier_of(before: &str) -> Option<&str> {
+    let rest = before
+        .strip_suffix("::")
+        .or_else(|| before.strip_suffix('.'))?;
+    let start = rest
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |idx| idx + 1);
+    Some(&rest[start..])
+}
+
+/// Whether a qualified reference (`qualifier.name`, `qualifier::name`) in
+/// `path` can mean `definition`: same file, imported file, or a qualifier
+/// naming the definition's module, type or file
+fn resolves_qualified(
+    definition: &SymbolRecord,
+    path: &str,
+    qualifier: &str,
+    imports: &HashMap<String, HashSet<String>>,
+) -> bool {
+    if definition.path == path
+        || imports
+            .get(path)
+            .is_some_and(|imported| imported.contains(&definition.path))
+    {
+        return true;
+    }
+    if qualifier.is_empty() {
+        return false;
+    }
+    let scope_leaf = definition
+        .scope
+        .as_deref()
+        .and_then(|scope| scope.rsplit(['.', ':']).next());
+    let file_stem = Path::new(&definition.path)
+        .file_stem()
+        .and_then(|stem| stem.to_str());
+    scope_leaf == Some(qualifier) || file_stem == Some(qualifier)
+}
+
+/// How much a reference through `name` says about its definitions: short
+/// words like `get` or `path` name many unrelated things and names found in
+/// most files say little, while compound names (`build_index`,
+/// `SymbolTable`) rarely collide
+fn name_weight(name: &str, definitions: usize, mentioning_files: usize, files: usize) -> f64 {
+    let chars: Vec<char> = name.chars().collect();
+    let compound = chars.iter().skip(1).any(|c| *c == '_')
+        || chars
+            .windows(2)
+            .any(|w| w[0].is_lowercase() && w[1].is_uppercase());
+    let specificity = (1.0 + files as f64 / mentioning_files.max(1) as f64).ln();
+    let mut weight = specificity / definitions as f64;
+    if compound && chars.len() >= 8 {
+        weight *= 10.0;
+    }
+    if name.starts_with('_') {
+        weight *= 0.1;
+    }
+    if definitions > 5 {
+        weight *= 0.1;
+    }
+    weight
+}
+
+/// PageRank over weighted edges; `nodes` counts every node, edges included
+fn page_rank(nodes: usize, edges: &HashMap<(usize, usize), f64>) -> Vec<f64> {
+    if nodes == 0 {
+        return Vec::new();
+    }
+    let mut out_weight = vec![0.0; nodes];
+    for (&(from, _), weight) in edges {
+        out_weight[from] += weight;
+    }
+    let base = (1.0 - DAMPING) / nodes as f64;
+    let mut rank = vec![1.0 / nodes as f64; nodes];
+    for _ in 0..ITERATIONS {
+        let dangling: f64 = (0..nodes)
+            .filter(|&node| out_weight[node] == 0.0)
+            .map(|node| rank[node])
+            .sum();
+        let mut next = vec![base + DAMPING * dangling / nodes as f64; nodes];
+        for (&(from, to), weight) in edges {
+            next[to] += DAMPING * rank[from] * weight / out_weight[from];
+        }
+        rank = next;
+    }
+    rank
+}
+
+/// Lines a wrapped declaration may continue over
+const MAX_SIGNATURE_LINES: usize = 8;
+
+/// Declaration at `line` on one line, without an opening brace; parameter
+/// lists wrapped over several lines are joined
+pub(crate) fn signature_of(lines: &[&str], line: usize) -> String {
+    let mut text = String::new();
+    let mut depth = 0i32;
+    for part in lines
+        .iter()
+        .skip(line.wrapping_sub(1))
+        .take(MAX_SIGNATURE_LINES)
+    {
+        let part = part.trim();
+        if !text.is_empty() && !text.ends_with(['(', '[']) && !part.starts_with([')', ']']) {
+            text.push(' ');
+        }
+        text.push_str(part);
+        depth += part.matches(['(', '[']).count() as i32;
+        depth -= part.matches([')', ']']).count() as i32;
+        if depth <= 0 {
+            break;
+        }
+    }
+    let text = text.replace(",)", ")").replace(",]", "]");
+    let text = text.strip_suffix('{').unwrap_or(&text).trim_end();
+    if text.chars().count() > MAX_SIGNATURE_CHARS {
+        let cut: String = text.chars().take(MAX_SIGNATURE_CHARS).collect();
+        format!("{}...", cut)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Rank the symbols under `root` and pick the best under `prefix` within `budget_tokens`
+fn build_map(root: &Path, prefix: &Path, budget_tokens: usize) -> Result<RepoMap> {
+    let start = Instant::now();
+    let files = FileScanner::new(root).scan()?;
+    let rel_path = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+
+    // Indexed symbols when there is a table, else parse every file
+    let records: Vec<SymbolRecord> = match SymbolTable::open_existing(root)? {
+        Some(table) => table.all()?,
+        None => {
+            let extractor = SymbolExtractor::new();
+            let mut records = Vec::new();
+            for file in &files {
+                let Some(lang) = file.language.as_deref() else {
+                    continue;
+                };
+                let path = rel_path(&file.path);
+                if let Ok(symbols) =
+                    extractor.extract_for_path(&file.content, lang, Path::new(&path))
+                {
+                    records.extend(
+                        symbols
+                            .iter()
+                            .map(|symbol| SymbolRecord::from_symbol(&path, lang, symbol)),
+                    );
+                }
+            }
+            records
+        }
+    };
+    // The table stores the paths it was indexed with
+    let records: Vec<SymbolRecord> = records
+        .into_iter()
+        .filter(|record| is_mappable(&record.kind))
+        .map(|mut record| {
+            record.path = rel_path(Path::new(&record.path));
+            record
+        })
+        .collect();
+
+    // Imports recorded by the index, to resolve qualified references
+    let mut imports: HashMap<String, HashSet<String>> = HashMap::new();
+    if let Ok(Some(graph)) = DependencyGraph::load(root) {
+        for (from, edge) in graph.edges() {
+            imports
+                .entry(from.to_string())
+                .or_default()
+                .insert(edge.path.clone());
+        }
+    }
+
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut by_path: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, record) in records.iter().enumerate() {
+        by_name.entry(record.name.as_str()).or_default().push(idx);
+        by_path.entry(record.path.as_str()).or_default().push(idx);
+    }
+
+    // Symbol nodes first, then one node per file for top-level references
+    let mut references: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut mentioning_files: HashMap<&str, usize> = HashMap::new();
+    let mut contents: HashMap<String, &str> = HashMap::new();
+    for (file_idx, file) in files.iter().enumerate() {
+        let path = rel_path(&file.path);
+        let file_node = records.len() + file_idx;
+        let local = by_path
+            .get(path.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut mentioned: HashSet<&str> = HashSet::new();
+        for (line_idx, text) in file.content.lines().enumerate() {
+            let line = line_idx + 1;
+            let from = local
+                .iter()
+                .copied()
+                .filter(|&idx| records[idx].line <= line && line <= records[idx].end_line)
+                .min_by_key(|&idx| records[idx].end_line - records[idx].line)
+                .unwrap_or(file_node);
+            for ident in IDENTIFIER.find_iter(text) {
+                let Some((&name, definitions)) = by_name.get_key_value(ident.as_str()) else {
+                    continue;
+                };
+                mentioned.insert(name);
+                // A declaration, not a reference to its namesakes
+                if definitions
+                    .iter()
+                    .any(|&idx| records[idx].path == path && records[idx].line == line)
+                {
+                    continue;
+                }
+                let called = text[ident.end()..].trim_start().starts_with('(');
+                let qualifier = qualifier_of(&text[..ident.start()]);
+                for &to in definitions {
+                    let definition = &records[to];
+                    if to == from
+                        || (is_callable(&definition.kind) && !called)
+                        || qualifier.is_some_and(|qualifier| {
+                            !resolves_qualified(definition, &path, qualifier, &imports)
+                        })
+                    {
+                        continue;
+                    }
+                    *references.entry((from, to)).or_default() += 1;
+                }
+            }
+        }
+        for name in mentioned {
+            *mentioning_files.entry(name).or_default() += 1;
+        }
+        contents.insert(path, file.content.as_str());
+    }
+    // Repeated references count, with diminishing returns
+    let edges: HashMap<(usize, usize), f64> = references
+        .into_iter()
+        .map(|((from, to), count)| {
+            let name = records[to].name.as_str();
+            let weight = name_weight(
+                name,
+                by_name[name].len(),
+                mentioning_files.get(name).copied().unwrap_or_default(),
+                files.len(),
+            ) * (count as f64).sqrt();
+            ((from, to), weight)
+        })
+        .collect();
+    let mut rank = page_rank(records.len() + files.len(), &edges);
+    for (idx, record) in records.iter().enumerate() {
+        if is_test_symbol(record) {
+            rank[idx] *= TEST_WEIGHT;
+        }
+    }
+
+    let mut candidates: Vec<usize> = (0..records.len())
+        .filter(|&idx| Path::new(&records[idx].path).starts_with(prefix))
+        .collect();
+    candidates.sort_by(|&a, &b| {
+        rank[b]
+            .total_cmp(&rank[a])
+            .then_with(|| records[a].path.cmp(&records[b].path))
+            .then(records[a].line.cmp(&records[b].line))
+    });
+
+    // Greedy fill: a file's header is paid for with its first symbol
+    let mut used = 0;
+    let mut selected: HashMap<&str, Vec<(usize, String)>> = HashMap::new();
+    let mut file_order: Vec<&str> = Vec::new();
+    for &idx in &candidates {
+        let record = &records[idx];
+        let Some(content) = contents.get(&record.path) else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let signature = signature_of(&lines, record.line);
+        if signature.is_empty() {
+            continue;
+        }
+        let header = if selected.contains_key(record.path.as_str()) {
+            0
+        } else {
+            count_tokens(&record.path) + 1
+        };
+        let cost = header + count_tokens(&signature) + 1;
+        if used + cost > budget_tokens {
+            continue;
+        }
+        used += cost;
+        if header > 0 {
+            file_order.push(record.path.as_str());
+        }
+        selected
+            .entry(record.path.as_str())
+            .or_default()
+            .push((idx, signature));
+    }
+
+    let results = file_order
+        .into_iter()
+        .map(|path| {
+            let mut picked = selected.remove(path).unwrap_or_default();
+            picked.sort_by_key(|(idx, _)| (records[*idx].line, records[*idx].end_line));
+            let spans: Vec<(usize, usize)> = picked
+                .iter()
+                .map(|(idx, _)| (records[*idx].line, records[*idx].end_line))
+                .collect();
+            let symbols: Vec<MapSymbol> = picked
+                .into_iter()
+                .map(|(idx, signature)| {
+                    let record = &records[idx];
+                    let depth = spans
+                        .iter()
+                        .filter(|&&(start, end)| {
+                            (start, end) != (record.line, record.end_line)
+                                && start <= record.line
+                                && record.end_line <= end
+                        })
+                        .count();
+                    MapSymbol {
+                        name: record.name.clone(),
+                        kind: record.kind.clone(),
+                        line: record.line,
+                        end_line: record.end_line,
+                        signature,
+                        score: rank[idx],
+                        depth,
+                    }
+                })
+                .collect();
+            MapFile {
+                path: path.to_string(),
+                score: symbols.iter().map(|s| s.score).fold(0.0, f64::max),
+                symbols,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let prefix_display = prefix.to_string_lossy().replace('\\', "/");
+    Ok(RepoMap {
+        meta: MapMeta {
+            schema_version: "1",
+            root: root.display().to_string(),
+            path: (!prefix_display.is_empty()).then_some(prefix_display),
+            budget_tokens,
+            tokens_used: used,
+            files_total: files.len(),
+            symbols_total: candidates.len(),
+            symbols_included: results.iter().map(|f| f.symbols.len()).sum(),
+            elapsed_ms: start.elapsed().as_secs_f64() * 1000.0,
+        },
+        results,
+    })
+}
+
+fn render_text(map: &RepoMap, use_color: bool) -> String {
+    let mut out = String::new();
+    for file in &map.results {
+        let path = if use_color {
+            file.path.cyan().to_string()
+        } else {
+            file.path.clone()
+        };
+        out.push_str(&path);
+        out.push('\n');
+        for symbol in &file.symbols {
+            let line = format!("{:>5}", symbol.line);
+            let line = if use_color {
+                line.yellow().to_string()
+            } else {
+                line
+            };
+            out.push_str(&format!(
+                "{}  {}{}\n",
+                line,
+                "  ".repeat(symbol.depth),
+                symbol.signature
+            ));
+        }
+    }
+    out
+}
+
+fn render_markdown(map: &RepoMap) -> String {
+    let mut out = String::new();
+    for (idx, file) in map.results.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("## `{}`\n\n", file.path));
+        for symbol in &file.symbols {
+            // A signature holding backticks needs a longer fence
+            let code = if symbol.signature.contains('`') {
+                format!("`` {} ``", symbol.signature)
+            } else {
+                format!("`{}`", symbol.signature)
+            };
+            out.push_str(&format!(
+                "{}- {} ({}, line {})\n",
+                "  ".repeat(symbol.depth),
+                code,
+                symbol.kind,
+                symbol.line
+            ));
+        }
+    }
+    out
+}
+
+/// Map of the symbols under `path` (relative to the current directory, or
+/// absolute; the whole repository when `None`)
+pub fn build(root: &Path, path: Option<&str>, budget_tokens: usize) -> Result<RepoMap> {
+    let prefix = match path {
+        Some(path) => target_rel_path(root, path)?,
+        None => Default::default(),
+    };
+    build_map(root, &prefix, budget_tokens)
+}
+
+/// Run the map command
+pub fn run(
+    path: Option<&str>,
+    budget_tokens: usize,
+    markdown: bool,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let root = get_root_with_index(std::env::current_dir()?);
+    let map = build(&root, path, budget_tokens)?;
+
+    if markdown {
+        print!("{}", render_markdown(&map));
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Json | OutputFormat::Json2 => {
+            print_json(&map, compact)?;
+        }
+        OutputFormat::Text => {
+            let use_color = crate::output::use_colors();
+            print!("{}", render_text(&map, use_color));
+            println!(
+                "\n{} {} symbols from {} files ({}/{} tokens)",
+                if use_color {
+                    "✓".green().to_string()
+                } else {
+                    "✓".to_string()
+                },
+                map.meta.symbols_included,
+                map.results.len(),
+                map.meta.tokens_used,
+                map.meta.budget_tokens
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, name: &str, content: &str) {
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        std::fs::write(path, content).expect("write file");
+    }
+
+    fn repo() -> TempDir {
+        let dir = TempDir::new().expect("tempdir");
+        write(
+            dir.path(),
+            "src/store.rs",
+            "pub struct Store {\n    items: Vec<u32>,\n}\n\nimpl Store {\n    pub fn get(&self) -> u32 {\n        self.items[0]\n    }\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/a.rs",
+            "pub fn use_a(store: &Store) -> u32 {\n    store.get()\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/b.rs",
+            "pub fn use_b(store: &Store) -> u32 {\n    store.get() + 1\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/unused.rs",
+            "pub fn lonely() -> u32 {\n    7\n}\n",
+        );
+        dir
+    }
+
+    #[test]
+    fn referenced_symbols_rank_first() {
+        let dir = repo();
+        let map = build_map(dir.path(), Path::new(""), 1_000).expect("map");
+        assert_eq!(map.results[0].path, "src/store.rs");
+        let names: Vec<&str> = map.results[0]
+            .symbols
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Store", "get"]);
+        assert_eq!(
+            map.results[0].symbols[1].signature,
+            "pub fn get(&self) -> u32"
+        );
+        assert_eq!(map.results.last().expect("files").path, "src/unused.rs");
+        assert_eq!(map.meta.symbols_included, 5);
+    }
+
+    #[test]
+    fn budget_and_path_limit_the_map() {
+        let dir = repo();
+        let small = build_map(dir.path(), Path::new(""), 12).expect("map");
+        assert!(small.meta.tokens_used <= 12);
+        assert_eq!(small.results.len(), 1);
+        assert_eq!(small.results[0].path, "src/store.rs");
+
+        let scoped = build_map(dir.path(), Path::new("src/a.rs"), 1_000).expect("map");
+        assert_eq!(scoped.meta.symbols_total, 1);
+        assert_eq!(scoped.results[0].symbols[0].name, "use_a");
+
+        let markdown = render_markdown(&scoped);
+        assert_eq!(
+            markdown,
+            "## `src/a.rs`\n\n- `pub fn use_a(store: &Store) -> u32` (function, line 1)\n"
+        );
+    }
+
+    #[test]
+    fn unresolved_qualified_names_and_test_helpers_rank_low() {
+        let dir = TempDir::new().expect("tempdir");
+        write(
+            dir.path(),
+            "src/store.rs",
+            "pub fn save_all(items: &[u32]) -> usize {\n    items.len()\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/main.rs",
+            "mod store;\n\nfn main() {\n    store::save_all(&[1]);\n}\n",
+        );
+        // `write` helpers that every `fs::write(` and `.write(` would credit
+        write(
+            dir.path(),
+            "src/util.rs",
+            "pub fn write(buf: &mut Vec<u8>) {\n    buf.push(0);\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/cache.rs",
+            "pub fn cached() {}\n\n#[cfg(test)]\nmod tests {\n    fn write(path: &str) {\n        std::fs::write(path, \"\").unwrap();\n    }\n\n    fn saves() {\n        write(\"a\");\n        write(\"b\");\n    }\n}\n",
+        );
+        write(
+            dir.path(),
+            "src/io.rs",
+            "pub fn dump(out: &mut File) {\n    out.write(b\"a\");\n    out.write(b\"b\");\n    std::fs::write(\"x\", \"\");\n    std::fs::write(\"y\", \"\");\n}\n",
+        );
+
+        let map = build_map(dir.path(), Path::new(""), 1_000).expect("map");
+        let score = |path: &str, name: &str, line: usize| {
+            map.results
+                .iter()
+                .filter(|file| file.path == path)
+                .flat_map(|file| &file.symbols)
+                .find(|symbol| symbol.name == name && symbol.line == line)
+                .map(|symbol| symbol.score)
+                .expect("symbol")
+        };
+        let save_all = score("src/store.rs", "save_all", 1);
+        assert_eq!(map.results[0].path, "src/store.rs");
+        assert!(save_all > score("src/util.rs", "write", 1));
+        assert!(save_all > score("src/cache.rs", "write", 5));
+    }
+
+    #[test]
+    fn wrapped_signatures_are_joined() {
+        let lines = vec![
+            "pub fn open(",
+            "    root: &Path,",
+            "    ids: &[String],",
+            ") -> Result<Self> {",
+            "    todo!()",
+        ];
+        assert_eq!(
+            signature_of(&lines, 1),
+            "pub fn open(root: &Path, ids: &[String]) -> Result<Self>"
+        );
+        assert_eq!(signature_of(&lines, 5), "todo!()");
+    }
+}
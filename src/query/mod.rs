@@ -4,10 +4,19 @@
 
 pub mod agent;
 pub mod callers;
+pub mod callgraph;
 pub mod changed_files;
+pub mod context;
 pub mod definition;
 pub mod dependents;
+pub mod deps;
+pub mod graph;
 pub mod index_filter;
+pub mod map;
+pub mod outline;
 pub mod references;
+pub mod result_ids;
+pub mod scope;
 pub mod search;
 pub mod symbols;
+pub mod syntax;
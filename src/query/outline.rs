@@ -0,0 +1,298 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! File outline: the nested symbol tree of a file.
+//!
+//! Symbols from `SymbolExtractor` are nested by byte-range containment.
+//! Containers that are not symbols themselves (Rust `impl` blocks) become
+//! nodes of their own, so methods sit under the type they implement.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::indexer::scanner::detect_language;
+use crate::output::{print_json, OutputFormat};
+use crate::parser::languages::LANGUAGES;
+use crate::parser::symbols::SymbolExtractor;
+use crate::query::dependents::target_rel_path;
+use crate::query::map::signature_of;
+use crate::utils::get_root_with_index;
+
+/// Symbol with the symbols nested inside it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlineNode {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub signature: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<OutlineNode>,
+}
+
+impl OutlineNode {
+    fn count(&self) -> usize {
+        1 + self.children.iter().map(OutlineNode::count).sum::<usize>()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileOutline {
+    pub path: String,
+    /// `None` when the file's language has no parser
+    pub language: Option<String>,
+    pub symbols: Vec<OutlineNode>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutlineMeta {
+    schema_version: &'static str,
+    root: String,
+    files: usize,
+    symbols: usize,
+    elapsed_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct OutlineReport {
+    meta: OutlineMeta,
+    results: Vec<FileOutline>,
+}
+
+/// Outline kind of a container that is not a symbol
+fn container_kind(node_kind: &str) -> &'static str {
+    match node_kind {
+        "impl_item" => "impl",
+        "mod_item" | "internal_module" | "namespace_definition" | "module" => "module",
+        _ => "class",
+    }
+}
+
+/// Nested symbols of `source`, outermost first and in file order
+pub fn outline_source(source: &str, language: &str, rel_path: &Path) -> Result<Vec<OutlineNode>> {
+    let extractor = SymbolExtractor::new();
+    let symbols = extractor.extract_for_path(source, language, rel_path)?;
+    let containers = extractor.extract_containers(source, language)?;
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut spans: Vec<(usize, usize, OutlineNode)> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            let node = OutlineNode {
+                name: symbol.name.clone(),
+                kind: symbol.kind.to_string(),
+                line: symbol.line,
+                end_line: symbol.end_line,
+                signature: signature_of(&lines, symbol.line),
+                children: Vec::new(),
+            };
+            Some((symbol.byte_start?, symbol.byte_end?, node))
+        })
+        .collect();
+    for container in containers {
+        let is_symbol = symbols.iter().any(|symbol| {
+            symbol.byte_start == Some(container.byte_start)
+                && symbol.byte_end == Some(container.byte_end)
+        });
+        if is_symbol {
+            continue;
+        }
+        let node = OutlineNode {
+            name: container.name,
+            kind: container_kind(&container.node_kind).to_string(),
+            line: container.line,
+            end_line: container.end_line,
+            signature: signature_of(&lines, container.line),
+            children: Vec::new(),
+        };
+        spans.push((container.byte_start, container.byte_end, node));
+    }
+    // Parents sort before the children they contain
+    spans.sort_by_key(|(start, end, _)| (*start, Reverse(*end)));
+
+    let mut roots = Vec::new();
+    let mut open: Vec<(usize, OutlineNode)> = Vec::new();
+    let close = |open: &mut Vec<(usize, OutlineNode)>, roots: &mut Vec<OutlineNode>| {
+        if let Some((_, node)) = open.pop() {
+            match open.last_mut() {
+                Some((_, parent)) => parent.children.push(node),
+                None => roots.push(node),
+            }
+        }
+    };
+    for (start, end, node) in spans {
+        while open.last().is_some_and(|(open_end, _)| *open_end <= start) {
+            close(&mut open, &mut roots);
+        }
+        open.push((end, node));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    Ok(roots)
+}
+
+/// Outlines of `files`, given relative to the current directory or absolute
+pub fn find(root: &Path, files: &[String]) -> Result<Vec<FileOutline>> {
+    files.iter().map(|file| outline_file(root, file)).collect()
+}
+
+/// Outline of one file, given relative to the current directory
+fn outline_file(root: &Path, file: &str) -> Result<FileOutline> {
+    let rel_path = target_rel_path(root, file)?;
+    let full_path = root.join(&rel_path);
+    let source = std::fs::read_to_string(&full_path)
+        .with_context(|| format!("Failed to read {}", full_path.display()))?;
+    let language = full_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(detect_language)
+        .filter(|lang| LANGUAGES.get(lang).is_some());
+    let symbols = match &language {
+        Some(language) => outline_source(&source, language, &rel_path)?,
+        None => Vec::new(),
+    };
+    Ok(FileOutline {
+        path: rel_path.to_string_lossy().replace('\\', "/"),
+        language,
+        symbols,
+    })
+}
+
+fn render_nodes(out: &mut String, nodes: &[OutlineNode], depth: usize, use_color: bool) {
+    for node in nodes {
+        let range = format!("{:>5}-{:<5}", node.line, node.end_line);
+        let kind = format!("({})", node.kind);
+        let (range, kind) = if use_color {
+            (range.yellow().to_string(), kind.dimmed().to_string())
+        } else {
+            (range, kind)
+        };
+        out.push_str(&format!(
+            "{} {}{} {}\n",
+            range,
+            "  ".repeat(depth),
+            node.signature,
+            kind
+        ));
+        render_nodes(out, &node.children, depth + 1, use_color);
+    }
+}
+
+fn render_text(outlines: &[FileOutline], use_color: bool) -> String {
+    let mut out = String::new();
+    for (idx, outline) in outlines.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        let path = if use_color {
+            outline.path.cyan().to_string()
+        } else {
+            outline.path.clone()
+        };
+        out.push_str(&path);
+        out.push('\n');
+        if outline.symbols.is_empty() {
+            out.push_str("  (no symbols)\n");
+        }
+        render_nodes(&mut out, &outline.symbols, 0, use_color);
+    }
+    out
+}
+
+/// Run the outline command
+pub fn run(files: &[String], format: OutputFormat, compact: bool) -> Result<()> {
+    let start = Instant::now();
+    let root = get_root_with_index(std::env::current_dir()?);
+    let outlines = find(&root, files)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Json2 => {
+            let report = OutlineReport {
+                meta: OutlineMeta {
+                    schema_version: "1",
+                    root: root.display().to_string(),
+                    files: outlines.len(),
+                    symbols: outlines
+                        .iter()
+                        .flat_map(|outline| &outline.symbols)
+                        .map(OutlineNode::count)
+                        .sum(),
+                    elapsed_ms: start.elapsed().as_secs_f64() * 1000.0,
+                },
+                results: outlines,
+            };
+            print_json(&report, compact)?;
+        }
+        OutputFormat::Text => {
+            print!("{}", render_text(&outlines, crate::output::use_colors()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(nodes: &[OutlineNode]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|node| {
+                let children = summary(&node.children);
+                if children.is_empty() {
+                    format!("{} {}", node.kind, node.name)
+                } else {
+                    format!("{} {} [{}]", node.kind, node.name, children.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nests_methods_under_impl_blocks_and_modules() {
+        let source = "pub struct Store {\n    value: u32,\n}\n\nimpl Store {\n    pub fn get(&self) -> u32 {\n        self.value\n    }\n}\n\nmod inner {\n    pub fn helper() {}\n}\n";
+        let outline = outline_source(source, "rust", Path::new("src/store.rs")).expect("outline");
+        assert_eq!(
+            summary(&outline),
+            vec![
+                "struct Store",
+                "impl Store [function get]",
+                "module inner [function helper]",
+            ]
+        );
+        let get = &outline[1].children[0];
+        assert_eq!((get.line, get.end_line), (6, 8));
+        assert_eq!(get.signature, "pub fn get(&self) -> u32");
+        assert_eq!(outline[1].signature, "impl Store");
+    }
+
+    #[test]
+    fn nests_python_methods_under_classes() {
+        let source = "class Calculator:\n    def add(self, a, b):\n        return a + b\n\n    def sub(self, a, b):\n        return a - b\n\ndef main():\n    pass\n";
+        let outline = outline_source(source, "python", Path::new("calc.py")).expect("outline");
+        assert_eq!(
+            summary(&outline),
+            vec![
+                "class Calculator [function add, function sub]",
+                "function main"
+            ]
+        );
+        let text = render_text(
+            &[FileOutline {
+                path: "calc.py".to_string(),
+                language: Some("python".to_string()),
+                symbols: outline,
+            }],
+            false,
+        );
+        assert_eq!(
+            text,
+            "calc.py\n    1-6     class Calculator: (class)\n    2-3       def add(self, a, b): (function)\n    5-6       def sub(self, a, b): (function)\n    8-9     def main(): (function)\n"
+        );
+    }
+}
@@ -6,57 +6,92 @@ use anyhow::Result;
 use colored::Colorize;
 use regex::Regex;
 use serde::Serialize;
+use std::path::Path;
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::FileScanner;
+use crate::indexer::scanner::{FileScanner, ScannedFile};
+use crate::output::print_json;
+use crate::output::OutputFormat;
+use crate::parser::languages::LANGUAGES;
+use crate::parser::references::{Reference, ReferenceExtractor, ReferenceKind};
 use crate::query::changed_files::ChangedFiles;
 use crate::query::index_filter::{find_files_with_content, read_scanned_files};
-use cgrep::output::print_json;
-use cgrep::utils::get_root_with_index;
+use crate::utils::get_root_with_index;
 
 /// Reference result for JSON output
 #[derive(Debug, Serialize)]
-struct ReferenceResult {
-    path: String,
-    line: usize,
-    column: usize,
-    code: String,
+pub struct ReferenceResult {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ReferenceKind,
+    pub code: String,
 }
 
-/// Run the references command
-pub fn run(
+/// Classified occurrences in one file; files without a grammar fall back to
+/// word matches
+fn file_references(
+    extractor: &ReferenceExtractor,
+    file: &ScannedFile,
+    name: &str,
+    re: &Regex,
+) -> Vec<Reference> {
+    let parsed = file
+        .language
+        .as_deref()
+        .filter(|lang| LANGUAGES.get(lang).is_some())
+        .and_then(|lang| extractor.extract(&file.content, lang, name).ok());
+    if let Some(refs) = parsed {
+        return refs;
+    }
+
+    let mut refs = Vec::new();
+    let mut offset = 0usize;
+    for (line_num, line) in file.content.split('\n').enumerate() {
+        for mat in re.find_iter(line) {
+            refs.push(Reference {
+                kind: ReferenceKind::Text,
+                line: line_num + 1,
+                column: mat.start() + 1,
+                byte_start: offset + mat.start(),
+                byte_end: offset + mat.end(),
+            });
+        }
+        offset += line.len() + 1;
+    }
+    refs
+}
+
+/// References to `name` under `root`, optionally limited to `kinds`
+pub fn find(
+    root: &Path,
     name: &str,
-    path: Option<&str>,
     max_results: usize,
     changed: Option<&str>,
-    format: OutputFormat,
-    compact: bool,
-) -> Result<()> {
-    let root = match path {
-        Some(p) => get_root_with_index(std::path::PathBuf::from(p).canonicalize()?),
-        None => get_root_with_index(std::env::current_dir()?),
-    };
-    let files = match find_files_with_content(&root, name)? {
+    kinds: &[ReferenceKind],
+    exclude_comments: bool,
+) -> Result<Vec<ReferenceResult>> {
+    let files = match find_files_with_content(root, name)? {
         Some(indexed_paths) => read_scanned_files(&indexed_paths),
         None => {
-            let scanner = FileScanner::new(&root);
+            let scanner = FileScanner::new(root);
             scanner.scan()?
         }
     };
     let changed_filter = changed
-        .map(|rev| ChangedFiles::from_scope(&root, rev))
+        .map(|rev| ChangedFiles::from_scope(root, rev))
         .transpose()?;
 
     // Pattern to match symbol with word boundaries
     let pattern = format!(r"\b{}\b", regex::escape(name));
     let re = Regex::new(&pattern)?;
 
+    let extractor = ReferenceExtractor::new();
     let mut results: Vec<ReferenceResult> = Vec::new();
 
     for file in &files {
         let rel_path = file
             .path
-            .strip_prefix(&root)
+            .strip_prefix(root)
             .unwrap_or(&file.path)
             .display()
             .to_string();
@@ -66,18 +101,32 @@ pub fn run(
             }
         }
 
-        for (line_num, line) in file.content.lines().enumerate() {
-            if let Some(mat) = re.find(line) {
-                results.push(ReferenceResult {
-                    path: rel_path.clone(),
-                    line: line_num + 1,
-                    column: mat.start() + 1,
-                    code: line.trim().to_string(),
-                });
-
-                if results.len() >= max_results {
-                    break;
-                }
+        let lines: Vec<&str> = file.content.lines().collect();
+        for reference in file_references(&extractor, file, name, &re) {
+            if !kinds.is_empty() && !kinds.contains(&reference.kind) {
+                continue;
+            }
+            if exclude_comments
+                && matches!(
+                    reference.kind,
+                    ReferenceKind::Comment | ReferenceKind::String
+                )
+            {
+                continue;
+            }
+
+            results.push(ReferenceResult {
+                path: rel_path.clone(),
+                line: reference.line,
+                column: reference.column,
+                kind: reference.kind,
+                code: lines
+                    .get(reference.line.saturating_sub(1))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_default(),
+            });
+            if results.len() >= max_results {
+                break;
             }
         }
 
@@ -86,6 +135,27 @@ pub fn run(
         }
     }
 
+    Ok(results)
+}
+
+/// Run the references command
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    name: &str,
+    path: Option<&str>,
+    max_results: usize,
+    changed: Option<&str>,
+    kinds: &[ReferenceKind],
+    exclude_comments: bool,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let root = match path {
+        Some(p) => get_root_with_index(std::path::PathBuf::from(p).canonicalize()?),
+        None => get_root_with_index(std::env::current_dir()?),
+    };
+    let results = find(&root, name, max_results, changed, kinds, exclude_comments)?;
+
     match format {
         OutputFormat::Json | OutputFormat::Json2 => {
             print_json(&results, compact)?;
@@ -101,10 +171,11 @@ pub fn run(
                 );
                 for result in &results {
                     println!(
-                        "  {}:{}:{} {}",
+                        "  {}:{}:{} [{}] {}",
                         result.path.cyan(),
                         result.line.to_string().yellow(),
                         result.column.to_string().dimmed(),
+                        result.kind.to_string().blue(),
                         result.code.dimmed()
                     );
                 }
@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Result IDs recorded for `agent expand`.
+//!
+//! IDs are anchored to the innermost enclosing symbol rather than a line
+//! number: an ID hashes the file path, the symbol's qualified name, the
+//! trimmed line and how many identical lines precede it inside that symbol.
+//! Edits elsewhere in the file leave IDs intact, and a moved line is found
+//! again inside its symbol's current range.
+//!
+//! Every json2 search (`agent locate`, MCP, batch) records the IDs it emits in
+//! `.cgrep/result_ids.sqlite` with the file, line, anchor and content hashes
+//! they point at, so `agent expand` resolves IDs with direct lookups and reads
+//! only the files involved.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::indexer::scanner::detect_language;
+use crate::parser::symbols::{qualify, SymbolExtractor};
+use crate::utils::INDEX_DIR;
+
+/// File name of the result ID store inside the index directory.
+pub const RESULT_ID_FILE: &str = "result_ids.sqlite";
+
+/// Bump when the table layout or stored values change; older stores are
+/// reset on open.
+const SCHEMA_VERSION: i64 = 2;
+
+/// Entries older than this are pruned whenever new ones are recorded.
+const RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Where a result ID pointed when it was emitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultLocation {
+    pub id: String,
+    /// Path relative to the index root
+    pub path: String,
+    pub line: usize,
+    pub anchor: LineAnchor,
+    /// [`content_hash`] of the whole file
+    pub file_hash: String,
+    /// [`content_hash`] of the trimmed result line
+    pub line_hash: String,
+}
+
+/// Where a line sits relative to the symbols of its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineAnchor {
+    /// Qualified name of the innermost enclosing symbol
+    pub symbol: Option<String>,
+    /// Current line range of that symbol (the whole file when there is none)
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Identical (trimmed) lines before this one inside the range
+    pub occurrence: usize,
+}
+
+/// Short blake3 digest used to detect edits since an ID was recorded.
+pub fn content_hash(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex()[..16].to_string()
+}
+
+/// Edit-stable result ID for `line_text` at `anchor` in `path`.
+pub fn anchored_id(path: &str, anchor: &LineAnchor, line_text: &str) -> String {
+    let payload = format!(
+        "{}:{}:{}:{}",
+        path,
+        anchor.symbol.as_deref().unwrap_or(""),
+        line_text.trim(),
+        anchor.occurrence
+    );
+    content_hash(payload.as_bytes())
+}
+
+/// Symbol ranges of one file, for anchoring and re-finding its lines.
+pub struct FileAnchors<'a> {
+    lines: Vec<&'a str>,
+    /// (qualified name, start line, end line)
+    symbols: Vec<(String, usize, usize)>,
+    /// Innermost symbol index per line (0-based line)
+    innermost: Vec<Option<usize>>,
+    /// 1-based line numbers of each trimmed line text, ascending
+    positions: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> FileAnchors<'a> {
+    /// Parse `content`; `rel_path` (relative to the index root) picks the
+    /// grammar and qualifies scopes. Unsupported files anchor to the file.
+    pub fn parse(rel_path: &Path, content: &'a str) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let language = rel_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(detect_language);
+        let mut symbols: Vec<(String, usize, usize)> = language
+            .and_then(|language| {
+                let extracted = SymbolExtractor::new()
+                    .extract_for_path(content, &language, rel_path)
+                    .ok()?;
+                Some(
+                    extracted
+                        .into_iter()
+                        .filter(|s| s.line >= 1 && s.end_line >= s.line)
+                        .map(|s| {
+                            let name = qualify(s.scope.as_deref(), &s.name, &language);
+                            (name, s.line, s.end_line.min(lines.len()))
+                        })
+                        .collect(),
+                )
+            })
+            .unwrap_or_default();
+        // Wider symbols first so nested ones overwrite them below
+        symbols.sort_by_key(|(_, start, end)| std::cmp::Reverse(end.saturating_sub(*start)));
+
+        let mut innermost = vec![None; lines.len()];
+        for (idx, (_, start, end)) in symbols.iter().enumerate() {
+            for slot in innermost.iter_mut().take(*end).skip(start - 1) {
+                *slot = Some(idx);
+            }
+        }
+
+        let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (idx, line) in lines.iter().enumerate() {
+            positions.entry(line.trim()).or_default().push(idx + 1);
+        }
+
+        Self {
+            lines,
+            symbols,
+            innermost,
+            positions,
+        }
+    }
+
+    /// Text of a 1-based line.
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        line.checked_sub(1)
+            .and_then(|idx| self.lines.get(idx).copied())
+    }
+
+    /// Anchor for a 1-based line, `None` when it is out of range.
+    pub fn anchor(&self, line: usize) -> Option<LineAnchor> {
+        let text = self.line(line)?.trim();
+        let (symbol, start_line, end_line) = match self.innermost[line - 1] {
+            Some(idx) => {
+                let (name, start, end) = &self.symbols[idx];
+                (Some(name.clone()), *start, *end)
+            }
+            None => (None, 1, self.lines.len()),
+        };
+        let occurrence = self.positions[text]
+            .iter()
+            .filter(|&&pos| pos >= start_line && pos < line)
+            .count();
+        Some(LineAnchor {
+            symbol,
+            start_line,
+            end_line,
+            occurrence,
+        })
+    }
+
+    /// Current line of a recorded anchor, searching the symbol's range (or
+    /// the whole file for top-level lines).
+    pub fn find(&self, symbol: Option<&str>, line_hash: &str, occurrence: usize) -> Option<usize> {
+        let (start, end) = match symbol {
+            Some(symbol) => self
+                .symbols
+                .iter()
+                .find(|(name, _, _)| name == symbol)
+                .map(|(_, start, end)| (*start, *end))?,
+            None => (1, self.lines.len()),
+        };
+        (start..=end)
+            .filter(|&line| {
+                self.line(line)
+                    .is_some_and(|text| content_hash(text.trim().as_bytes()) == line_hash)
+            })
+            .nth(occurrence)
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// SQLite-backed map from result ID to [`ResultLocation`].
+pub struct ResultIdStore {
+    conn: Connection,
+}
+
+impl ResultIdStore {
+    /// Location of the store for a repository root.
+    pub fn path_for(root: &Path) -> PathBuf {
+        root.join(INDEX_DIR).join(RESULT_ID_FILE)
+    }
+
+    /// Opens or creates the store for an indexed repository root.
+    pub fn open(root: &Path) -> Result<Self> {
+        let path = Self::path_for(root);
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open result ID store: {}", path.display()))?;
+        conn.busy_timeout(std::time::Duration::from_secs(2))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch("DROP TABLE IF EXISTS result_ids;")?;
+        }
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS result_ids (
+                id TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                symbol TEXT,
+                start_line INTEGER NOT NULL,
+                end_line INTEGER NOT NULL,
+                occurrence INTEGER NOT NULL,
+                file_hash TEXT NOT NULL,
+                line_hash TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_result_ids_recorded_at ON result_ids(recorded_at);
+            "#,
+        )
+        .context("Failed to initialize result ID store schema")?;
+        conn.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+        Ok(Self { conn })
+    }
+
+    /// Opens the store for a repository if any IDs have been recorded.
+    pub fn open_existing(root: &Path) -> Result<Option<Self>> {
+        if !Self::path_for(root).exists() {
+            return Ok(None);
+        }
+        Self::open(root).map(Some)
+    }
+
+    /// Records (or refreshes) locations and prunes expired entries.
+    pub fn record(&mut self, locations: &[ResultLocation]) -> Result<()> {
+        let now = unix_now();
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO result_ids \
+                 (id, path, line, symbol, start_line, end_line, occurrence, file_hash, \
+                 line_hash, recorded_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for location in locations {
+                stmt.execute(params![
+                    location.id,
+                    location.path,
+                    location.line as i64,
+                    location.anchor.symbol,
+                    location.anchor.start_line as i64,
+                    location.anchor.end_line as i64,
+                    location.anchor.occurrence as i64,
+                    location.file_hash,
+                    location.line_hash,
+                    now,
+                ])?;
+            }
+            tx.execute(
+                "DELETE FROM result_ids WHERE recorded_at < ?1",
+                params![now - RETENTION_SECS],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Looks up one ID.
+    pub fn get(&self, id: &str) -> Result<Option<ResultLocation>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, path, line, symbol, start_line, end_line, occurrence, file_hash, \
+             line_hash \
+             FROM result_ids WHERE id = ?1",
+        )?;
+        let location = stmt
+            .query_row(params![id], |row| {
+                Ok(ResultLocation {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    line: row.get::<_, i64>(2)? as usize,
+                    anchor: LineAnchor {
+                        symbol: row.get(3)?,
+                        start_line: row.get::<_, i64>(4)? as usize,
+                        end_line: row.get::<_, i64>(5)? as usize,
+                        occurrence: row.get::<_, i64>(6)? as usize,
+                    },
+                    file_hash: row.get(7)?,
+                    line_hash: row.get(8)?,
+                })
+            })
+            .optional()?;
+        Ok(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn location(id: &str, line: usize) -> ResultLocation {
+        ResultLocation {
+            id: id.to_string(),
+            path: "src/lib.rs".to_string(),
+            line,
+            anchor: LineAnchor {
+                symbol: Some("crate::a".to_string()),
+                start_line: line,
+                end_line: line + 3,
+                occurrence: 0,
+            },
+            file_hash: content_hash(b"file"),
+            line_hash: content_hash(b"fn a() {}"),
+        }
+    }
+
+    #[test]
+    fn records_and_replaces_locations() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join(INDEX_DIR)).expect("index dir");
+        assert!(ResultIdStore::open_existing(dir.path())
+            .expect("open")
+            .is_none());
+
+        let mut store = ResultIdStore::open(dir.path()).expect("open");
+        store
+            .record(&[location("a", 3), location("b", 9)])
+            .expect("record");
+        store.record(&[location("a", 4)]).expect("re-record");
+
+        let store = ResultIdStore::open_existing(dir.path())
+            .expect("open")
+            .expect("exists");
+        assert_eq!(store.get("a").expect("get"), Some(location("a", 4)));
+        assert_eq!(store.get("b").expect("get"), Some(location("b", 9)));
+        assert_eq!(store.get("missing").expect("get"), None);
+    }
+
+    #[test]
+    fn anchors_survive_edits_outside_the_symbol() {
+        let before = "use std::fmt;\n\nfn alpha() {\n    let x = 1;\n    log(x);\n    log(x);\n}\n";
+        let after = "use std::fmt;\nuse std::io;\n\n// alpha\nfn alpha() {\n    let x = 1;\n    log(x);\n    log(x);\n}\n";
+        let path = Path::new("src/lib.rs");
+
+        let old = FileAnchors::parse(path, before);
+        let anchor = old.anchor(6).expect("anchor");
+        assert_eq!(anchor.symbol.as_deref(), Some("crate::alpha"));
+        assert_eq!((anchor.start_line, anchor.end_line), (3, 7));
+        assert_eq!(anchor.occurrence, 1);
+        let id = anchored_id("src/lib.rs", &anchor, old.line(6).expect("line"));
+
+        let new = FileAnchors::parse(path, after);
+        let line_hash = content_hash(b"log(x);");
+        let moved = new
+            .find(anchor.symbol.as_deref(), &line_hash, anchor.occurrence)
+            .expect("found");
+        assert_eq!(moved, 8);
+        let new_anchor = new.anchor(moved).expect("anchor");
+        assert_eq!(
+            anchored_id("src/lib.rs", &new_anchor, new.line(moved).expect("line")),
+            id
+        );
+
+        let top_level = new.anchor(2).expect("anchor");
+        assert_eq!(top_level.symbol, None);
+        assert_eq!(top_level.start_line, 1);
+    }
+}
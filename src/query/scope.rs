@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Symbol-scoped context for search hits and expanded result IDs.
+//!
+//! Instead of a symmetric N-line window, `--scope symbol` returns the
+//! innermost function, method or type around a hit and `--scope signature`
+//! only its declaration, up to where the body opens.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::indexer::scanner::detect_language;
+use crate::parser::symbols::{Symbol, SymbolExtractor, SymbolKind};
+
+/// How much context to return around a hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextScope {
+    /// N lines before and after the hit (`-C`)
+    #[default]
+    Lines,
+    /// The whole enclosing function, method or type
+    Symbol,
+    /// The enclosing symbol's declaration, without its body
+    Signature,
+}
+
+/// Line range of a symbol that can enclose a hit (1-based, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolSpan {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Last line of the declaration, where the body opens
+    pub signature_end_line: usize,
+}
+
+impl SymbolSpan {
+    /// Span for a known line range of `lines` (clamped to the file)
+    pub fn from_lines(lines: &[&str], start_line: usize, end_line: usize) -> Option<Self> {
+        let end_line = end_line.min(lines.len());
+        if start_line == 0 || start_line > end_line {
+            return None;
+        }
+        let signature_end_line = (start_line..=end_line)
+            .find(|&line| {
+                let text = lines[line - 1].trim_end();
+                text.contains('{') || text.ends_with(':')
+            })
+            .unwrap_or(start_line);
+        Some(Self {
+            start_line,
+            end_line,
+            signature_end_line,
+        })
+    }
+
+    fn from_symbol(symbol: &Symbol, content: &str, lines: &[&str]) -> Option<Self> {
+        let line_of = |byte: usize| {
+            content
+                .get(..byte)
+                .map(|prefix| prefix.matches('\n').count() + 1)
+        };
+        let start_line = symbol.byte_start.and_then(line_of).unwrap_or(symbol.line);
+        // Node ranges are end-exclusive; a trailing newline belongs to the last line
+        let end_line = symbol
+            .byte_end
+            .map(|end| match content.as_bytes().get(end.wrapping_sub(1)) {
+                Some(b'\n') => end - 1,
+                _ => end,
+            })
+            .and_then(line_of)
+            .unwrap_or(symbol.end_line);
+        Self::from_lines(lines, start_line, end_line)
+    }
+}
+
+/// Enclosing-symbol spans of one file
+pub struct SymbolSpans {
+    spans: Vec<SymbolSpan>,
+}
+
+impl SymbolSpans {
+    /// Parse `content`; `path` picks the grammar. Unsupported files have no spans.
+    pub fn parse(path: &Path, content: &str) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let spans = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(detect_language)
+            .and_then(|language| SymbolExtractor::new().extract(content, &language).ok())
+            .unwrap_or_default()
+            .iter()
+            .filter(|symbol| encloses_code(&symbol.kind))
+            .filter_map(|symbol| SymbolSpan::from_symbol(symbol, content, &lines))
+            .collect();
+        Self { spans }
+    }
+
+    /// Innermost span containing a 1-based line
+    pub fn enclosing(&self, line: usize) -> Option<SymbolSpan> {
+        self.spans
+            .iter()
+            .filter(|span| span.start_line <= line && line <= span.end_line)
+            .min_by_key(|span| {
+                (
+                    span.end_line - span.start_line,
+                    std::cmp::Reverse(span.start_line),
+                )
+            })
+            .copied()
+    }
+}
+
+fn encloses_code(kind: &SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Function
+            | SymbolKind::Method
+            | SymbolKind::Class
+            | SymbolKind::Struct
+            | SymbolKind::Interface
+            | SymbolKind::Trait
+            | SymbolKind::Enum
+    )
+}
+
+/// Context lines for a hit at `line` inside `span`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopedContext {
+    /// Line of the first `before` entry
+    pub start_line: usize,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl ScopedContext {
+    /// Context for `scope`; `None` for [`ContextScope::Lines`]
+    pub fn new(lines: &[&str], line: usize, span: SymbolSpan, scope: ContextScope) -> Option<Self> {
+        let end_line = match scope {
+            ContextScope::Lines => return None,
+            ContextScope::Symbol => span.end_line,
+            ContextScope::Signature => span.signature_end_line,
+        };
+        let collect = |from: usize, to: usize| -> Vec<String> {
+            (from..=to)
+                .filter_map(|n| lines.get(n - 1).map(|text| text.to_string()))
+                .collect()
+        };
+        // Hits below the signature keep only the declaration above them
+        let before_end = (line - 1).min(end_line);
+        Some(Self {
+            start_line: span.start_line,
+            before: collect(span.start_line, before_end),
+            after: collect(line + 1, end_line),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+use std::fmt;
+
+struct Parser {
+    depth: usize,
+}
+
+impl Parser {
+    fn parse(
+        &self,
+        input: &str,
+    ) -> usize {
+        let trimmed = input.trim();
+        trimmed.len() + self.depth
+    }
+}
+";
+
+    #[test]
+    fn symbol_scope_returns_the_innermost_function() {
+        let lines: Vec<&str> = SOURCE.lines().collect();
+        let spans = SymbolSpans::parse(Path::new("lib.rs"), SOURCE);
+        let span = spans.enclosing(13).expect("enclosing fn");
+        assert_eq!((span.start_line, span.end_line), (8, 14));
+        assert_eq!(span.signature_end_line, 11);
+        assert!(spans.enclosing(1).is_none());
+
+        let context =
+            ScopedContext::new(&lines, 13, span, ContextScope::Symbol).expect("symbol scope");
+        assert_eq!(context.start_line, 8);
+        assert_eq!(context.before.len(), 5);
+        assert_eq!(context.after, vec!["    }"]);
+    }
+
+    #[test]
+    fn signature_scope_stops_where_the_body_opens() {
+        let lines: Vec<&str> = SOURCE.lines().collect();
+        let span = SymbolSpans::parse(Path::new("lib.rs"), SOURCE)
+            .enclosing(13)
+            .expect("enclosing fn");
+
+        let in_body =
+            ScopedContext::new(&lines, 13, span, ContextScope::Signature).expect("signature");
+        assert_eq!(in_body.start_line, 8);
+        assert_eq!(
+            in_body.before.first().map(String::as_str),
+            Some("    fn parse(")
+        );
+        assert_eq!(
+            in_body.before.last().map(String::as_str),
+            Some("    ) -> usize {")
+        );
+        assert!(in_body.after.is_empty());
+
+        let in_signature =
+            ScopedContext::new(&lines, 9, span, ContextScope::Signature).expect("signature");
+        assert_eq!(in_signature.before, vec!["    fn parse("]);
+        assert_eq!(in_signature.after.len(), 2);
+
+        assert!(ScopedContext::new(&lines, 13, span, ContextScope::Lines).is_none());
+    }
+}
@@ -4,40 +4,53 @@
 
 use anyhow::{Context, Result};
 use colored::Colorize;
+use once_cell::sync::Lazy;
 use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Component, Path, PathBuf};
-use std::time::Instant;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tantivy::{
     collector::TopDocs,
     query::{BooleanQuery, FuzzyTermQuery, Occur, QueryParser, TermQuery},
     schema::{Term, Value},
-    Index, TantivyDocument,
+    TantivyDocument,
 };
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::FileScanner;
-use crate::query::changed_files::ChangedFiles;
-use cgrep::cache::{CacheKey, SearchCache};
-use cgrep::config::{Config, EmbeddingProviderType};
-use cgrep::embedding::{
+use crate::cache::{CacheKey, SearchCache};
+use crate::config::{Config, EmbeddingProviderType};
+use crate::daemon;
+use crate::embedding::{
     CommandProvider, DummyProvider, EmbeddingProvider, EmbeddingProviderConfig, EmbeddingStorage,
-    FastEmbedder, DEFAULT_EMBEDDING_DIM,
+    FastEmbedder, HttpProvider, DEFAULT_EMBEDDING_DIM,
 };
-use cgrep::errors::IndexNotFoundError;
-use cgrep::filters::{
+use crate::errors::IndexNotFoundError;
+use crate::filters::{
     matches_file_type, matches_glob_compiled, should_exclude_compiled, CompiledGlob,
 };
-use cgrep::hybrid::{
+use crate::hybrid::{
     BM25Result, HybridConfig, HybridResult, HybridSearcher, SearchMode as HybridSearchMode,
 };
-use cgrep::output::{
+use crate::indexer::scanner::FileScanner;
+use crate::indexer::tokenizer;
+use crate::output::OutputFormat;
+use crate::output::{
     colorize_context, colorize_line_num, colorize_match, colorize_path, print_json, use_colors,
 };
-use cgrep::utils::INDEX_DIR;
+use crate::query::changed_files::ChangedFiles;
+use crate::query::index_filter::{open_index, tokenize_for_field};
+use crate::query::result_ids::{
+    anchored_id, content_hash, FileAnchors, ResultIdStore, ResultLocation,
+};
+use crate::query::scope::{ContextScope, ScopedContext, SymbolSpan, SymbolSpans};
+use crate::query::syntax::StructuredQuery;
+use crate::session::{delivered_ranges, AlreadySent, SessionTracker};
+use crate::tokens::{count_tokens, truncate_to_tokens};
+use crate::utils::INDEX_DIR;
 const DEFAULT_CACHE_TTL_MS: u64 = 600_000; // 10 minutes
 
 /// Search result for internal use and text output
@@ -67,6 +80,21 @@ pub struct SearchResult {
     /// Symbol end line (for semantic/hybrid)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chunk_end: Option<u32>,
+    /// First line of `context_before` when it does not run up to the match
+    /// (`--scope signature` on a hit inside the body)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_start: Option<usize>,
+}
+
+impl SearchResult {
+    /// Line of the first context line (or of the match without context)
+    pub fn context_start_line(&self) -> Option<usize> {
+        let line = self.line?;
+        Some(
+            self.context_start
+                .unwrap_or_else(|| line.saturating_sub(self.context_before.len())),
+        )
+    }
 }
 
 /// Minimal search result for JSON output
@@ -121,18 +149,19 @@ impl<'a> SearchResultCompactJson<'a> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum IndexMode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum IndexMode {
     Index,
     Scan,
 }
 
-struct SearchOutcome {
-    results: Vec<SearchResult>,
-    files_with_matches: usize,
-    total_matches: usize,
-    mode: IndexMode,
-    cache_hit: bool,
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SearchOutcome {
+    pub(crate) results: Vec<SearchResult>,
+    pub(crate) files_with_matches: usize,
+    pub(crate) total_matches: usize,
+    pub(crate) mode: IndexMode,
+    pub(crate) cache_hit: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -160,6 +189,9 @@ struct SearchJson2Meta<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_total_chars: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    tokens_used: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_chars_per_snippet: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_context_chars: Option<usize>,
@@ -169,6 +201,8 @@ struct SearchJson2Meta<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     changed_rev: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     path_aliases: Option<BTreeMap<String, String>>,
 }
 
@@ -198,13 +232,8 @@ struct SearchJson2Result {
 
 impl SearchJson2Result {
     fn from_result(result: &SearchResult, include_context: bool, path_value: Option<&str>) -> Self {
-        let (start_line, end_line) = if let Some(line) = result.line {
-            let start = line.saturating_sub(result.context_before.len());
-            let end = line + result.context_after.len();
-            (Some(start), Some(end))
-        } else {
-            (None, None)
-        };
+        let start_line = result.context_start_line();
+        let end_line = result.line.map(|line| line + result.context_after.len());
 
         let id = result
             .result_id
@@ -240,63 +269,189 @@ impl SearchJson2Result {
 struct SearchJson2Payload<'a> {
     meta: SearchJson2Meta<'a>,
     results: Vec<SearchJson2Result>,
+    /// Hits whose lines the session already received
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    already_sent: &'a [AlreadySent],
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 struct SearchOutputBudget {
     max_chars_per_snippet: Option<usize>,
     max_total_chars: Option<usize>,
+    max_tokens: Option<usize>,
     max_context_chars: Option<usize>,
     dedupe_context: bool,
     suppress_boilerplate: bool,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 struct BudgetApplyStats {
     truncated: bool,
     dropped_results: usize,
+    /// Estimated tokens of the returned paths, snippets and context
+    tokens_used: usize,
 }
 
-/// Run the search command
-#[allow(clippy::too_many_arguments)]
-pub fn run(
-    query: &str,
-    path: Option<&str>,
-    max_results: usize,
-    context: usize,
-    file_type: Option<&str>,
-    glob_pattern: Option<&str>,
-    exclude_pattern: Option<&str>,
-    changed: Option<&str>,
-    quiet: bool,
-    fuzzy: bool,
-    no_index: bool,
-    regex: bool,
-    case_sensitive: bool,
-    format: OutputFormat,
-    compact: bool,
-    search_mode: Option<HybridSearchMode>,
+/// Search parameters, shared by the CLI and long-running front-ends (`cgrep mcp`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    pub query: String,
+    pub path: Option<String>,
+    pub max_results: usize,
+    pub context: usize,
+    /// Replace the `context` window with the enclosing symbol or its signature
+    pub scope: ContextScope,
+    pub file_type: Option<String>,
+    pub glob: Option<String>,
+    pub exclude: Option<String>,
+    pub changed: Option<String>,
+    pub fuzzy: bool,
+    pub no_index: bool,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub search_mode: Option<HybridSearchMode>,
+    pub context_pack: Option<usize>,
+    pub use_cache: bool,
+    pub cache_ttl: Option<u64>,
+    pub max_chars_per_snippet: Option<usize>,
+    pub max_total_chars: Option<usize>,
+    /// Total budget in estimated tokens, enforced after the character caps
+    pub max_tokens: Option<usize>,
+    pub max_context_chars: Option<usize>,
+    pub dedupe_context: bool,
+    pub path_alias: bool,
+    pub suppress_boilerplate: bool,
+    /// Agent session: return hits it already received as references
+    pub session: Option<String>,
+}
+
+/// Ranked results of one search after context packing and output budgets
+///
+/// Serializable so `cgrep daemon` can answer a search and the CLI prints it.
+#[derive(Serialize, Deserialize)]
+pub struct SearchReport {
+    pub(crate) outcome: SearchOutcome,
+    pub(crate) search_mode: HybridSearchMode,
     context_pack: Option<usize>,
-    use_cache: bool,
-    cache_ttl: Option<u64>,
-    max_chars_per_snippet: Option<usize>,
-    max_total_chars: Option<usize>,
-    max_context_chars: Option<usize>,
-    dedupe_context: bool,
-    path_alias: bool,
-    suppress_boilerplate: bool,
-) -> Result<()> {
+    budget: SearchOutputBudget,
+    budget_stats: BudgetApplyStats,
+    changed_rev: Option<String>,
+    #[serde(default)]
+    pub(crate) already_sent: Vec<AlreadySent>,
+    #[serde(skip)]
+    compiled_regex: Option<Regex>,
+    pub(crate) elapsed: Duration,
+}
+
+impl SearchReport {
+    fn json2_payload<'a>(
+        &'a self,
+        options: &'a SearchOptions,
+        compact: bool,
+    ) -> SearchJson2Payload<'a> {
+        let (path_alias_lookup, path_aliases_meta) = if options.path_alias {
+            let (lookup, aliases) = build_path_aliases(&self.outcome.results);
+            (Some(lookup), Some(aliases))
+        } else {
+            (None, None)
+        };
+
+        let results: Vec<SearchJson2Result> = self
+            .outcome
+            .results
+            .iter()
+            .map(|result| {
+                let alias = path_alias_lookup
+                    .as_ref()
+                    .and_then(|lookup| lookup.get(&result.path))
+                    .map(|s| s.as_str());
+                SearchJson2Result::from_result(result, !compact, alias)
+            })
+            .collect();
+
+        SearchJson2Payload {
+            meta: SearchJson2Meta {
+                schema_version: "1",
+                query: &options.query,
+                search_mode: self.search_mode.to_string(),
+                index_mode: match self.outcome.mode {
+                    IndexMode::Index => "index",
+                    IndexMode::Scan => "scan",
+                },
+                elapsed_ms: self.elapsed.as_secs_f64() * 1000.0,
+                files_with_matches: self.outcome.files_with_matches,
+                total_matches: self.outcome.total_matches,
+                cache_hit: self.outcome.cache_hit,
+                context_pack: self.context_pack,
+                truncated: self.budget_stats.truncated,
+                dropped_results: self.budget_stats.dropped_results,
+                max_total_chars: self.budget.max_total_chars,
+                max_tokens: self.budget.max_tokens,
+                tokens_used: self.budget_stats.tokens_used,
+                max_chars_per_snippet: self.budget.max_chars_per_snippet,
+                max_context_chars: self.budget.max_context_chars,
+                dedupe_context: self.budget.dedupe_context,
+                path_alias: options.path_alias,
+                suppress_boilerplate: self.budget.suppress_boilerplate,
+                changed_rev: self.changed_rev.as_deref(),
+                session: options.session.as_deref(),
+                path_aliases: path_aliases_meta,
+            },
+            results,
+            already_sent: &self.already_sent,
+        }
+    }
+
+    /// The `json2` payload as a JSON value (`compact` drops context lines)
+    pub fn to_json2(&self, options: &SearchOptions, compact: bool) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.json2_payload(options, compact))?)
+    }
+}
+
+fn compile_query_regex(options: &SearchOptions) -> Result<Option<Regex>> {
+    if !options.regex {
+        return Ok(None);
+    }
+    let regex = RegexBuilder::new(&options.query)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .context("Invalid regex pattern")?;
+    Ok(Some(regex))
+}
+
+/// Run a search and apply the output budget for `format`
+pub fn execute(options: &SearchOptions, format: OutputFormat) -> Result<SearchReport> {
+    execute_inner(options, format, None)
+}
+
+/// Like [`execute`], reusing a config already loaded for the index root
+pub fn execute_with_config(
+    options: &SearchOptions,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<SearchReport> {
+    execute_inner(options, format, Some(config))
+}
+
+fn execute_inner(
+    options: &SearchOptions,
+    format: OutputFormat,
+    loaded_config: Option<&Config>,
+) -> Result<SearchReport> {
     let start_time = Instant::now();
-    let use_color = use_colors() && format == OutputFormat::Text;
+    let query = options.query.as_str();
+    let file_type = options.file_type.as_deref();
+    let glob_pattern = options.glob.as_deref();
+    let exclude_pattern = options.exclude.as_deref();
 
     // Precompile glob patterns for efficient repeated matching
     let compiled_glob = glob_pattern.and_then(CompiledGlob::new);
     let compiled_exclude = exclude_pattern.and_then(CompiledGlob::new);
 
-    let search_root = resolve_search_root(path)?;
+    let search_root = resolve_search_root(options.path.as_deref())?;
 
     // Find index root (may be in parent directory)
-    let (index_root, index_path, using_parent) = match cgrep::utils::find_index_root(&search_root) {
+    let (index_root, index_path, using_parent) = match crate::utils::find_index_root(&search_root) {
         Some(index_root) => (
             index_root.root.clone(),
             index_root.index_path,
@@ -306,14 +461,31 @@ pub fn run(
     };
 
     // Load config relative to the index root so running from subdirectories works.
-    let config = Config::load_for_dir(&index_root);
-    let effective_max_results = max_results;
+    let owned_config;
+    let config = match loaded_config {
+        Some(config) => config,
+        None => {
+            owned_config = Config::load_for_dir(&index_root);
+            &owned_config
+        }
+    };
+    let mut session = options.session.as_deref().and_then(|session_id| {
+        let ttl_ms = config.cache.session_ttl_ms();
+        SessionTracker::open(&search_root, &index_root, session_id, ttl_ms)
+            .map_err(|err| eprintln!("Warning: failed to update session: {:#}", err))
+            .ok()
+    });
+    // Hits the session already received are dropped below; fetch enough to refill
+    let effective_max_results =
+        options.max_results + session.as_ref().map_or(0, SessionTracker::delivery_count);
     let config_exclude_patterns: Vec<CompiledGlob> = config
         .exclude_patterns
         .iter()
         .filter_map(|p| CompiledGlob::new(p.as_str()))
         .collect();
-    let changed_filter = changed
+    let changed_filter = options
+        .changed
+        .as_deref()
         .map(|rev| ChangedFiles::from_scope(&search_root, rev))
         .transpose()?;
 
@@ -321,30 +493,21 @@ pub fn run(
         eprintln!("Using index from: {}", index_root.display());
     }
 
-    let requested_mode = if no_index || regex {
+    let requested_mode = if options.no_index || options.regex {
         IndexMode::Scan
     } else {
         IndexMode::Index
     };
 
-    if requested_mode == IndexMode::Scan && fuzzy {
+    if requested_mode == IndexMode::Scan && options.fuzzy {
         eprintln!("Warning: --fuzzy is only supported with index search; ignoring.");
     }
 
-    let compiled_regex = if regex {
-        Some(
-            RegexBuilder::new(query)
-                .case_insensitive(!case_sensitive)
-                .build()
-                .context("Invalid regex pattern")?,
-        )
-    } else {
-        None
-    };
+    let compiled_regex = compile_query_regex(options)?;
 
     // Check for hybrid search mode
-    let effective_search_mode = search_mode.unwrap_or(HybridSearchMode::Keyword);
-    let effective_cache_ttl = cache_ttl.unwrap_or(DEFAULT_CACHE_TTL_MS);
+    let effective_search_mode = options.search_mode.unwrap_or(HybridSearchMode::Keyword);
+    let effective_cache_ttl = options.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL_MS);
 
     let mut outcome = match effective_search_mode {
         HybridSearchMode::Semantic | HybridSearchMode::Hybrid => {
@@ -353,9 +516,9 @@ pub fn run(
                 query,
                 &index_root,
                 &search_root,
-                &config,
+                config,
                 effective_max_results,
-                context,
+                options.context,
                 file_type,
                 glob_pattern,
                 exclude_pattern,
@@ -364,7 +527,7 @@ pub fn run(
                 &config_exclude_patterns,
                 changed_filter.as_ref(),
                 effective_search_mode,
-                use_cache,
+                options.use_cache,
                 effective_cache_ttl,
             )?
         }
@@ -374,7 +537,7 @@ pub fn run(
             &search_root,
             &index_path,
             effective_max_results,
-            context,
+            options.context,
             file_type,
             glob_pattern,
             exclude_pattern,
@@ -383,35 +546,98 @@ pub fn run(
             &config_exclude_patterns,
             changed_filter.as_ref(),
             requested_mode,
-            fuzzy,
+            options.fuzzy,
             compiled_regex.as_ref(),
-            case_sensitive,
-            use_cache,
+            options.case_sensitive,
+            options.use_cache,
             effective_cache_ttl,
         )?,
     };
 
-    let effective_context_pack = context_pack.filter(|v| *v > 0);
+    if options.scope != ContextScope::Lines {
+        apply_context_scope(&mut outcome.results, &search_root, options.scope);
+    }
+
+    let effective_context_pack = options.context_pack.filter(|v| *v > 0);
     if let Some(pack_gap) = effective_context_pack {
         apply_context_pack(&mut outcome.results, pack_gap);
     }
 
+    let already_sent = match session.as_mut() {
+        Some(tracker) => {
+            let sent = drop_already_sent(&mut outcome.results, tracker);
+            outcome.results.truncate(options.max_results);
+            sent
+        }
+        None => Vec::new(),
+    };
+
     let budget = SearchOutputBudget {
-        max_chars_per_snippet,
-        max_total_chars,
-        max_context_chars,
-        dedupe_context: dedupe_context || format == OutputFormat::Json2,
-        suppress_boilerplate: suppress_boilerplate || format == OutputFormat::Json2,
+        max_chars_per_snippet: options.max_chars_per_snippet,
+        max_total_chars: options.max_total_chars,
+        max_tokens: options.max_tokens,
+        max_context_chars: options.max_context_chars,
+        dedupe_context: options.dedupe_context || format == OutputFormat::Json2,
+        suppress_boilerplate: options.suppress_boilerplate || format == OutputFormat::Json2,
     };
     let budget_stats = apply_output_budget(&mut outcome.results, budget);
-    let (path_alias_lookup, path_aliases_meta) = if format == OutputFormat::Json2 && path_alias {
-        let (lookup, aliases) = build_path_aliases(&outcome.results);
-        (Some(lookup), Some(aliases))
-    } else {
-        (None, None)
-    };
 
-    let elapsed = start_time.elapsed();
+    // json2 IDs are what `agent expand` is handed back later
+    if format == OutputFormat::Json2 {
+        let record = index_path.exists();
+        if let Err(err) = assign_result_ids(&mut outcome.results, &search_root, &index_root, record)
+        {
+            eprintln!("Warning: failed to record result IDs: {:#}", err);
+        }
+    }
+
+    if let Some(mut tracker) = session {
+        for result in &outcome.results {
+            if let Some(line) = result.line {
+                tracker.record(
+                    result.result_id.as_deref(),
+                    &result.path,
+                    &result_ranges(result, line),
+                );
+            }
+        }
+        if let Err(err) = tracker.finish() {
+            eprintln!("Warning: failed to update session: {:#}", err);
+        }
+    }
+
+    Ok(SearchReport {
+        outcome,
+        search_mode: effective_search_mode,
+        context_pack: effective_context_pack,
+        budget,
+        budget_stats,
+        changed_rev: changed_filter.as_ref().map(|f| f.rev().to_string()),
+        already_sent,
+        compiled_regex,
+        elapsed: start_time.elapsed(),
+    })
+}
+
+/// Run the search command
+pub fn run(
+    options: &SearchOptions,
+    quiet: bool,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let use_color = use_colors() && format == OutputFormat::Text;
+    let query = options.query.as_str();
+    let report = match daemon::search(options, format) {
+        Some(mut report) => {
+            report.compiled_regex = compile_query_regex(options)?;
+            report
+        }
+        None => execute(options, format)?,
+    };
+    let outcome = &report.outcome;
+    let compiled_regex = &report.compiled_regex;
+    let elapsed = report.elapsed;
 
     // Output based on format
     match format {
@@ -433,47 +659,7 @@ pub fn run(
             }
         }
         OutputFormat::Json2 => {
-            let json2_results: Vec<SearchJson2Result> = outcome
-                .results
-                .iter()
-                .map(|result| {
-                    let alias = path_alias_lookup
-                        .as_ref()
-                        .and_then(|lookup| lookup.get(&result.path))
-                        .map(|s| s.as_str());
-                    SearchJson2Result::from_result(result, !compact, alias)
-                })
-                .collect();
-
-            let payload = SearchJson2Payload {
-                meta: SearchJson2Meta {
-                    schema_version: "1",
-                    query,
-                    search_mode: effective_search_mode.to_string(),
-                    index_mode: match outcome.mode {
-                        IndexMode::Index => "index",
-                        IndexMode::Scan => "scan",
-                    },
-                    elapsed_ms: elapsed.as_secs_f64() * 1000.0,
-                    files_with_matches: outcome.files_with_matches,
-                    total_matches: outcome.total_matches,
-                    cache_hit: outcome.cache_hit,
-                    context_pack: effective_context_pack,
-                    truncated: budget_stats.truncated,
-                    dropped_results: budget_stats.dropped_results,
-                    max_total_chars: budget.max_total_chars,
-                    max_chars_per_snippet: budget.max_chars_per_snippet,
-                    max_context_chars: budget.max_context_chars,
-                    dedupe_context: budget.dedupe_context,
-                    path_alias,
-                    suppress_boilerplate: budget.suppress_boilerplate,
-                    changed_rev: changed_filter.as_ref().map(|f| f.rev()),
-                    path_aliases: path_aliases_meta,
-                },
-                results: json2_results,
-            };
-
-            print_json(&payload, compact)?;
+            print_json(&report.json2_payload(options, compact), compact)?;
         }
         OutputFormat::Text => {
             if outcome.results.is_empty() {
@@ -494,15 +680,18 @@ pub fn run(
                     println!("\nFound {} results for: {}\n", outcome.results.len(), query);
                 }
 
+                let highlight_text = StructuredQuery::parse(query)
+                    .map(|structured| structured.text())
+                    .unwrap_or_else(|| query.to_string());
                 let highlight_snippet = |snippet: &str| {
                     if outcome.mode == IndexMode::Scan {
                         if let Some(re) = compiled_regex.as_ref() {
                             highlight_matches_regex(snippet, re, use_color)
                         } else {
-                            highlight_matches(snippet, query, use_color)
+                            highlight_matches(snippet, &highlight_text, use_color)
                         }
                     } else {
-                        highlight_matches(snippet, query, use_color)
+                        highlight_matches(snippet, &highlight_text, use_color)
                     }
                 };
 
@@ -553,13 +742,12 @@ pub fn run(
                     if has_context {
                         if let Some(match_line) = result.line {
                             let max_line = match_line + result.context_after.len();
-                            let min_line = match_line.saturating_sub(result.context_before.len());
+                            let min_line = result.context_start_line().unwrap_or(match_line);
                             let width = std::cmp::max(max_line, min_line).to_string().len();
 
                             // Print context before
                             for (i, line) in result.context_before.iter().enumerate() {
-                                let ctx_line_num =
-                                    match_line.saturating_sub(result.context_before.len() - i);
+                                let ctx_line_num = min_line + i;
                                 let prefix = format_line_prefix(" ", ctx_line_num, width);
                                 println!("{}{}", prefix, colorize_context(line, use_color));
                             }
@@ -594,6 +782,17 @@ pub fn run(
                 }
             }
 
+            if !report.already_sent.is_empty() {
+                println!(
+                    "Already sent in this session ({}):",
+                    report.already_sent.len()
+                );
+                for sent in &report.already_sent {
+                    let line_info = format!(":{}", colorize_line_num(sent.line, use_color));
+                    println!("  {}{}", colorize_path(&sent.path, use_color), line_info);
+                }
+            }
+
             // Print stats unless quiet
             if !quiet {
                 eprintln!(
@@ -609,6 +808,45 @@ pub fn run(
     Ok(())
 }
 
+/// Replace results whose lines `session_id` already received with
+/// references, and record the lines of the rest as delivered
+/// Lines a hit delivers with its current context
+fn result_ranges(result: &SearchResult, line: usize) -> Vec<(usize, usize)> {
+    delivered_ranges(
+        line,
+        result.context_start_line().unwrap_or(line),
+        result.context_before.len(),
+        result.context_after.len(),
+    )
+}
+
+/// Drop hits the session already received, before budgets and the result
+/// limit are applied so their room goes to unseen hits
+fn drop_already_sent(
+    results: &mut Vec<SearchResult>,
+    tracker: &mut SessionTracker<'_>,
+) -> Vec<AlreadySent> {
+    let mut already_sent = Vec::new();
+    results.retain(|result| {
+        let Some(line) = result.line else {
+            return true;
+        };
+        match tracker.covered(
+            result.result_id.as_deref(),
+            &result.path,
+            line,
+            &result_ranges(result, line),
+        ) {
+            Some(sent) => {
+                already_sent.push(sent);
+                false
+            }
+            None => true,
+        }
+    });
+    already_sent
+}
+
 fn stable_result_id(result: &SearchResult) -> String {
     let payload = format!(
         "{}:{}:{}",
@@ -620,6 +858,113 @@ fn stable_result_id(result: &SearchResult) -> String {
     hash.to_hex()[..16].to_string()
 }
 
+/// Give json2 results edit-stable IDs and, with an index (`record`), remember
+/// where they point so `agent expand` can look them up
+fn assign_result_ids(
+    results: &mut [SearchResult],
+    search_root: &Path,
+    index_root: &Path,
+    record: bool,
+) -> Result<()> {
+    // display path -> (path relative to the index root, content)
+    let mut files: HashMap<String, Option<(String, String)>> = HashMap::new();
+    for result in results.iter() {
+        if result.line.is_none() || files.contains_key(&result.path) {
+            continue;
+        }
+        let full_path = search_root.join(&result.path);
+        let file = std::fs::read_to_string(&full_path).ok().map(|content| {
+            let rel_path = full_path
+                .strip_prefix(index_root)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|_| result.path.clone());
+            (rel_path, content)
+        });
+        files.insert(result.path.clone(), file);
+    }
+    let anchors: HashMap<&str, (&str, String, FileAnchors<'_>)> = files
+        .iter()
+        .filter_map(|(display_path, file)| {
+            let (rel_path, content) = file.as_ref()?;
+            let anchors = FileAnchors::parse(Path::new(rel_path), content);
+            Some((
+                display_path.as_str(),
+                (rel_path.as_str(), content_hash(content.as_bytes()), anchors),
+            ))
+        })
+        .collect();
+
+    let mut locations = Vec::new();
+    for result in results.iter_mut() {
+        let Some(line) = result.line else {
+            continue;
+        };
+        let Some((rel_path, file_hash, file)) = anchors.get(result.path.as_str()) else {
+            continue;
+        };
+        let (Some(anchor), Some(text)) = (file.anchor(line), file.line(line)) else {
+            continue;
+        };
+        let id = anchored_id(rel_path, &anchor, text);
+        if record {
+            locations.push(ResultLocation {
+                id: id.clone(),
+                path: rel_path.to_string(),
+                line,
+                anchor,
+                file_hash: file_hash.clone(),
+                line_hash: content_hash(text.trim().as_bytes()),
+            });
+        }
+        result.result_id = Some(id);
+    }
+
+    if locations.is_empty() {
+        return Ok(());
+    }
+    ResultIdStore::open(index_root)?.record(&locations)
+}
+
+/// Replace each result's context with its enclosing symbol (`--scope`),
+/// keeping the line window for hits outside any symbol
+fn apply_context_scope(results: &mut [SearchResult], search_root: &Path, scope: ContextScope) {
+    let mut files: HashMap<String, Option<(String, Option<SymbolSpans>)>> = HashMap::new();
+    for result in results.iter_mut() {
+        let Some(line) = result.line else {
+            continue;
+        };
+        let Some((content, spans)) = files
+            .entry(result.path.clone())
+            .or_insert_with(|| {
+                std::fs::read_to_string(search_root.join(&result.path))
+                    .ok()
+                    .map(|content| (content, None))
+            })
+            .as_mut()
+        else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        // Semantic hits already carry their symbol's range from the index
+        let span = match (result.chunk_start, result.chunk_end) {
+            (Some(start), Some(end)) if (start as usize..=end as usize).contains(&line) => {
+                SymbolSpan::from_lines(&lines, start as usize, end as usize)
+            }
+            _ => spans
+                .get_or_insert_with(|| SymbolSpans::parse(Path::new(&result.path), content))
+                .enclosing(line),
+        };
+        let Some(scoped) = span.and_then(|span| ScopedContext::new(&lines, line, span, scope))
+        else {
+            continue;
+        };
+        result.context_start =
+            (scoped.start_line + scoped.before.len() != line).then_some(scoped.start_line);
+        result.context_before = scoped.before;
+        result.context_after = scoped.after;
+    }
+}
+
 fn apply_context_pack(results: &mut [SearchResult], pack_gap: usize) {
     let mut last_end_by_path: HashMap<String, usize> = HashMap::new();
 
@@ -628,7 +973,10 @@ fn apply_context_pack(results: &mut [SearchResult], pack_gap: usize) {
             continue;
         };
 
-        if result.context_before.is_empty() && result.context_after.is_empty() {
+        // Packing assumes context runs contiguously through the match
+        if (result.context_before.is_empty() && result.context_after.is_empty())
+            || result.context_start.is_some()
+        {
             continue;
         }
 
@@ -683,7 +1031,7 @@ fn apply_output_budget(
 
     if let Some(max_context_chars) = budget.max_context_chars {
         for result in results.iter_mut() {
-            let trimmed = trim_result_context_chars(result, max_context_chars);
+            let trimmed = trim_result_context(result, max_context_chars, BudgetUnit::Chars);
             if trimmed {
                 stats.truncated = true;
             }
@@ -691,11 +1039,22 @@ fn apply_output_budget(
     }
 
     if let Some(max_total_chars) = budget.max_total_chars {
-        let total_stats = enforce_total_chars_budget(results, max_total_chars);
+        let total_stats = enforce_total_budget(results, max_total_chars, BudgetUnit::Chars);
         stats.truncated |= total_stats.truncated;
         stats.dropped_results = total_stats.dropped_results;
     }
 
+    if let Some(max_tokens) = budget.max_tokens {
+        let token_stats = enforce_total_budget(results, max_tokens, BudgetUnit::Tokens);
+        stats.truncated |= token_stats.truncated;
+        stats.dropped_results += token_stats.dropped_results;
+    }
+
+    stats.tokens_used = results
+        .iter()
+        .map(|result| result_size(result, BudgetUnit::Tokens))
+        .sum();
+
     stats
 }
 
@@ -789,43 +1148,45 @@ fn is_boilerplate_line(line: &str) -> bool {
         || matches!(trimmed, "{" | "}" | "(" | ")" | "[" | "]")
 }
 
-fn trim_result_context_chars(result: &mut SearchResult, max_context_chars: usize) -> bool {
-    let mut remaining = max_context_chars;
+fn trim_result_context(result: &mut SearchResult, max_context: usize, unit: BudgetUnit) -> bool {
+    let mut remaining = max_context;
     let mut trimmed = false;
 
     for line in result.context_before.iter_mut() {
         let original = line.clone();
-        *line = truncate_with_ellipsis(line, remaining);
+        *line = unit.truncate(line, remaining);
         if *line != original {
             trimmed = true;
         }
-        remaining = remaining.saturating_sub(char_count(line));
+        remaining = remaining.saturating_sub(unit.measure(line));
     }
     result.context_before.retain(|line| !line.is_empty());
 
     for line in result.context_after.iter_mut() {
         let original = line.clone();
-        *line = truncate_with_ellipsis(line, remaining);
+        *line = unit.truncate(line, remaining);
         if *line != original {
             trimmed = true;
         }
-        remaining = remaining.saturating_sub(char_count(line));
+        remaining = remaining.saturating_sub(unit.measure(line));
     }
     result.context_after.retain(|line| !line.is_empty());
 
     trimmed
 }
 
-fn enforce_total_chars_budget(
+fn enforce_total_budget(
     results: &mut Vec<SearchResult>,
-    max_total_chars: usize,
+    max_total: usize,
+    unit: BudgetUnit,
 ) -> BudgetApplyStats {
-    if max_total_chars == 0 {
+    if max_total == 0 {
         let dropped_results = results.len();
         results.clear();
         return BudgetApplyStats {
             truncated: dropped_results > 0,
             dropped_results,
+            ..BudgetApplyStats::default()
         };
     }
 
@@ -834,14 +1195,14 @@ fn enforce_total_chars_budget(
     let mut truncated = false;
 
     for result in results.iter_mut() {
-        let mandatory = mandatory_chars(result);
-        if used + mandatory > max_total_chars {
+        let mandatory = mandatory_size(result, unit);
+        if used + mandatory > max_total {
             truncated = true;
             break;
         }
         used += mandatory;
 
-        let optional_budget = max_total_chars.saturating_sub(used);
+        let optional_budget = max_total.saturating_sub(used);
         if optional_budget == 0 {
             if !result.snippet.is_empty()
                 || !result.context_before.is_empty()
@@ -856,26 +1217,26 @@ fn enforce_total_chars_budget(
             continue;
         }
 
-        let snippet_chars = char_count(&result.snippet);
+        let snippet_size = unit.measure(&result.snippet);
         let mut remaining_for_context = optional_budget;
-        if snippet_chars > optional_budget {
+        if snippet_size > optional_budget {
             truncated = true;
-            result.snippet = truncate_with_ellipsis(&result.snippet, optional_budget);
+            result.snippet = unit.truncate(&result.snippet, optional_budget);
             result.context_before.clear();
             result.context_after.clear();
             keep += 1;
-            used += optional_chars(result);
+            used += optional_size(result, unit);
             continue;
         }
-        remaining_for_context = remaining_for_context.saturating_sub(snippet_chars);
+        remaining_for_context = remaining_for_context.saturating_sub(snippet_size);
 
-        if context_chars(result) > remaining_for_context
-            && trim_result_context_chars(result, remaining_for_context)
+        if context_size(result, unit) > remaining_for_context
+            && trim_result_context(result, remaining_for_context, unit)
         {
             truncated = true;
         }
 
-        used += optional_chars(result);
+        used += optional_size(result, unit);
         keep += 1;
     }
 
@@ -888,27 +1249,33 @@ fn enforce_total_chars_budget(
     BudgetApplyStats {
         truncated,
         dropped_results,
+        ..BudgetApplyStats::default()
     }
 }
 
-fn mandatory_chars(result: &SearchResult) -> usize {
-    let line_chars = result
+/// Size of everything a result will print, in `unit`
+fn result_size(result: &SearchResult, unit: BudgetUnit) -> usize {
+    mandatory_size(result, unit) + optional_size(result, unit)
+}
+
+fn mandatory_size(result: &SearchResult, unit: BudgetUnit) -> usize {
+    let line = result
         .line
-        .map(|line| line.to_string().len())
+        .map(|line| unit.measure(&line.to_string()))
         .unwrap_or_default();
-    result.path.len() + line_chars
+    unit.measure(&result.path) + line
 }
 
-fn optional_chars(result: &SearchResult) -> usize {
-    char_count(&result.snippet) + context_chars(result)
+fn optional_size(result: &SearchResult, unit: BudgetUnit) -> usize {
+    unit.measure(&result.snippet) + context_size(result, unit)
 }
 
-fn context_chars(result: &SearchResult) -> usize {
+fn context_size(result: &SearchResult, unit: BudgetUnit) -> usize {
     result
         .context_before
         .iter()
         .chain(result.context_after.iter())
-        .map(|line| char_count(line))
+        .map(|line| unit.measure(line))
         .sum()
 }
 
@@ -934,6 +1301,40 @@ fn char_count(input: &str) -> usize {
     input.chars().count()
 }
 
+/// What an output budget counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BudgetUnit {
+    Chars,
+    /// Estimated cl100k tokens (see [`crate::tokens`])
+    Tokens,
+}
+
+impl BudgetUnit {
+    fn measure(self, text: &str) -> usize {
+        match self {
+            BudgetUnit::Chars => char_count(text),
+            BudgetUnit::Tokens => count_tokens(text),
+        }
+    }
+
+    /// Shorten `text` to `max` units, marking the cut with `...`
+    fn truncate(self, text: &str, max: usize) -> String {
+        match self {
+            BudgetUnit::Chars => truncate_with_ellipsis(text, max),
+            BudgetUnit::Tokens => {
+                let ellipsis = count_tokens("...");
+                if count_tokens(text) <= max {
+                    text.to_string()
+                } else if max <= ellipsis {
+                    truncate_to_tokens(text, max).to_string()
+                } else {
+                    format!("{}...", truncate_to_tokens(text, max - ellipsis))
+                }
+            }
+        }
+    }
+}
+
 fn build_path_aliases(
     results: &[SearchResult],
 ) -> (HashMap<String, String>, BTreeMap<String, String>) {
@@ -1031,6 +1432,82 @@ struct IndexCandidate {
     symbol_end: Option<u32>,
 }
 
+/// Whether a display path passes the type, glob, exclude and `--changed` filters
+fn candidate_in_scope(
+    display_path: &str,
+    file_type: Option<&str>,
+    compiled_glob: Option<&CompiledGlob>,
+    compiled_exclude: Option<&CompiledGlob>,
+    config_exclude_patterns: &[CompiledGlob],
+    changed_filter: Option<&ChangedFiles>,
+) -> bool {
+    changed_filter.is_none_or(|filter| filter.matches_rel_path(display_path))
+        && matches_file_type(display_path, file_type)
+        && matches_glob_compiled(display_path, compiled_glob)
+        && !should_exclude_compiled(display_path, compiled_exclude)
+        && !config_exclude_patterns
+            .iter()
+            .any(|p| should_exclude_compiled(display_path, Some(p)))
+}
+
+/// Query embedding providers built by this process, keyed by their configuration.
+///
+/// Loading a model dominates semantic query latency; `cgrep daemon` keeps it warm.
+static QUERY_PROVIDERS: Lazy<Mutex<HashMap<String, Box<dyn EmbeddingProvider>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn query_provider(config: &Config) -> Result<Box<dyn EmbeddingProvider>> {
+    match config.embeddings.provider() {
+        EmbeddingProviderType::Builtin => EmbeddingProviderConfig::from_env()
+            .and_then(FastEmbedder::new)
+            .map(|provider| Box::new(provider) as Box<dyn EmbeddingProvider>),
+        EmbeddingProviderType::Dummy => Ok(Box::new(DummyProvider::new(DEFAULT_EMBEDDING_DIM))),
+        EmbeddingProviderType::Command => Ok(Box::new(CommandProvider::new(
+            config.embeddings.command().to_string(),
+            config.embeddings.model().to_string(),
+        ))),
+        EmbeddingProviderType::Http => HttpProvider::from_embedding_config(&config.embeddings)
+            .map(|provider| Box::new(provider) as Box<dyn EmbeddingProvider>),
+    }
+}
+
+/// Embed the query with the configured provider (`None` with a warning on failure)
+fn embed_query(query: &str, config: &Config) -> Option<Vec<f32>> {
+    let key = format!("{:?}", config.embeddings);
+    let mut providers = QUERY_PROVIDERS.lock().unwrap_or_else(|e| e.into_inner());
+    let provider = match providers.entry(key) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => match query_provider(config) {
+            Ok(provider) => entry.insert(provider),
+            Err(err) => {
+                eprintln!("Warning: embedding provider unavailable: {}", err);
+                return None;
+            }
+        },
+    };
+
+    match provider.embed_one(query) {
+        Ok(query_embedding) => Some(query_embedding),
+        Err(err) => {
+            eprintln!("Warning: embedding query failed (using BM25 only): {}", err);
+            None
+        }
+    }
+}
+
+/// Trimmed text of a 1-indexed line, used as the snippet of vector-only results
+fn line_snippet(file_path: &Path, line: usize) -> String {
+    let Ok(file) = fs::File::open(file_path) else {
+        return String::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .nth(line.saturating_sub(1))
+        .and_then(|line| line.ok())
+        .map(|text| truncate_with_ellipsis(text.trim(), 150))
+        .unwrap_or_default()
+}
+
 #[allow(clippy::too_many_arguments)]
 fn collect_index_candidates(
     query: &str,
@@ -1053,8 +1530,7 @@ fn collect_index_candidates(
         .into());
     }
 
-    let index = Index::open_in_dir(&index_path).context("Failed to open index")?;
-    let reader = index.reader()?;
+    let (index, reader) = open_index(&index_path)?;
     let searcher = reader.searcher();
 
     let schema = index.schema();
@@ -1078,7 +1554,15 @@ fn collect_index_candidates(
         .get_field("line_number")
         .context("Missing line_number field")?;
 
-    let text_query: Box<dyn tantivy::query::Query> = if fuzzy {
+    let structured = StructuredQuery::parse(query);
+    // `kind:` only matches symbol documents
+    let doc_type = match &structured {
+        Some(structured) if structured.targets_symbols() => "symbol",
+        _ => doc_type,
+    };
+    let text_query: Box<dyn tantivy::query::Query> = if let Some(structured) = &structured {
+        structured.to_tantivy(&schema)?
+    } else if fuzzy {
         let terms: Vec<&str> = query.split_whitespace().collect();
         if terms.is_empty() {
             anyhow::bail!("Fuzzy search requires at least one search term");
@@ -1086,20 +1570,24 @@ fn collect_index_candidates(
         let mut fuzzy_queries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = Vec::new();
 
         for term in terms {
-            let distance = if term.len() <= 4 { 1 } else { 2 };
-
-            let content_term = Term::from_field_text(content_field, term);
-            let content_fuzzy = FuzzyTermQuery::new(content_term, distance, true);
-            fuzzy_queries.push((Occur::Should, Box::new(content_fuzzy)));
-
-            let symbols_term = Term::from_field_text(symbols_field, term);
-            let symbols_fuzzy = FuzzyTermQuery::new(symbols_term, distance, true);
-            fuzzy_queries.push((Occur::Should, Box::new(symbols_fuzzy)));
+            // Same analyzer as indexing: lowercased identifier plus sub-words
+            for field in [content_field, symbols_field] {
+                for token in tokenize_for_field(&index, field, term)? {
+                    let distance = if token.len() <= 4 { 1 } else { 2 };
+                    let fuzzy_term = Term::from_field_text(field, &token);
+                    let fuzzy_query = FuzzyTermQuery::new(fuzzy_term, distance, true);
+                    fuzzy_queries.push((Occur::Should, Box::new(fuzzy_query)));
+                }
+            }
         }
 
         Box::new(BooleanQuery::new(fuzzy_queries))
     } else {
-        let query_parser = QueryParser::for_index(&index, vec![content_field, symbols_field]);
+        let query_parser = QueryParser::new(
+            schema.clone(),
+            vec![content_field, symbols_field],
+            tokenizer::query_tokenizers(),
+        );
         Box::new(query_parser.parse_query(query)?)
     };
 
@@ -1130,24 +1618,24 @@ fn collect_index_candidates(
         let Some(display_path) = scoped_display_path(&full_path, search_root) else {
             continue;
         };
-        if let Some(filter) = changed_filter {
-            if !filter.matches_rel_path(&display_path) {
-                continue;
-            }
-        }
-
-        if !matches_file_type(&display_path, file_type) {
-            continue;
-        }
-        if !matches_glob_compiled(&display_path, compiled_glob) {
-            continue;
-        }
-        if should_exclude_compiled(&display_path, compiled_exclude) {
+        if !candidate_in_scope(
+            &display_path,
+            file_type,
+            compiled_glob,
+            compiled_exclude,
+            config_exclude_patterns,
+            changed_filter,
+        ) {
             continue;
         }
-        if config_exclude_patterns
-            .iter()
-            .any(|p| should_exclude_compiled(&display_path, Some(p)))
+
+        let doc_type_value = doc
+            .get_first(doc_type_field)
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if structured
+            .as_ref()
+            .is_some_and(|s| !s.matches_fields(&index_rel_path(&full_path, index_root), None))
         {
             continue;
         }
@@ -1162,14 +1650,24 @@ fn collect_index_candidates(
             .and_then(|v| v.as_u64())
             .unwrap_or(1) as usize;
 
-        let (snippet, line_num) = find_snippet_with_line(content_value, query, 150);
-        let doc_type_value = doc
-            .get_first(doc_type_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
+        // Symbol documents start with a `name kind` header above the source
+        // lines from `line_offset`
+        let is_symbol = doc_type_value == "symbol";
+        let body = if is_symbol {
+            content_value
+                .split_once('\n')
+                .map(|(_, body)| body)
+                .unwrap_or("")
+        } else {
+            content_value
+        };
+        let snippet_query = structured.as_ref().map(StructuredQuery::text);
+        let (mut snippet, line_num) =
+            find_snippet_with_line(body, snippet_query.as_deref().unwrap_or(query), 150);
         let mut line_num = line_num.map(|l| l + line_offset.saturating_sub(1));
-        if line_num.is_none() && doc_type_value == "symbol" {
+        if line_num.is_none() && is_symbol {
             line_num = Some(line_offset);
+            snippet = symbol_start_snippet(body, &full_path, line_offset);
         }
 
         let symbol_id = if doc_type_value == "symbol" {
@@ -1243,12 +1741,14 @@ fn keyword_search(
         IndexMode::Scan
     };
 
+    // `OR` and field values are case-sensitive in structured queries
+    let structured = regex.is_none() && StructuredQuery::parse(query).is_some();
     let normalized_query = if regex.is_some() {
         query.to_string()
     } else {
         normalize_query(
             query,
-            effective_mode == IndexMode::Index || !case_sensitive,
+            (effective_mode == IndexMode::Index || !case_sensitive) && !structured,
             effective_mode == IndexMode::Index,
         )
     };
@@ -1336,6 +1836,41 @@ fn keyword_search(
     Ok(outcome)
 }
 
+/// Path relative to the index root, as `path:` filters see it
+fn index_rel_path(full_path: &Path, index_root: &Path) -> String {
+    full_path
+        .strip_prefix(index_root)
+        .unwrap_or(full_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Whether a vector result passes the field filters of a structured query,
+/// looking up its symbol's kind and name in the embedding store
+fn vector_result_matches(
+    structured: &StructuredQuery,
+    hr: &HybridResult,
+    storage: &EmbeddingStorage,
+    index_root: &Path,
+) -> bool {
+    let symbol = storage
+        .get_symbols_for_path(&hr.path)
+        .ok()
+        .and_then(|symbols| {
+            symbols.into_iter().find(|symbol| {
+                Some(symbol.start_line) == hr.chunk_start && Some(symbol.end_line) == hr.chunk_end
+            })
+        });
+    let full_path = resolve_full_path(&hr.path, index_root);
+    structured.matches_fields(
+        &index_rel_path(&full_path, index_root),
+        Some(match &symbol {
+            Some(symbol) => (symbol.symbol_kind.as_str(), symbol.symbol_name.as_str()),
+            None => ("", ""),
+        }),
+    )
+}
+
 fn parse_index_mode(mode: &str) -> IndexMode {
     if mode.eq_ignore_ascii_case("scan") {
         IndexMode::Scan
@@ -1395,6 +1930,7 @@ fn index_search(
             result_id: None,
             chunk_start: None,
             chunk_end: None,
+            context_start: None,
         });
     }
 
@@ -1432,6 +1968,16 @@ fn scan_search(
     } else {
         String::new()
     };
+    let structured = match regex {
+        Some(_) => None,
+        None => StructuredQuery::parse(query),
+    };
+    if structured
+        .as_ref()
+        .is_some_and(StructuredQuery::has_kind_filter)
+    {
+        eprintln!("Warning: kind: filters need an index; ignoring in scan mode.");
+    }
     let scanner = FileScanner::new(root);
     let files = scanner.scan()?;
 
@@ -1476,6 +2022,8 @@ fn scan_search(
 
                 let matched = if let Some(re) = regex {
                     re.is_match(line)
+                } else if let Some(structured) = &structured {
+                    structured.matches_line(&rel_path, line, case_sensitive)
                 } else if case_sensitive {
                     line.contains(query)
                 } else {
@@ -1509,6 +2057,7 @@ fn scan_search(
                     result_id: None,
                     chunk_start: None,
                     chunk_end: None,
+                    context_start: None,
                 });
             }
         } else {
@@ -1520,6 +2069,8 @@ fn scan_search(
 
                 let matched = if let Some(re) = regex {
                     re.is_match(line)
+                } else if let Some(structured) = &structured {
+                    structured.matches_line(&rel_path, line, case_sensitive)
                 } else if case_sensitive {
                     line.contains(query)
                 } else {
@@ -1556,6 +2107,7 @@ fn scan_search(
                     result_id: None,
                     chunk_start: None,
                     chunk_end: None,
+                    context_start: None,
                 });
             }
         }
@@ -1596,9 +2148,11 @@ fn hybrid_search(
         .map(|f| format!("{}:{}", f.rev(), f.signature()))
         .filter(|s| !s.is_empty());
 
+    let structured = StructuredQuery::parse(query);
+
     // Build cache key
     let cache_key = CacheKey {
-        query: normalize_query(query, true, true),
+        query: normalize_query(query, structured.is_none(), true),
         mode: mode.to_string(),
         max_results,
         context,
@@ -1636,6 +2190,7 @@ fn hybrid_search(
                             result_id: hr.result_id.clone(),
                             chunk_start: hr.chunk_start,
                             chunk_end: hr.chunk_end,
+                            context_start: None,
                         })
                     })
                     .collect();
@@ -1683,148 +2238,131 @@ fn hybrid_search(
         None
     };
 
-    // Get BM25 results first
-    if !index_path.exists() {
-        return Err(anyhow::anyhow!(
-            "Index required for hybrid search. Run: cgrep index"
-        ));
-    }
+    let hybrid_config =
+        HybridConfig::new(config.search.weight_text(), config.search.weight_vector())
+            .with_candidate_k(config.search.candidate_k())
+            .with_max_results(max_results)
+            .with_fusion(config.search.fusion())
+            .with_nprobe(config.search.nprobe());
+    let hybrid_searcher = HybridSearcher::new(hybrid_config);
 
-    let bm25_candidates = collect_index_candidates(
-        query,
-        index_root,
-        search_root,
-        max_results * 3, // Get more for reranking
-        "symbol",
-        file_type,
-        compiled_glob,
-        compiled_exclude,
-        config_exclude_patterns,
-        changed_filter,
-        false,
-    )?;
+    let query_embedding = match (mode, embedding_storage.as_ref()) {
+        (HybridSearchMode::Keyword, _) => None,
+        (_, None) => {
+            eprintln!("Warning: No embedding storage found. Using BM25 only.");
+            None
+        }
+        (_, Some(_)) => match &structured {
+            // Embed the text, not the filter syntax
+            Some(structured) => embed_query(&structured.text(), config),
+            None => embed_query(query, config),
+        },
+    };
 
-    // Convert to BM25Result format
-    let bm25_results: Vec<BM25Result> = bm25_candidates
-        .into_iter()
-        .map(|candidate| BM25Result {
-            path: candidate.stored_path,
-            score: candidate.score,
-            snippet: candidate.snippet,
-            line: candidate.line,
-            chunk_start: candidate
-                .symbol_start
-                .or_else(|| candidate.line.map(|l| l as u32)),
-            chunk_end: candidate
-                .symbol_end
-                .or_else(|| candidate.line.map(|l| l as u32)),
-            symbol_id: candidate.symbol_id,
-        })
-        .collect();
+    // Semantic candidates come from the embedding store alone, so BM25 is only
+    // needed for hybrid mode or as the fallback when no query embedding exists
+    let bm25_results: Vec<BM25Result> =
+        if mode == HybridSearchMode::Semantic && query_embedding.is_some() {
+            Vec::new()
+        } else {
+            if !index_path.exists() {
+                return Err(anyhow::anyhow!(
+                    "Index required for hybrid search. Run: cgrep index"
+                ));
+            }
 
-    // Create hybrid searcher
-    let hybrid_config = HybridConfig::default().with_max_results(max_results);
-    let hybrid_searcher = HybridSearcher::new(hybrid_config);
+            collect_index_candidates(
+                query,
+                index_root,
+                search_root,
+                max_results * 3, // Get more for reranking
+                "symbol",
+                file_type,
+                compiled_glob,
+                compiled_exclude,
+                config_exclude_patterns,
+                changed_filter,
+                false,
+            )?
+            .into_iter()
+            .map(|candidate| BM25Result {
+                path: candidate.stored_path,
+                score: candidate.score,
+                snippet: candidate.snippet,
+                line: candidate.line,
+                chunk_start: candidate
+                    .symbol_start
+                    .or_else(|| candidate.line.map(|l| l as u32)),
+                chunk_end: candidate
+                    .symbol_end
+                    .or_else(|| candidate.line.map(|l| l as u32)),
+                symbol_id: candidate.symbol_id,
+            })
+            .collect()
+        };
 
     // Perform hybrid search based on mode
-    let hybrid_results: Vec<HybridResult> = match mode {
-        HybridSearchMode::Semantic | HybridSearchMode::Hybrid => {
-            if let Some(ref storage) = embedding_storage {
-                let provider_type = config.embeddings.provider();
-                let provider_result: Result<Box<dyn EmbeddingProvider>> = match provider_type {
-                    EmbeddingProviderType::Builtin => EmbeddingProviderConfig::from_env()
-                        .and_then(FastEmbedder::new)
-                        .map(|provider| Box::new(provider) as Box<dyn EmbeddingProvider>),
-                    EmbeddingProviderType::Dummy => {
-                        Ok(Box::new(DummyProvider::new(DEFAULT_EMBEDDING_DIM)))
-                    }
-                    EmbeddingProviderType::Command => Ok(Box::new(CommandProvider::new(
-                        config.embeddings.command().to_string(),
-                        config.embeddings.model().to_string(),
-                    ))),
-                };
-
-                let query_embedding = match provider_result {
-                    Ok(mut provider) => match provider.embed_one(query) {
-                        Ok(query_embedding) => Some(query_embedding),
-                        Err(err) => {
-                            eprintln!("Warning: embedding query failed (using BM25 only): {}", err);
-                            None
-                        }
-                    },
-                    Err(err) => {
-                        eprintln!("Warning: embedding provider unavailable: {}", err);
-                        None
-                    }
-                };
+    let hybrid_results: Vec<HybridResult> = match (query_embedding, embedding_storage.as_ref()) {
+        (Some(query_embedding), Some(storage)) => {
+            // Vector candidates come straight from the embedding store, so they
+            // are filtered here the same way BM25 candidates are at collection.
+            let vector_results: Vec<HybridResult> = hybrid_searcher
+                .semantic_search(&query_embedding, storage)
+                .unwrap_or_else(|err| {
+                    eprintln!("Warning: semantic retrieval failed: {}", err);
+                    Vec::new()
+                })
+                .into_iter()
+                .filter(|hr| {
+                    let full_path = resolve_full_path(&hr.path, index_root);
+                    scoped_display_path(&full_path, search_root).is_some_and(|display_path| {
+                        candidate_in_scope(
+                            &display_path,
+                            file_type,
+                            compiled_glob,
+                            compiled_exclude,
+                            config_exclude_patterns,
+                            changed_filter,
+                        )
+                    })
+                })
+                .filter(|hr| match &structured {
+                    Some(structured) => vector_result_matches(structured, hr, storage, index_root),
+                    None => true,
+                })
+                .collect();
 
-                if let Some(query_embedding) = query_embedding {
-                    match mode {
-                        HybridSearchMode::Semantic => hybrid_searcher
-                            .semantic_search(bm25_results, &query_embedding, storage)
-                            .unwrap_or_default(),
-                        HybridSearchMode::Hybrid => hybrid_searcher
-                            .rerank_with_embeddings(bm25_results, &query_embedding, storage)
-                            .unwrap_or_default(),
-                        HybridSearchMode::Keyword => Vec::new(),
-                    }
-                } else {
-                    bm25_results
-                        .iter()
-                        .map(|r| HybridResult {
-                            path: r.path.clone(),
-                            score: r.score,
-                            text_score: r.score,
-                            vector_score: 0.0,
-                            text_norm: r.score,
-                            vector_norm: 0.0,
-                            snippet: r.snippet.clone(),
-                            line: r.line,
-                            chunk_start: r.chunk_start,
-                            chunk_end: r.chunk_end,
-                            result_id: r.symbol_id.clone(),
-                        })
-                        .collect()
+            let mut fused = match mode {
+                HybridSearchMode::Semantic => {
+                    vector_results.into_iter().take(max_results).collect()
+                }
+                _ => hybrid_searcher
+                    .fuse(bm25_results, vector_results, &query_embedding, storage)
+                    .unwrap_or_default(),
+            };
+            for hr in fused.iter_mut().filter(|hr| hr.snippet.is_empty()) {
+                if let Some(line) = hr.line {
+                    hr.snippet = line_snippet(&resolve_full_path(&hr.path, index_root), line);
                 }
-            } else {
-                eprintln!("Warning: No embedding storage found. Using BM25 only.");
-                bm25_results
-                    .iter()
-                    .map(|r| HybridResult {
-                        path: r.path.clone(),
-                        score: r.score,
-                        text_score: r.score,
-                        vector_score: 0.0,
-                        text_norm: r.score,
-                        vector_norm: 0.0,
-                        snippet: r.snippet.clone(),
-                        line: r.line,
-                        chunk_start: r.chunk_start,
-                        chunk_end: r.chunk_end,
-                        result_id: r.symbol_id.clone(),
-                    })
-                    .collect()
             }
+            fused
         }
-        HybridSearchMode::Keyword => {
-            // Should not reach here
-            bm25_results
-                .iter()
-                .map(|r| HybridResult {
-                    path: r.path.clone(),
-                    score: r.score,
-                    text_score: r.score,
-                    vector_score: 0.0,
-                    text_norm: r.score,
-                    vector_norm: 0.0,
-                    snippet: r.snippet.clone(),
-                    line: r.line,
-                    chunk_start: r.chunk_start,
-                    chunk_end: r.chunk_end,
-                    result_id: r.symbol_id.clone(),
-                })
-                .collect()
-        }
+        _ => bm25_results
+            .iter()
+            .map(|r| HybridResult {
+                path: r.path.clone(),
+                score: r.score,
+                text_score: r.score,
+                vector_score: 0.0,
+                text_norm: r.score,
+                vector_norm: 0.0,
+                snippet: r.snippet.clone(),
+                line: r.line,
+                chunk_start: r.chunk_start,
+                chunk_end: r.chunk_end,
+                result_id: r.symbol_id.clone(),
+            })
+            .collect(),
     };
 
     // Convert to SearchResult with context
@@ -1838,19 +2376,14 @@ fn hybrid_search(
         };
 
         // Apply filters
-        if !matches_file_type(&display_path, file_type) {
-            continue;
-        }
-        if !matches_glob_compiled(&display_path, compiled_glob) {
-            continue;
-        }
-        if should_exclude_compiled(&display_path, compiled_exclude) {
-            continue;
-        }
-        if config_exclude_patterns
-            .iter()
-            .any(|p| should_exclude_compiled(&display_path, Some(p)))
-        {
+        if !candidate_in_scope(
+            &display_path,
+            file_type,
+            compiled_glob,
+            compiled_exclude,
+            config_exclude_patterns,
+            None,
+        ) {
             continue;
         }
 
@@ -1872,6 +2405,7 @@ fn hybrid_search(
             result_id: hr.result_id.clone(),
             chunk_start: hr.chunk_start,
             chunk_end: hr.chunk_end,
+            context_start: None,
         });
     }
 
@@ -1958,6 +2492,27 @@ fn highlight_matches(text: &str, query: &str, use_color: bool) -> String {
 }
 
 /// Find a relevant snippet containing the query terms, also returning line number
+/// Declaration line of a symbol whose text did not match: the first line of
+/// its indexed preview, else the source line at `line`
+fn symbol_start_snippet(body: &str, full_path: &Path, line: usize) -> String {
+    let first = match body.lines().next() {
+        Some(first) => first.trim().to_string(),
+        None => fs::read_to_string(full_path)
+            .ok()
+            .and_then(|text| {
+                text.lines()
+                    .nth(line.saturating_sub(1))
+                    .map(|l| l.trim().to_string())
+            })
+            .unwrap_or_default(),
+    };
+    if first.len() <= 150 {
+        first
+    } else {
+        format!("{}...", &first[..150])
+    }
+}
+
 fn find_snippet_with_line(content: &str, query: &str, max_len: usize) -> (String, Option<usize>) {
     let query_lower = query.to_lowercase();
     let terms: Vec<&str> = query_lower.split_whitespace().collect();
@@ -1992,7 +2547,7 @@ fn find_snippet_with_line(content: &str, query: &str, max_len: usize) -> (String
     (snippet, None)
 }
 
-fn resolve_search_root(path: Option<&str>) -> Result<PathBuf> {
+pub(crate) fn resolve_search_root(path: Option<&str>) -> Result<PathBuf> {
     let cwd = std::env::current_dir().context("Cannot determine current directory")?;
     let requested = path.map(PathBuf::from).unwrap_or_else(|| cwd.clone());
     let absolute = if requested.is_absolute() {
@@ -2138,6 +2693,144 @@ mod tests {
         assert_eq!(outcome.results[0].path, "sub.rs");
     }
 
+    #[test]
+    fn index_search_matches_identifier_sub_words() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        std::fs::write(root.join("a.rs"), "pub struct IndexBuilder;\n").expect("write a");
+        std::fs::write(root.join("b.rs"), "fn run(max_results: usize) {}\n").expect("write b");
+        std::fs::write(root.join("c.js"), "const limit = maxResults;\n").expect("write c");
+        std::fs::write(root.join("d.rs"), "fn unrelated() {}\n").expect("write d");
+
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+
+        let paths = |query: &str| {
+            let outcome =
+                index_search(query, root, root, 10, 0, None, None, None, &[], None, false)
+                    .expect("index search");
+            let mut paths: Vec<String> = outcome.results.into_iter().map(|r| r.path).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(paths("index builder"), vec!["a.rs"]);
+        assert_eq!(paths("IndexBuilder"), vec!["a.rs"]);
+        assert_eq!(paths("max results"), vec!["b.rs", "c.js"]);
+        assert_eq!(paths("max_results"), vec!["b.rs", "c.js"]);
+    }
+
+    #[test]
+    fn kind_filter_hits_report_source_lines() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).expect("mkdir src");
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "/// First helper\npub fn alpha() -> u32 {\n    1\n}\n\n/// Second helper\n/// calls the first\npub fn beta() -> u32 {\n    let x = 2;\n    alpha();\n    x\n}\n\npub fn gamma() {}\n",
+        )
+        .expect("write lib.rs");
+
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+
+        let hits = |query: &str| {
+            let outcome =
+                index_search(query, root, root, 10, 0, None, None, None, &[], None, false)
+                    .expect("index search");
+            let mut hits: Vec<(Option<usize>, String)> = outcome
+                .results
+                .into_iter()
+                .map(|r| (r.line, r.snippet))
+                .collect();
+            hits.sort();
+            hits
+        };
+        assert_eq!(
+            hits("kind:function alpha"),
+            vec![
+                (Some(2), "pub fn alpha() -> u32 {".to_string()),
+                (Some(10), "alpha();".to_string()),
+            ]
+        );
+        // Matched by name only: the declaration line
+        assert_eq!(
+            hits("kind:function sym:gamma"),
+            vec![(Some(14), "pub fn gamma() {}".to_string())]
+        );
+    }
+
+    #[test]
+    fn index_search_applies_structured_query_filters() {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/indexer")).expect("mkdir src");
+        std::fs::create_dir_all(root.join("tests")).expect("mkdir tests");
+        std::fs::write(
+            root.join("src/indexer/build.rs"),
+            "pub fn build_index() {\n    // exact phrase here\n}\n\npub const BUILD_LIMIT: usize = 3;\n",
+        )
+        .expect("write build.rs");
+        std::fs::write(
+            root.join("src/build.py"),
+            "def build_index():\n    return 1  # phrase exact\n",
+        )
+        .expect("write build.py");
+        std::fs::write(root.join("tests/build.rs"), "fn build_index_test() {}\n")
+            .expect("write test");
+
+        IndexBuilder::new(root)
+            .expect("builder")
+            .without_progress()
+            .build_stats(false, DEFAULT_WRITER_BUDGET_BYTES)
+            .expect("build");
+
+        let hits = |query: &str| {
+            let outcome =
+                index_search(query, root, root, 10, 0, None, None, None, &[], None, false)
+                    .expect("index search");
+            let mut hits: Vec<(String, Option<usize>)> = outcome
+                .results
+                .into_iter()
+                .map(|r| (r.path, r.line))
+                .collect();
+            hits.sort();
+            hits
+        };
+        let hit = |path: &str, line: usize| (path.to_string(), Some(line));
+        assert_eq!(
+            hits("lang:rust path:src/indexer build"),
+            vec![hit("src/indexer/build.rs", 1)]
+        );
+        assert_eq!(
+            hits("\"exact phrase\""),
+            vec![hit("src/indexer/build.rs", 2)]
+        );
+        assert_eq!(
+            hits("build -test"),
+            vec![hit("src/build.py", 1), hit("src/indexer/build.rs", 1)]
+        );
+        assert_eq!(
+            hits("lang:py OR path:tests build"),
+            vec![hit("src/build.py", 1), hit("tests/build.rs", 1)]
+        );
+        assert_eq!(
+            hits("kind:const sym:build"),
+            vec![hit("src/indexer/build.rs", 5)]
+        );
+        // Paths match from the index root, not above it
+        let root_name = root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .expect("root name");
+        assert!(hits(&format!("path:{} build", root_name)).is_empty());
+    }
+
     #[test]
     fn context_pack_trims_overlapping_context() {
         let mut results = vec![
@@ -2154,6 +2847,7 @@ mod tests {
                 result_id: None,
                 chunk_start: None,
                 chunk_end: None,
+                context_start: None,
             },
             SearchResult {
                 path: "src/lib.rs".to_string(),
@@ -2168,6 +2862,7 @@ mod tests {
                 result_id: None,
                 chunk_start: None,
                 chunk_end: None,
+                context_start: None,
             },
         ];
 
@@ -2192,6 +2887,7 @@ mod tests {
             result_id: None,
             chunk_start: None,
             chunk_end: None,
+            context_start: None,
         };
 
         let a = stable_result_id(&result);
@@ -2214,6 +2910,7 @@ mod tests {
             result_id: None,
             chunk_start: None,
             chunk_end: None,
+            context_start: None,
         }
     }
 
@@ -2225,6 +2922,7 @@ mod tests {
             SearchOutputBudget {
                 max_chars_per_snippet: Some(8),
                 max_total_chars: None,
+                max_tokens: None,
                 max_context_chars: None,
                 dedupe_context: false,
                 suppress_boilerplate: false,
@@ -2243,6 +2941,7 @@ mod tests {
             SearchOutputBudget {
                 max_chars_per_snippet: None,
                 max_total_chars: None,
+                max_tokens: None,
                 max_context_chars: Some(6),
                 dedupe_context: false,
                 suppress_boilerplate: false,
@@ -2271,6 +2970,7 @@ mod tests {
             SearchOutputBudget {
                 max_chars_per_snippet: None,
                 max_total_chars: Some(25),
+                max_tokens: None,
                 max_context_chars: None,
                 dedupe_context: false,
                 suppress_boilerplate: false,
@@ -2282,6 +2982,33 @@ mod tests {
         assert_eq!(results.len(), 3 - stats.dropped_results);
     }
 
+    #[test]
+    fn budget_max_tokens_truncates_and_reports_usage() {
+        let mut long = sample_result("a.rs", 1, "let needle = build_request(config);");
+        long.context_after = vec!["    send(needle).await?;".to_string(); 20];
+        let mut results = vec![long, sample_result("b.rs", 2, "needle")];
+        let stats = apply_output_budget(
+            &mut results,
+            SearchOutputBudget {
+                max_chars_per_snippet: None,
+                max_total_chars: None,
+                max_tokens: Some(40),
+                max_context_chars: None,
+                dedupe_context: false,
+                suppress_boilerplate: false,
+            },
+        );
+
+        assert!(stats.truncated);
+        assert!(stats.tokens_used <= 40, "{}", stats.tokens_used);
+        assert!(results[0].context_after.len() < 20);
+        let used: usize = results
+            .iter()
+            .map(|result| result_size(result, BudgetUnit::Tokens))
+            .sum();
+        assert_eq!(stats.tokens_used, used);
+    }
+
     #[test]
     fn budget_dedupes_context_lines_per_path() {
         let mut first = sample_result("same.rs", 1, "a");
@@ -2297,6 +3024,7 @@ mod tests {
             SearchOutputBudget {
                 max_chars_per_snippet: None,
                 max_total_chars: None,
+                max_tokens: None,
                 max_context_chars: None,
                 dedupe_context: true,
                 suppress_boilerplate: false,
@@ -6,54 +6,53 @@ use anyhow::Result;
 use colored::Colorize;
 use serde::Serialize;
 use std::collections::HashSet;
+use std::path::Path;
 use std::time::Instant;
 
-use crate::cli::OutputFormat;
-use crate::indexer::scanner::{FileScanner, ScannedFile};
-use crate::parser::symbols::SymbolExtractor;
-use crate::query::changed_files::ChangedFiles;
-use crate::query::index_filter::{find_files_with_symbol, read_scanned_files};
-use cgrep::config::Config;
-use cgrep::filters::{
+use crate::config::Config;
+use crate::filters::{
     matches_file_type, matches_glob_compiled, should_exclude_compiled, CompiledGlob,
 };
-use cgrep::output::{
+use crate::output::OutputFormat;
+use crate::output::{
     colorize_kind, colorize_line_num, colorize_name, colorize_path, print_json, use_colors,
 };
-use cgrep::utils::get_root_with_index;
+use crate::query::changed_files::ChangedFiles;
+use crate::query::index_filter::find_symbol_records;
+use crate::utils::get_root_with_index;
 
 /// Symbol result for JSON output
 #[derive(Debug, Serialize)]
-struct SymbolResult {
-    name: String,
-    kind: String,
-    path: String,
-    line: usize,
+pub struct SymbolResult {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qualified_name: Option<String>,
+    pub kind: String,
+    pub path: String,
+    pub line: usize,
 }
 
-/// Run the symbols command
-#[allow(clippy::too_many_arguments)]
-pub fn run(
-    name: &str,
-    symbol_type: Option<&str>,
-    lang: Option<&str>,
-    file_type: Option<&str>,
-    glob_pattern: Option<&str>,
-    exclude_pattern: Option<&str>,
-    changed: Option<&str>,
-    quiet: bool,
-    format: OutputFormat,
-    compact: bool,
-) -> Result<()> {
-    let start_time = Instant::now();
-    let use_color = use_colors() && format == OutputFormat::Text;
-
-    // Load config for exclude patterns
-    let config = Config::load();
+/// Path, language and kind filters applied to symbol matches
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SymbolFilters<'a> {
+    pub symbol_type: Option<&'a str>,
+    pub lang: Option<&'a str>,
+    pub file_type: Option<&'a str>,
+    pub glob: Option<&'a str>,
+    pub exclude: Option<&'a str>,
+    pub changed: Option<&'a str>,
+}
 
+/// Symbols under `root` matching `name`, and the number of files they came from
+pub fn find(
+    root: &Path,
+    config: &Config,
+    name: &str,
+    filters: &SymbolFilters<'_>,
+) -> Result<(Vec<SymbolResult>, usize)> {
     // Precompile glob patterns for efficient repeated matching
-    let compiled_glob = glob_pattern.and_then(CompiledGlob::new);
-    let compiled_exclude = exclude_pattern.and_then(CompiledGlob::new);
+    let compiled_glob = filters.glob.and_then(CompiledGlob::new);
+    let compiled_exclude = filters.exclude.and_then(CompiledGlob::new);
 
     // Compile config exclude patterns
     let config_exclude_patterns: Vec<CompiledGlob> = config
@@ -62,30 +61,22 @@ pub fn run(
         .filter_map(|p| CompiledGlob::new(p.as_str()))
         .collect();
 
-    let root = get_root_with_index(std::env::current_dir()?);
-    let extractor = SymbolExtractor::new();
-    let name_lower = name.to_lowercase();
-    let changed_filter = changed
-        .map(|rev| ChangedFiles::from_scope(&root, rev))
+    let changed_filter = filters
+        .changed
+        .map(|rev| ChangedFiles::from_scope(root, rev))
         .transpose()?;
 
-    // Try to use index for fast file filtering first
-    let files: Vec<ScannedFile> = match find_files_with_symbol(&root, name)? {
-        Some(indexed_paths) => read_scanned_files(&indexed_paths),
-        None => {
-            let scanner = FileScanner::new(&root);
-            scanner.scan()?
-        }
-    };
+    // Symbol table (or index-narrowed parse) already filters by name
+    let records = find_symbol_records(root, name)?;
 
     let mut results: Vec<SymbolResult> = Vec::new();
     let mut files_searched: HashSet<String> = HashSet::new();
 
-    for file in files {
-        let rel_path = file
-            .path
-            .strip_prefix(&root)
-            .unwrap_or(&file.path)
+    for record in records {
+        let full_path = root.join(&record.path);
+        let rel_path = full_path
+            .strip_prefix(root)
+            .unwrap_or(&full_path)
             .display()
             .to_string();
 
@@ -95,7 +86,7 @@ pub fn run(
                 continue;
             }
         }
-        if !matches_file_type(&rel_path, file_type) {
+        if !matches_file_type(&rel_path, filters.file_type) {
             continue;
         }
         if !matches_glob_compiled(&rel_path, compiled_glob.as_ref()) {
@@ -113,40 +104,63 @@ pub fn run(
         }
 
         // Filter by language if specified
-        if let Some(filter_lang) = lang {
-            if file.language.as_deref() != Some(filter_lang) {
+        if let Some(filter_lang) = filters.lang {
+            if record.language != filter_lang {
                 continue;
             }
         }
 
         files_searched.insert(rel_path.clone());
 
-        if let Some(ref file_lang) = file.language {
-            if let Ok(symbols) = extractor.extract(&file.content, file_lang) {
-                for symbol in symbols {
-                    // Filter by name
-                    if !symbol.name.to_lowercase().contains(&name_lower) {
-                        continue;
-                    }
-
-                    // Filter by type if specified
-                    if let Some(filter_type) = symbol_type {
-                        if symbol.kind.to_string() != filter_type.to_lowercase() {
-                            continue;
-                        }
-                    }
-
-                    results.push(SymbolResult {
-                        name: symbol.name.clone(),
-                        kind: symbol.kind.to_string(),
-                        path: rel_path.clone(),
-                        line: symbol.line,
-                    });
-                }
+        // Filter by type if specified
+        if let Some(filter_type) = filters.symbol_type {
+            if record.kind != filter_type.to_lowercase() {
+                continue;
             }
         }
+
+        results.push(SymbolResult {
+            qualified_name: record.scope.as_ref().map(|_| record.qualified_name()),
+            name: record.name,
+            kind: record.kind,
+            path: rel_path,
+            line: record.line,
+        });
     }
 
+    Ok((results, files_searched.len()))
+}
+
+/// Run the symbols command
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    name: &str,
+    symbol_type: Option<&str>,
+    lang: Option<&str>,
+    file_type: Option<&str>,
+    glob_pattern: Option<&str>,
+    exclude_pattern: Option<&str>,
+    changed: Option<&str>,
+    quiet: bool,
+    format: OutputFormat,
+    compact: bool,
+) -> Result<()> {
+    let start_time = Instant::now();
+    let use_color = use_colors() && format == OutputFormat::Text;
+
+    // Load config for exclude patterns
+    let config = Config::load();
+    let root = get_root_with_index(std::env::current_dir()?);
+    let filters = SymbolFilters {
+        symbol_type,
+        lang,
+        file_type,
+        glob: glob_pattern,
+        exclude: exclude_pattern,
+        changed,
+    };
+    let (results, files_searched) = find(&root, &config, name, &filters)?;
+
     let elapsed = start_time.elapsed();
 
     match format {
@@ -176,7 +190,10 @@ pub fn run(
                     println!(
                         "  {} {} {}:{}",
                         colorize_kind(&kind_str, use_color),
-                        colorize_name(&result.name, use_color),
+                        colorize_name(
+                            result.qualified_name.as_deref().unwrap_or(&result.name),
+                            use_color
+                        ),
                         colorize_path(&result.path, use_color),
                         colorize_line_num(result.line, use_color)
                     );
@@ -197,7 +214,7 @@ pub fn run(
             if !quiet {
                 eprintln!(
                     "\n{} files | {} symbols | {:.2}ms",
-                    files_searched.len(),
+                    files_searched,
                     results.len(),
                     elapsed.as_secs_f64() * 1000.0
                 );
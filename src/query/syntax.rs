@@ -0,0 +1,529 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Inline query syntax: field filters and boolean operators in one string.
+//!
+//! `lang:rust path:src/indexer kind:function sym:build "exact phrase" -test OR foo`
+//!
+//! Terms are separated by spaces and must all match; `OR` between two terms
+//! makes either one enough, and a leading `-` excludes a term. Quoted text is
+//! matched as a phrase. Fields:
+//!
+//! - `lang:` (`language:`) file language (`rust`, `ts`, `py`, ...)
+//! - `path:` path component prefix, with `*`/`**`/`?` globs
+//! - `kind:` symbol kind; restricts results to symbols
+//! - `sym:` (`symbol:`) symbol name
+//!
+//! Queries using none of this keep plain ranked matching of any term.
+
+use anyhow::Result;
+use regex::Regex;
+use tantivy::query::{
+    AllQuery, BooleanQuery, ConstScoreQuery, Occur, PhraseQuery, Query, RegexQuery, TermQuery,
+};
+use tantivy::schema::{Field, FieldType, IndexRecordOption, Schema, Term};
+use tantivy::tokenizer::TokenizerManager;
+
+use crate::indexer::scanner::detect_language;
+use crate::indexer::tokenizer;
+
+/// Field filter of a term
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// Language name as stored in the index (`rust`, `typescript`)
+    Lang(String),
+    /// Path component prefix as a regex fragment, and that fragment
+    /// compiled for relative paths
+    Path {
+        pattern: String,
+        relative: Regex,
+    },
+    /// Symbol kind (`function`, `struct`)
+    Kind(String),
+    Symbol(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Atom {
+    Word(String),
+    Phrase(String),
+    Field(Filter),
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryTerm {
+    pub negated: bool,
+    pub atom: Atom,
+}
+
+/// Parsed query: every group must match, any term of a group is enough
+#[derive(Debug, Clone)]
+pub struct StructuredQuery {
+    pub groups: Vec<Vec<QueryTerm>>,
+}
+
+/// Language name for a `lang:` value, accepting extensions and short names
+fn language_name(value: &str) -> String {
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "c++" => "cpp".to_string(),
+        "c#" | "cs" => "csharp".to_string(),
+        _ => detect_language(&value).unwrap_or(value),
+    }
+}
+
+/// Symbol kind for a `kind:` value, accepting common abbreviations
+fn kind_name(value: &str) -> String {
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "fn" | "func" | "def" => "function".to_string(),
+        "const" => "constant".to_string(),
+        "var" | "let" => "variable".to_string(),
+        "mod" | "namespace" | "package" => "module".to_string(),
+        "iface" => "interface".to_string(),
+        _ => value,
+    }
+}
+
+/// Regex fragment for a path glob: `*` and `?` stay within a component
+fn path_pattern(value: &str) -> String {
+    let value = value.trim_start_matches("./").trim_end_matches('/');
+    let mut pattern = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern
+}
+
+fn parse_field(name: &str, value: &str) -> Option<Filter> {
+    if value.is_empty() {
+        return None;
+    }
+    match name {
+        "lang" | "language" => Some(Filter::Lang(language_name(value))),
+        "path" => {
+            let pattern = path_pattern(value);
+            let relative = Regex::new(&format!("^(.*/)?{}.*$", pattern)).ok()?;
+            Some(Filter::Path { pattern, relative })
+        }
+        "kind" => Some(Filter::Kind(kind_name(value))),
+        "sym" | "symbol" => Some(Filter::Symbol(value.to_string())),
+        _ => None,
+    }
+}
+
+/// Read a quoted string starting after its opening quote
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            break;
+        }
+        text.push(c);
+    }
+    text
+}
+
+impl StructuredQuery {
+    /// Parse `query`; `None` when it uses none of the syntax
+    pub fn parse(query: &str) -> Option<Self> {
+        let mut groups: Vec<Vec<QueryTerm>> = Vec::new();
+        let mut structured = false;
+        let mut join_next = false;
+        let mut chars = query.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&first) = chars.peek() else {
+                break;
+            };
+
+            // `-x` negates, `--flag` and a lone `-` are text
+            let mut negated = false;
+            if first == '-' {
+                chars.next();
+                match chars.peek() {
+                    Some(&c) if c != '-' && !c.is_whitespace() => negated = true,
+                    _ => {
+                        let mut word = "-".to_string();
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            word.push(c);
+                        }
+                        push_term(
+                            &mut groups,
+                            &mut join_next,
+                            QueryTerm {
+                                negated: false,
+                                atom: Atom::Word(word),
+                            },
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            let atom = if chars.next_if_eq(&'"').is_some() {
+                Atom::Phrase(read_quoted(&mut chars))
+            } else {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    word.push(c);
+                    // `path:"a b"` quotes the value
+                    if c == ':' && chars.peek() == Some(&'"') {
+                        chars.next();
+                        word.push_str(&read_quoted(&mut chars));
+                        break;
+                    }
+                }
+                if word == "OR" && !negated {
+                    if !groups.is_empty() {
+                        join_next = true;
+                        structured = true;
+                    }
+                    continue;
+                }
+                match word.split_once(':') {
+                    Some((name, value)) => match parse_field(&name.to_lowercase(), value) {
+                        Some(filter) => Atom::Field(filter),
+                        None => Atom::Word(word),
+                    },
+                    None => Atom::Word(word),
+                }
+            };
+            structured |= negated || !matches!(atom, Atom::Word(_));
+            push_term(&mut groups, &mut join_next, QueryTerm { negated, atom });
+        }
+
+        structured.then_some(Self { groups })
+    }
+
+    /// Positive words, phrases and symbol names, for snippets, highlighting
+    /// and query embeddings
+    pub fn text(&self) -> String {
+        self.groups
+            .iter()
+            .flatten()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.atom {
+                Atom::Word(text) | Atom::Phrase(text) => Some(text.as_str()),
+                Atom::Field(Filter::Symbol(name)) => Some(name.as_str()),
+                Atom::Field(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether a `kind:` filter restricts results to symbols
+    pub fn targets_symbols(&self) -> bool {
+        self.groups
+            .iter()
+            .flatten()
+            .any(|term| !term.negated && matches!(term.atom, Atom::Field(Filter::Kind(_))))
+    }
+
+    /// Whether the query has a `kind:` filter, which scans cannot check
+    pub fn has_kind_filter(&self) -> bool {
+        self.groups
+            .iter()
+            .flatten()
+            .any(|term| matches!(term.atom, Atom::Field(Filter::Kind(_))))
+    }
+
+    /// tantivy query over the index fields. Stored paths may be absolute, so
+    /// `path:` can also match above the index root; candidates are checked
+    /// again with [`Self::matches_fields`].
+    pub fn to_tantivy(&self, schema: &Schema) -> Result<Box<dyn Query>> {
+        let field = |name: &str| schema.get_field(name);
+        let content = field("content")?;
+        let symbols = field("symbols")?;
+        let language = field("language")?;
+        let path_exact = field("path_exact")?;
+        let symbol_kind = field("symbol_kind")?;
+        let tokenizers = tokenizer::query_tokenizers();
+
+        let term_query = |term: &QueryTerm| -> Result<Option<Box<dyn Query>>> {
+            let query: Box<dyn Query> = match &term.atom {
+                Atom::Word(text) | Atom::Phrase(text) => {
+                    match text_query(schema, &tokenizers, &[content, symbols], text) {
+                        Some(query) => query,
+                        None => return Ok(None),
+                    }
+                }
+                Atom::Field(Filter::Symbol(name)) => {
+                    match text_query(schema, &tokenizers, &[symbols], name) {
+                        Some(query) => query,
+                        None => return Ok(None),
+                    }
+                }
+                Atom::Field(Filter::Lang(name)) => Box::new(ConstScoreQuery::new(
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(language, name),
+                        IndexRecordOption::Basic,
+                    )),
+                    0.0,
+                )),
+                Atom::Field(Filter::Kind(kind)) => Box::new(ConstScoreQuery::new(
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(symbol_kind, kind),
+                        IndexRecordOption::Basic,
+                    )),
+                    0.0,
+                )),
+                Atom::Field(Filter::Path { pattern, .. }) => {
+                    let pattern = format!("(.*/)?{}.*", pattern);
+                    Box::new(ConstScoreQuery::new(
+                        Box::new(RegexQuery::from_pattern(&pattern, path_exact)?),
+                        0.0,
+                    ))
+                }
+            };
+            Ok(Some(query))
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for group in &self.groups {
+            let mut alternatives: Vec<(bool, Box<dyn Query>)> = Vec::new();
+            for term in group {
+                if let Some(query) = term_query(term)? {
+                    alternatives.push((term.negated, query));
+                }
+            }
+            match alternatives.len() {
+                0 => {}
+                1 => {
+                    let (negated, query) = alternatives.remove(0);
+                    clauses.push((if negated { Occur::MustNot } else { Occur::Must }, query));
+                }
+                _ => {
+                    let either = alternatives
+                        .into_iter()
+                        .map(|(negated, query)| {
+                            let query: Box<dyn Query> = if negated {
+                                Box::new(BooleanQuery::new(vec![
+                                    (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+                                    (Occur::MustNot, query),
+                                ]))
+                            } else {
+                                query
+                            };
+                            (Occur::Should, query)
+                        })
+                        .collect();
+                    clauses.push((Occur::Must, Box::new(BooleanQuery::new(either))));
+                }
+            }
+        }
+        // Only exclusions: start from every document
+        if !clauses.iter().any(|(occur, _)| *occur == Occur::Must) {
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        }
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// Whether a line of the file at relative `path` matches, for scans.
+    /// `kind:` cannot be checked on lines and always passes.
+    pub fn matches_line(&self, path: &str, line: &str, case_sensitive: bool) -> bool {
+        let line = if case_sensitive {
+            line.to_string()
+        } else {
+            line.to_lowercase()
+        };
+        let contains = |text: &str| {
+            if case_sensitive {
+                line.contains(text)
+            } else {
+                line.contains(&text.to_lowercase())
+            }
+        };
+        self.groups.iter().all(|group| {
+            group.iter().any(|term| {
+                let matched = match &term.atom {
+                    Atom::Word(text) | Atom::Phrase(text) => contains(text),
+                    Atom::Field(Filter::Symbol(name)) => contains(name),
+                    Atom::Field(Filter::Kind(_)) => return true,
+                    Atom::Field(filter) => matches_path_filter(filter, path),
+                };
+                matched != term.negated
+            })
+        })
+    }
+
+    /// Whether a result at `path`, relative to the index root, passes the
+    /// groups made only of field filters; `symbol` is its kind and name.
+    /// Groups holding free text only rank and are skipped, as are `kind:` and
+    /// `sym:` without a symbol.
+    pub fn matches_fields(&self, path: &str, symbol: Option<(&str, &str)>) -> bool {
+        self.groups
+            .iter()
+            .filter(|group| group.iter().all(|t| matches!(t.atom, Atom::Field(_))))
+            .all(|group| {
+                group.iter().any(|term| {
+                    let matched = match &term.atom {
+                        Atom::Field(Filter::Kind(wanted)) => match symbol {
+                            Some((kind, _)) => kind.eq_ignore_ascii_case(wanted),
+                            None => return true,
+                        },
+                        Atom::Field(Filter::Symbol(wanted)) => match symbol {
+                            Some((_, name)) => name.to_lowercase().contains(&wanted.to_lowercase()),
+                            None => return true,
+                        },
+                        Atom::Field(filter) => matches_path_filter(filter, path),
+                        _ => true,
+                    };
+                    matched != term.negated
+                })
+            })
+    }
+}
+
+fn push_term(groups: &mut Vec<Vec<QueryTerm>>, join_next: &mut bool, term: QueryTerm) {
+    match groups.last_mut() {
+        Some(group) if *join_next => group.push(term),
+        _ => groups.push(vec![term]),
+    }
+    *join_next = false;
+}
+
+/// `lang:` and `path:` against a relative path
+fn matches_path_filter(filter: &Filter, path: &str) -> bool {
+    match filter {
+        Filter::Lang(name) => std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(detect_language)
+            .is_some_and(|lang| &lang == name),
+        Filter::Path { relative, .. } => relative.is_match(&path.replace('\\', "/")),
+        _ => true,
+    }
+}
+
+/// Term or phrase query for `text` over `fields`, analyzed like queries on them
+fn text_query(
+    schema: &Schema,
+    tokenizers: &TokenizerManager,
+    fields: &[Field],
+    text: &str,
+) -> Option<Box<dyn Query>> {
+    let per_field: Vec<(Occur, Box<dyn Query>)> = fields
+        .iter()
+        .filter_map(|&field| {
+            let name = match schema.get_field_entry(field).field_type() {
+                FieldType::Str(options) => options
+                    .get_indexing_options()
+                    .map(|indexing| indexing.tokenizer().to_string()),
+                _ => None,
+            }?;
+            let mut analyzer = tokenizers.get(&name)?;
+            let mut terms: Vec<(usize, Term)> = Vec::new();
+            let mut stream = analyzer.token_stream(text);
+            stream.process(&mut |token| {
+                terms.push((token.position, Term::from_field_text(field, &token.text)));
+            });
+            let query: Box<dyn Query> = match terms.len() {
+                0 => return None,
+                1 => Box::new(TermQuery::new(
+                    terms.remove(0).1,
+                    IndexRecordOption::WithFreqs,
+                )),
+                _ => Box::new(PhraseQuery::new_with_offset(terms)),
+            };
+            Some((Occur::Should, query))
+        })
+        .collect();
+    (!per_field.is_empty()).then(|| Box::new(BooleanQuery::new(per_field)) as Box<dyn Query>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(query: &str) -> Vec<Vec<String>> {
+        StructuredQuery::parse(query)
+            .expect("structured")
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|term| {
+                        let atom = match &term.atom {
+                            Atom::Word(text) => format!("word:{}", text),
+                            Atom::Phrase(text) => format!("phrase:{}", text),
+                            Atom::Field(Filter::Lang(name)) => format!("lang:{}", name),
+                            Atom::Field(Filter::Path { pattern, .. }) => {
+                                format!("path:{}", pattern)
+                            }
+                            Atom::Field(Filter::Kind(kind)) => format!("kind:{}", kind),
+                            Atom::Field(Filter::Symbol(name)) => format!("sym:{}", name),
+                        };
+                        if term.negated {
+                            format!("-{}", atom)
+                        } else {
+                            atom
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_fields_phrases_negation_and_or() {
+        assert_eq!(
+            summary(r#"lang:rs path:src/indexer kind:fn sym:build "exact phrase" -test OR foo"#),
+            vec![
+                vec!["lang:rust"],
+                vec!["path:src/indexer"],
+                vec!["kind:function"],
+                vec!["sym:build"],
+                vec!["phrase:exact phrase"],
+                vec!["-word:test", "word:foo"],
+            ]
+        );
+        assert_eq!(
+            summary(r#"path:"my dir/**/*.rs" a OR b OR c --force -"not this""#),
+            vec![
+                vec![r"path:my dir/.*/[^/]*\.rs"],
+                vec!["word:a", "word:b", "word:c"],
+                vec!["word:--force"],
+                vec!["-phrase:not this"],
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_is_not_structured() {
+        assert!(StructuredQuery::parse("index builder").is_none());
+        assert!(StructuredQuery::parse("std::fmt http://example.com --force").is_none());
+        assert!(StructuredQuery::parse("OR").is_none());
+    }
+
+    #[test]
+    fn matches_lines_and_symbols() {
+        let query = StructuredQuery::parse("lang:rust path:src needle -skip").expect("structured");
+        assert!(query.matches_line("src/lib.rs", "let Needle = 1;", false));
+        assert!(!query.matches_line("src/lib.rs", "let Needle = 1;", true));
+        assert!(!query.matches_line("src/lib.rs", "needle; // skip", false));
+        assert!(!query.matches_line("src/app.py", "needle", false));
+        assert!(!query.matches_line("tests/lib.rs", "needle", false));
+        assert_eq!(query.text(), "needle");
+
+        let query = StructuredQuery::parse("kind:function sym:build OR sym:run path:src/**/query")
+            .expect("structured");
+        assert!(query.targets_symbols());
+        assert!(query.matches_fields("src/a/query/mod.rs", Some(("function", "build_map"))));
+        assert!(!query.matches_fields("src/a/query/mod.rs", Some(("struct", "build_map"))));
+        assert!(!query.matches_fields("src/a/query/mod.rs", Some(("function", "parse"))));
+        assert!(!query.matches_fields("src/queries.rs", Some(("function", "run"))));
+        assert!(query.matches_fields("src/a/query/mod.rs", None));
+        assert!(!query.matches_fields("lib/query/mod.rs", None));
+    }
+}
@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Agent sessions: which results a conversation has already received.
+//!
+//! `--session <id>` on `search`, `agent locate` and `agent expand` records the
+//! line ranges each call delivered in `.cgrep/sessions/<hash>.json`, next to
+//! the search cache. Later calls in the same session return hits whose lines
+//! were all delivered before, from an unchanged file, as short "already sent"
+//! references instead of repeating the text. Deliveries expire after a TTL.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::query::result_ids::content_hash;
+
+/// Lines of one file delivered to a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Result ID the lines were delivered under, when there was one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Path relative to the index root
+    pub path: String,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// `content_hash` of the file when the lines were delivered
+    pub file_hash: String,
+    /// Unix epoch milliseconds
+    pub delivered_at: u64,
+}
+
+/// Hit returned as a reference because its lines were already delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlreadySent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub path: String,
+    pub line: usize,
+}
+
+/// Deliveries of one session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub deliveries: Vec<Delivery>,
+}
+
+impl Session {
+    /// Whether every line of `ranges` in `path` was delivered while the file
+    /// hashed to `file_hash`; returns the ID of the delivery holding the first line
+    pub fn covers(
+        &self,
+        path: &str,
+        file_hash: &str,
+        ranges: &[(usize, usize)],
+    ) -> Option<Option<String>> {
+        let deliveries: Vec<&Delivery> = self
+            .deliveries
+            .iter()
+            .filter(|d| d.path == path && d.file_hash == file_hash)
+            .collect();
+        let holder = |line: usize| {
+            deliveries
+                .iter()
+                .find(|d| d.start_line <= line && line <= d.end_line)
+        };
+        let covered = ranges
+            .iter()
+            .all(|&(start, end)| (start..=end).all(|line| holder(line).is_some()));
+        if !covered {
+            return None;
+        }
+        let first = ranges.first().map(|&(start, _)| start)?;
+        Some(holder(first).and_then(|d| d.id.clone()))
+    }
+
+    /// Record `ranges` of `path` as delivered now
+    pub fn record(
+        &mut self,
+        id: Option<&str>,
+        path: &str,
+        file_hash: &str,
+        ranges: &[(usize, usize)],
+    ) {
+        let now = now_ms();
+        for &(start_line, end_line) in ranges {
+            self.deliveries.push(Delivery {
+                id: id.map(str::to_string),
+                path: path.to_string(),
+                start_line,
+                end_line,
+                file_hash: file_hash.to_string(),
+                delivered_at: now,
+            });
+        }
+    }
+}
+
+/// Session store under `.cgrep/sessions`
+pub struct SessionStore {
+    sessions_dir: PathBuf,
+    ttl_ms: u64,
+}
+
+impl SessionStore {
+    /// Default delivery TTL (30 minutes)
+    pub const DEFAULT_TTL_MS: u64 = 1_800_000;
+
+    /// Open the store of an index root, creating its directory
+    pub fn new<P: AsRef<Path>>(repo_root: P, ttl_ms: u64) -> Result<Self> {
+        let sessions_dir = repo_root.as_ref().join(".cgrep").join("sessions");
+        fs::create_dir_all(&sessions_dir).with_context(|| {
+            format!(
+                "Failed to create sessions directory: {}",
+                sessions_dir.display()
+            )
+        })?;
+        Ok(Self {
+            sessions_dir,
+            ttl_ms,
+        })
+    }
+
+    fn session_path(&self, id: &str) -> PathBuf {
+        let hash = blake3::hash(id.as_bytes());
+        self.sessions_dir
+            .join(format!("{}.json", &hash.to_hex()[..32]))
+    }
+
+    /// Load a session without its expired deliveries (empty if new)
+    pub fn load(&self, id: &str) -> Result<Session> {
+        let path = self.session_path(id);
+        let mut session = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str::<Session>(&content)
+                .ok()
+                .filter(|session| session.id == id)
+                .unwrap_or_else(|| Session {
+                    id: id.to_string(),
+                    deliveries: Vec::new(),
+                }),
+            Err(_) => Session {
+                id: id.to_string(),
+                deliveries: Vec::new(),
+            },
+        };
+        let now = now_ms();
+        session
+            .deliveries
+            .retain(|d| now.saturating_sub(d.delivered_at) < self.ttl_ms);
+        Ok(session)
+    }
+
+    /// Write a session back and prune sessions idle past the TTL
+    pub fn save(&self, session: &Session) -> Result<()> {
+        let path = self.session_path(&session.id);
+        let json = serde_json::to_string(session).context("Failed to serialize session")?;
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write session: {}", path.display()))?;
+        self.prune()?;
+        Ok(())
+    }
+
+    /// Remove session files not written within the TTL
+    pub fn prune(&self) -> Result<usize> {
+        let mut count = 0;
+        let now = SystemTime::now();
+        for entry in fs::read_dir(&self.sessions_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                    let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+                    if age.as_millis() as u64 > self.ttl_ms {
+                        fs::remove_file(&path)?;
+                        count += 1;
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Checks the hits of one call against a session and records the rest
+///
+/// Hits are checked against earlier calls only, so overlapping hits of the
+/// same call are all returned. Nothing is written until [`Self::finish`].
+pub struct SessionTracker<'a> {
+    store: SessionStore,
+    session: Session,
+    search_root: &'a Path,
+    index_root: &'a Path,
+    /// display path -> (path relative to the index root, file hash)
+    files: HashMap<String, Option<(String, String)>>,
+    /// Deliveries of this call
+    pending: Vec<Delivery>,
+}
+
+impl<'a> SessionTracker<'a> {
+    /// Load `session_id` from the store of `index_root`; hit paths are
+    /// relative to `search_root`
+    pub fn open(
+        search_root: &'a Path,
+        index_root: &'a Path,
+        session_id: &str,
+        ttl_ms: u64,
+    ) -> Result<Self> {
+        let store = SessionStore::new(index_root, ttl_ms)?;
+        let session = store.load(session_id)?;
+        Ok(Self {
+            store,
+            session,
+            search_root,
+            index_root,
+            files: HashMap::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Reference for a hit whose `ranges` the session already received, else
+    /// `None` and the ranges are recorded as delivered
+    pub fn check(
+        &mut self,
+        id: Option<&str>,
+        path: &str,
+        line: usize,
+        ranges: Vec<(usize, usize)>,
+    ) -> Option<AlreadySent> {
+        let sent = self.covered(id, path, line, &ranges);
+        if sent.is_none() {
+            self.record(id, path, &ranges);
+        }
+        sent
+    }
+
+    /// Reference for a hit whose `ranges` the session already received
+    pub fn covered(
+        &mut self,
+        id: Option<&str>,
+        path: &str,
+        line: usize,
+        ranges: &[(usize, usize)],
+    ) -> Option<AlreadySent> {
+        let (rel_path, file_hash) = self.file(path)?;
+        let sent_id = self.session.covers(&rel_path, &file_hash, ranges)?;
+        Some(AlreadySent {
+            id: id.map(str::to_string).or(sent_id),
+            path: path.to_string(),
+            line,
+        })
+    }
+
+    /// Record `ranges` of a returned hit as delivered by this call
+    pub fn record(&mut self, id: Option<&str>, path: &str, ranges: &[(usize, usize)]) {
+        let Some((rel_path, file_hash)) = self.file(path) else {
+            return;
+        };
+        let now = now_ms();
+        self.pending
+            .extend(ranges.iter().map(|&(start_line, end_line)| Delivery {
+                id: id.map(str::to_string),
+                path: rel_path.clone(),
+                start_line,
+                end_line,
+                file_hash: file_hash.clone(),
+                delivered_at: now,
+            }));
+    }
+
+    /// Deliveries recorded by earlier calls
+    pub fn delivery_count(&self) -> usize {
+        self.session.deliveries.len()
+    }
+
+    /// Path relative to the index root and content hash of a hit's file
+    fn file(&mut self, path: &str) -> Option<(String, String)> {
+        let (search_root, index_root) = (self.search_root, self.index_root);
+        self.files
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                let full_path = search_root.join(path);
+                let content = fs::read(&full_path).ok()?;
+                let rel_path = full_path
+                    .strip_prefix(index_root)
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_else(|_| path.to_string());
+                Some((rel_path, content_hash(&content)))
+            })
+            .clone()
+    }
+
+    /// Record this call's deliveries
+    pub fn finish(mut self) -> Result<()> {
+        self.session.deliveries.append(&mut self.pending);
+        self.store.save(&self.session)
+    }
+}
+
+/// Lines a hit at `line` delivers: `context_start..` the last context line,
+/// split in two when the context before it does not run up to the hit
+/// (`--scope signature` on a hit inside the body)
+pub fn delivered_ranges(
+    line: usize,
+    context_start: usize,
+    before: usize,
+    after: usize,
+) -> Vec<(usize, usize)> {
+    let end_line = line + after;
+    if before > 0 && context_start + before < line {
+        vec![
+            (context_start, context_start + before - 1),
+            (line, end_line),
+        ]
+    } else {
+        vec![(context_start.min(line), end_line)]
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn covers_only_delivered_lines_of_unchanged_files() {
+        let dir = tempdir().unwrap();
+        let store = SessionStore::new(dir.path(), SessionStore::DEFAULT_TTL_MS).unwrap();
+        let mut session = store.load("conv-1").unwrap();
+        assert!(session.deliveries.is_empty());
+
+        session.record(Some("abc"), "src/lib.rs", "h1", &[(10, 20)]);
+        session.record(None, "src/lib.rs", "h1", &[(21, 25)]);
+        store.save(&session).unwrap();
+
+        let session = store.load("conv-1").unwrap();
+        assert_eq!(
+            session.covers("src/lib.rs", "h1", &[(12, 14)]),
+            Some(Some("abc".to_string()))
+        );
+        // Spans two deliveries
+        assert!(session.covers("src/lib.rs", "h1", &[(18, 24)]).is_some());
+        assert_eq!(session.covers("src/lib.rs", "h1", &[(18, 26)]), None);
+        // The file changed since
+        assert_eq!(session.covers("src/lib.rs", "h2", &[(12, 14)]), None);
+        assert_eq!(session.covers("src/main.rs", "h1", &[(12, 14)]), None);
+
+        assert!(store.load("conv-2").unwrap().deliveries.is_empty());
+    }
+
+    #[test]
+    fn deliveries_expire_after_ttl() {
+        let dir = tempdir().unwrap();
+        let store = SessionStore::new(dir.path(), 1).unwrap();
+        let mut session = store.load("conv").unwrap();
+        session.record(None, "a.rs", "h", &[(1, 3)]);
+        store.save(&session).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        assert!(store.load("conv").unwrap().deliveries.is_empty());
+    }
+
+    #[test]
+    fn split_context_delivers_two_ranges() {
+        assert_eq!(delivered_ranges(7, 5, 2, 1), vec![(5, 8)]);
+        assert_eq!(delivered_ranges(13, 8, 4, 0), vec![(8, 11), (13, 13)]);
+        assert_eq!(delivered_ranges(4, 4, 0, 0), vec![(4, 4)]);
+    }
+}
@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Offline token counting for output budgets.
+//!
+//! Approximates the cl100k BPE tokenizer without shipping its 100k-entry
+//! merge table: text is split with cl100k's pre-tokenization rules (words
+//! with their leading space, 1-3 digit numbers, punctuation runs, whitespace
+//! runs), then each piece is costed from a small built-in vocabulary of
+//! common whole-word tokens and the typical subword length for the rest.
+//! Counts for source code usually land within ~15% of the real tokenizer.
+
+/// Words of 7+ letters that cl100k encodes as a single token (lowercase, sorted)
+const VOCAB: &[&str] = &[
+    "abstract",
+    "address",
+    "archive",
+    "attribute",
+    "between",
+    "boolean",
+    "channel",
+    "character",
+    "children",
+    "command",
+    "comment",
+    "compile",
+    "component",
+    "connect",
+    "connection",
+    "console",
+    "constant",
+    "container",
+    "content",
+    "context",
+    "continue",
+    "control",
+    "convert",
+    "current",
+    "database",
+    "default",
+    "description",
+    "dictionary",
+    "directory",
+    "display",
+    "document",
+    "element",
+    "entries",
+    "environment",
+    "example",
+    "exception",
+    "execute",
+    "extends",
+    "extension",
+    "factory",
+    "finally",
+    "function",
+    "generate",
+    "handler",
+    "headers",
+    "include",
+    "instance",
+    "integer",
+    "interface",
+    "internal",
+    "invalid",
+    "iterator",
+    "library",
+    "manager",
+    "mapping",
+    "matches",
+    "message",
+    "middleware",
+    "mutable",
+    "namespace",
+    "options",
+    "override",
+    "package",
+    "partial",
+    "pattern",
+    "pointer",
+    "position",
+    "previous",
+    "println",
+    "private",
+    "process",
+    "project",
+    "property",
+    "protected",
+    "refresh",
+    "register",
+    "release",
+    "replace",
+    "request",
+    "require",
+    "resolve",
+    "resource",
+    "response",
+    "runtime",
+    "serialize",
+    "service",
+    "session",
+    "settings",
+    "storage",
+    "template",
+    "timeout",
+    "transform",
+    "validate",
+    "validation",
+    "version",
+    "virtual",
+    "visitor",
+    "without",
+];
+
+/// Estimated cl100k token count of `text`
+pub fn count_tokens(text: &str) -> usize {
+    pieces(text).map(piece_tokens).sum()
+}
+
+/// Longest prefix of `text` with at most `max_tokens` tokens, cut between
+/// pre-tokenizer pieces
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> &str {
+    let mut used = 0;
+    let mut end = 0;
+    for piece in pieces(text) {
+        used += piece_tokens(piece);
+        if used > max_tokens {
+            break;
+        }
+        end += piece.len();
+    }
+    &text[..end]
+}
+
+fn is_letter(c: char) -> bool {
+    c.is_alphabetic()
+}
+
+fn is_number(c: char) -> bool {
+    c.is_numeric()
+}
+
+fn is_newline(c: char) -> bool {
+    c == '\r' || c == '\n'
+}
+
+fn is_punct(c: char) -> bool {
+    !c.is_whitespace() && !is_letter(c) && !is_number(c)
+}
+
+/// Split `text` like the cl100k pre-tokenizer pattern:
+///
+/// ```text
+/// '(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+
+/// ```
+fn pieces(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let len = next_piece_len(rest);
+        let (piece, tail) = rest.split_at(len);
+        rest = tail;
+        Some(piece)
+    })
+}
+
+fn next_piece_len(text: &str) -> usize {
+    // Pieces longer than the lookahead are split; their cost barely changes
+    let chars: Vec<(usize, char)> = text.char_indices().take(64).collect();
+    let lookahead_end = chars
+        .last()
+        .map(|(byte, c)| byte + c.len_utf8())
+        .unwrap_or(0);
+    let byte_at = |idx: usize| chars.get(idx).map(|(b, _)| *b).unwrap_or(lookahead_end);
+    let char_at = |idx: usize| chars.get(idx).map(|(_, c)| *c);
+    let run = |from: usize, pred: fn(char) -> bool| {
+        let mut idx = from;
+        while char_at(idx).is_some_and(pred) {
+            idx += 1;
+        }
+        idx
+    };
+    let first = chars[0].1;
+
+    // Contractions
+    if first == '\'' {
+        let lower: String = chars[1..chars.len().min(3)]
+            .iter()
+            .map(|(_, c)| c.to_ascii_lowercase())
+            .collect();
+        for suffix in ["ll", "ve", "re", "s", "d", "m", "t"] {
+            if lower.starts_with(suffix) {
+                return byte_at(1 + suffix.len());
+            }
+        }
+    }
+
+    // Words, optionally with one leading non-letter, non-newline character
+    if is_letter(first) {
+        return byte_at(run(1, is_letter));
+    }
+    if !is_newline(first) && !is_number(first) && char_at(1).is_some_and(is_letter) {
+        return byte_at(run(2, is_letter));
+    }
+
+    if is_number(first) {
+        return byte_at(run(1, is_number).min(3));
+    }
+
+    // Punctuation runs, with an optional leading space and trailing newlines
+    let punct_start = if first == ' ' && char_at(1).is_some_and(is_punct) {
+        1
+    } else {
+        0
+    };
+    if char_at(punct_start).is_some_and(is_punct) {
+        let end = run(punct_start, is_punct);
+        return byte_at(run(end, is_newline));
+    }
+
+    // Whitespace: through the last newline of the run, else leave the last
+    // space to prefix the next word
+    let end = run(0, char::is_whitespace);
+    if let Some(last_newline) = (0..end)
+        .rev()
+        .find(|&idx| char_at(idx).is_some_and(is_newline))
+    {
+        return byte_at(last_newline + 1);
+    }
+    if end > 1 && char_at(end).is_some() {
+        return byte_at(end - 1);
+    }
+    byte_at(end)
+}
+
+fn piece_tokens(piece: &str) -> usize {
+    let first = piece.chars().next().unwrap_or(' ');
+    if first.is_whitespace() && piece.chars().all(char::is_whitespace) {
+        // cl100k has single tokens for indentation and blank-line runs
+        return piece.chars().count().div_ceil(16);
+    }
+    if first == '\'' && piece.len() <= 3 {
+        return 1;
+    }
+    if piece.chars().all(is_number) {
+        return 1;
+    }
+
+    let body = piece.strip_prefix(' ').unwrap_or(piece);
+    if body.chars().next().is_some_and(is_punct) {
+        let puncts = body.chars().filter(|c| is_punct(*c)).count();
+        // A leading `_`, `.` or `$` usually merges into the following word
+        let word = body.trim_start_matches(|c: char| is_punct(c) || is_newline(c));
+        if !word.is_empty() {
+            let lead = usize::from(!matches!(first, '_' | '.' | '$'));
+            return lead + word_tokens(word);
+        }
+        // Common operators and delimiters pair up into one token
+        return puncts.div_ceil(2);
+    }
+    word_tokens(body)
+}
+
+/// Tokens for a run of letters, split at camelCase humps
+fn word_tokens(word: &str) -> usize {
+    let chars: Vec<char> = word.chars().collect();
+    let mut tokens = 0;
+    let mut start = 0;
+    for idx in 1..=chars.len() {
+        let boundary = idx == chars.len()
+            || (chars[idx].is_uppercase() && !chars[idx - 1].is_uppercase())
+            || (chars[idx].is_uppercase()
+                && chars.get(idx + 1).is_some_and(|c| c.is_lowercase())
+                && chars[idx - 1].is_uppercase());
+        if boundary {
+            tokens += hump_tokens(&chars[start..idx]);
+            start = idx;
+        }
+    }
+    tokens
+}
+
+fn hump_tokens(hump: &[char]) -> usize {
+    if hump.len() <= 6 {
+        return 1;
+    }
+    let lower: String = hump.iter().flat_map(|c| c.to_lowercase()).collect();
+    if VOCAB.binary_search(&lower.as_str()).is_ok() {
+        return 1;
+    }
+    // Longer words average about four characters per subword token
+    1 + (hump.len() - 6).div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vocab_is_sorted_for_binary_search() {
+        assert!(VOCAB.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn splits_like_the_cl100k_pre_tokenizer() {
+        let split: Vec<&str> =
+            pieces("fn parse(input: &str) -> usize {\n    let n = 12345;\n}").collect();
+        assert_eq!(
+            split,
+            vec![
+                "fn", " parse", "(input", ":", " &", "str", ")", " ->", " usize", " {\n", "   ",
+                " let", " n", " =", " ", "123", "45", ";\n", "}"
+            ]
+        );
+        assert_eq!(pieces("it's").collect::<Vec<_>>(), vec!["it", "'s"]);
+        assert_eq!(
+            pieces("a  \n\n  b").collect::<Vec<_>>(),
+            vec!["a", "  \n\n", " ", " b"]
+        );
+    }
+
+    #[test]
+    fn counts_words_numbers_and_punctuation() {
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("return result"), 2);
+        assert_eq!(count_tokens("getElementById"), 4);
+        assert_eq!(count_tokens("x == y && z"), 5);
+        assert_eq!(count_tokens("        indented"), 3);
+        let code = "fn parse(input: &str) -> usize {\n    input.len()\n}\n";
+        let tokens = count_tokens(code);
+        assert!((12..=20).contains(&tokens), "{}", tokens);
+    }
+
+    #[test]
+    fn truncates_between_pieces() {
+        let text = "alpha beta gamma delta";
+        assert_eq!(truncate_to_tokens(text, 2), "alpha beta");
+        assert_eq!(truncate_to_tokens(text, 0), "");
+        assert_eq!(truncate_to_tokens(text, 10), text);
+    }
+}
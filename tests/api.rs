@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::fs;
+
+use cgrep::api::{
+    IndexOptions, Indexer, ReferenceKind, SearchMode, SearchRequest, Searcher, SymbolFilters,
+};
+use tempfile::TempDir;
+
+fn indexed_repo() -> TempDir {
+    let dir = TempDir::new().expect("tempdir");
+    fs::create_dir_all(dir.path().join("src")).expect("mkdir");
+    fs::write(
+        dir.path().join("src/config.rs"),
+        "pub struct Settings;\n\npub fn parse_settings() -> Settings {\n    Settings\n}\n",
+    )
+    .expect("write config");
+    fs::write(
+        dir.path().join("src/main.rs"),
+        "mod config;\n\nfn main() {\n    let _s = config::parse_settings();\n}\n",
+    )
+    .expect("write main");
+
+    let summary = Indexer::build(&IndexOptions::new(dir.path())).expect("index");
+    assert_eq!(summary.files.indexed, 2);
+    assert!(summary.embeddings.is_none());
+    dir
+}
+
+#[test]
+fn searcher_returns_typed_results() {
+    let dir = indexed_repo();
+    let searcher = Searcher::open(dir.path()).expect("open");
+
+    let response = searcher
+        .search(&SearchRequest::new("parse_settings"))
+        .expect("search");
+    assert!(response.used_index);
+    assert_eq!(response.mode, SearchMode::Keyword);
+    let paths: Vec<&str> = response.results.iter().map(|r| r.path.as_str()).collect();
+    assert!(paths.contains(&"src/config.rs"), "{:?}", paths);
+    assert!(paths.contains(&"src/main.rs"), "{:?}", paths);
+
+    let scoped = searcher
+        .search(&SearchRequest {
+            max_results: Some(1),
+            ..SearchRequest::new("parse_settings")
+        })
+        .expect("limited search");
+    assert_eq!(scoped.results.len(), 1);
+
+    let definitions = searcher.definition("parse_settings").expect("definition");
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(definitions[0].path, "src/config.rs");
+    assert_eq!(definitions[0].line, 3);
+
+    let symbols = searcher
+        .symbols("Settings", &SymbolFilters::default())
+        .expect("symbols");
+    assert!(symbols.iter().any(|s| s.kind == "struct"));
+
+    let callers = searcher.callers("parse_settings").expect("callers");
+    assert_eq!(callers.len(), 1);
+    assert_eq!(callers[0].path, "src/main.rs");
+    assert_eq!(callers[0].caller.as_deref(), Some("main"));
+
+    let definitions_only = searcher
+        .references("parse_settings", &[ReferenceKind::Definition], 10)
+        .expect("references");
+    assert_eq!(definitions_only.len(), 1);
+
+    let dependents = searcher.dependents("src/config.rs").expect("dependents");
+    assert_eq!(dependents.len(), 1);
+    assert_eq!(dependents[0].path, "src/main.rs");
+}
+
+#[test]
+fn incremental_build_reports_unchanged_files() {
+    let dir = indexed_repo();
+    let summary = Indexer::build(&IndexOptions::new(dir.path())).expect("reindex");
+    assert_eq!(summary.files.indexed, 0);
+    assert_eq!(summary.files.unchanged, 2);
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn cgrep_json(dir: &Path, args: &[&str]) -> Value {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let assert = cmd
+        .current_dir(dir)
+        .args(["--format", "json"])
+        .args(args)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).expect("utf8");
+    serde_json::from_str(&stdout).expect("json")
+}
+
+fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn search_routes_through_running_daemon() {
+    let dir = TempDir::new().expect("tempdir");
+    fs::write(
+        dir.path().join("lib.rs"),
+        "pub fn daemon_needle() -> u32 {\n    42\n}\n",
+    )
+    .expect("write");
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let status = cgrep_json(dir.path(), &["daemon", "status"]);
+    assert_eq!(status["running"], false);
+
+    let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin!("cgrep"))
+        .current_dir(dir.path())
+        .args(["daemon", "start", "--no-watch"])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .expect("spawn daemon");
+    let socket = dir.path().join(".cgrep").join("daemon.sock");
+    wait_for("daemon socket", || socket.exists());
+
+    let results = cgrep_json(dir.path(), &["search", "daemon_needle"]);
+    assert_eq!(results[0]["path"], "lib.rs");
+    assert_eq!(results[0]["line"], 1);
+
+    // The search above and this status call were both answered by the daemon
+    let status = cgrep_json(dir.path(), &["daemon", "status"]);
+    assert_eq!(status["running"], true);
+    assert_eq!(status["daemon"]["requests"], 2);
+    assert_eq!(status["daemon"]["watching"], false);
+
+    let mut stop = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    stop.current_dir(dir.path())
+        .args(["daemon", "stop"])
+        .assert()
+        .success();
+    wait_for("daemon exit", || daemon.try_wait().expect("wait").is_some());
+    assert!(!socket.exists());
+
+    // Without a daemon the CLI searches in-process
+    let results = cgrep_json(dir.path(), &["search", "daemon_needle"]);
+    assert_eq!(results[0]["path"], "lib.rs");
+}
@@ -246,3 +246,34 @@ fn index_auto_skips_on_schema_mismatch() {
     let storage = EmbeddingStorage::open_default(dir.path()).unwrap();
     assert_eq!(storage.count_symbols().unwrap(), 0);
 }
+
+#[test]
+fn semantic_search_answers_without_bm25_index() {
+    let dir = TempDir::new().unwrap();
+    write_dummy_embeddings_config(dir.path());
+    write_file(
+        &dir.path().join("src").join("lib.rs"),
+        "fn alpha() {}\nfn beta() {}\n",
+    );
+    run_index(dir.path(), &["--force", "--embeddings", "precompute"]);
+
+    // Keep only the embedding store
+    for entry in fs::read_dir(dir.path().join(".cgrep")).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .file_name()
+            .is_some_and(|name| name != "embeddings.sqlite")
+        {
+            if path.is_dir() {
+                fs::remove_dir_all(&path).unwrap();
+            } else {
+                fs::remove_file(&path).unwrap();
+            }
+        }
+    }
+
+    let mut cmd = cargo_bin_cmd!("cgrep");
+    cmd.current_dir(dir.path())
+        .args(["search", "alpha", "--mode", "semantic", "--format", "json"]);
+    cmd.assert().success().stdout(contains("fn alpha() {}"));
+}
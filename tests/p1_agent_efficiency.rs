@@ -202,3 +202,499 @@ fn json2_path_alias_and_boilerplate_suppression_work() {
         assert_eq!(result["snippet"], "[boilerplate suppressed]");
     }
 }
+
+#[test]
+fn scope_symbol_returns_enclosing_function_for_search_and_expand() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/lib.rs"),
+        "pub fn unrelated() {}\n\npub fn scoped_target(\n    input: &str,\n) -> usize {\n    let first = input.len();\n    let needle_value = first * 2;\n    needle_value + 1\n}\n\npub fn after() {}\n",
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let mut search_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let search_assert = search_cmd
+        .current_dir(dir.path())
+        .args([
+            "--format",
+            "json2",
+            "search",
+            "needle_value",
+            "--scope",
+            "symbol",
+        ])
+        .assert()
+        .success();
+    let search_json: Value =
+        serde_json::from_slice(&search_assert.get_output().stdout).expect("json");
+    let result = &search_json["results"][0];
+    assert_eq!(result["line"], 7);
+    assert_eq!(result["start_line"], 3);
+    assert_eq!(result["end_line"], 9);
+    assert_eq!(result["context_before"][0], "pub fn scoped_target(");
+    assert_eq!(result["context_after"][1], "}");
+
+    let id = result["id"].as_str().expect("id").to_string();
+    let mut expand_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let expand_assert = expand_cmd
+        .current_dir(dir.path())
+        .args(["agent", "expand", "--id", &id, "--scope", "signature"])
+        .assert()
+        .success();
+    let expand_json: Value =
+        serde_json::from_slice(&expand_assert.get_output().stdout).expect("json");
+    let expanded = &expand_json["results"][0];
+    assert_eq!(expanded["line"], 7);
+    assert_eq!(expanded["start_line"], 3);
+    assert_eq!(
+        expanded["context_before"],
+        serde_json::json!(["pub fn scoped_target(", "    input: &str,", ") -> usize {"])
+    );
+    assert!(expanded.get("context_after").is_none());
+}
+
+#[test]
+fn agent_context_packs_enclosing_symbols_within_budget() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/lib.rs"),
+        "pub fn unrelated() {}\n\npub fn scoped_target(\n    input: &str,\n) -> usize {\n    let first = input.len();\n    let needle_value = first * 2;\n    needle_value + 1\n}\n\npub fn after() {}\n",
+    );
+    write_file(
+        &dir.path().join("src/other.rs"),
+        "pub fn other(x: usize) -> usize {\n    let needle_value = x;\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    let d = 4;\n    needle_value + a + b + c + d\n}\n",
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let mut full_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let full_assert = full_cmd
+        .current_dir(dir.path())
+        .args(["agent", "context", "needle_value"])
+        .assert()
+        .success();
+    let full: Value = serde_json::from_slice(&full_assert.get_output().stdout).expect("json");
+    assert_eq!(full["meta"]["stage"], "context");
+    assert_eq!(full["meta"]["budget_tokens"], 4000);
+    assert_eq!(full["meta"]["included"], 2);
+    assert!(full["meta"]["dropped"]
+        .as_array()
+        .expect("dropped")
+        .is_empty());
+    let files = full["results"].as_array().expect("results");
+    let lib = files
+        .iter()
+        .find(|file| file["path"] == "src/lib.rs")
+        .expect("lib.rs block");
+    let block = &lib["blocks"][0];
+    assert_eq!(block["start_line"], 3);
+    assert_eq!(block["end_line"], 9);
+    assert_eq!(block["ids"].as_array().expect("ids").len(), 1);
+    assert!(block["text"]
+        .as_str()
+        .expect("text")
+        .starts_with("pub fn scoped_target("));
+
+    let mut small_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let small_assert = small_cmd
+        .current_dir(dir.path())
+        .args(["agent", "context", "needle_value", "--budget", "30"])
+        .assert()
+        .success();
+    let small: Value = serde_json::from_slice(&small_assert.get_output().stdout).expect("json");
+    let used = small["meta"]["tokens_used"].as_u64().expect("tokens_used");
+    assert!(used <= 30, "tokens_used {} over budget", used);
+    let dropped = small["meta"]["dropped"].as_array().expect("dropped");
+    assert!(!dropped.is_empty());
+    assert_eq!(dropped[0]["reason"], "budget");
+    assert!(dropped[0]["id"].as_str().is_some());
+}
+
+#[test]
+fn agent_context_reports_cheapest_cost_for_dropped_hits() {
+    let dir = TempDir::new().expect("tempdir");
+    let filler = "// lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor";
+    write_file(
+        &dir.path().join("src/lib.rs"),
+        &format!(
+            "{filler}\n{filler}\n{filler}\npub fn tiny() -> usize {{\n    cheap_needle()\n}}\n{filler}\n{filler}\n{filler}\n"
+        ),
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let context = |budget: &str| -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let assert = cmd
+            .current_dir(dir.path())
+            .args(["agent", "context", "cheap_needle", "--budget", budget])
+            .assert()
+            .success();
+        serde_json::from_slice(&assert.get_output().stdout).expect("json")
+    };
+
+    let full = context("4000");
+    assert_eq!(full["meta"]["included"], 1);
+    assert_eq!(full["results"][0]["blocks"][0]["start_line"], 4);
+    let cost = full["meta"]["tokens_used"].as_u64().expect("tokens_used");
+
+    // The enclosing function is cheaper than the fallback window around the hit.
+    let short = context(&(cost - 1).to_string());
+    let dropped = short["meta"]["dropped"].as_array().expect("dropped");
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0]["tokens"].as_u64(), Some(cost));
+}
+
+#[test]
+fn session_returns_already_sent_hits_as_references() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/lib.rs"),
+        "pub fn first() {\n    let session_needle = 1;\n}\n",
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let locate = |session: &str| -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let assert = cmd
+            .current_dir(dir.path())
+            .args(["agent", "locate", "session_needle", "--session", session])
+            .assert()
+            .success();
+        serde_json::from_slice(&assert.get_output().stdout).expect("json")
+    };
+
+    let first = locate("conv");
+    assert_eq!(first["meta"]["session"], "conv");
+    assert_eq!(first["results"].as_array().expect("results").len(), 1);
+    assert!(first.get("already_sent").is_none());
+    let id = first["results"][0]["id"].as_str().expect("id").to_string();
+
+    let second = locate("conv");
+    assert!(second["results"].as_array().expect("results").is_empty());
+    assert_eq!(second["already_sent"][0]["id"], id.as_str());
+    assert_eq!(second["already_sent"][0]["path"], "src/lib.rs");
+    assert_eq!(second["already_sent"][0]["line"], 2);
+
+    // Other sessions are unaffected
+    assert_eq!(
+        locate("other")["results"]
+            .as_array()
+            .expect("results")
+            .len(),
+        1
+    );
+
+    // A wider expand window was not sent yet; repeating it was
+    let expand = || -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let assert = cmd
+            .current_dir(dir.path())
+            .args(["agent", "expand", "--id", &id, "--session", "conv"])
+            .assert()
+            .success();
+        serde_json::from_slice(&assert.get_output().stdout).expect("json")
+    };
+    assert_eq!(expand()["results"].as_array().expect("results").len(), 1);
+    let repeated = expand();
+    assert!(repeated["results"].as_array().expect("results").is_empty());
+    assert_eq!(repeated["already_sent"][0]["id"], id.as_str());
+
+    // Editing the file makes its lines new again
+    write_file(
+        &dir.path().join("src/lib.rs"),
+        "pub fn first() {\n    let session_needle = 2;\n}\n",
+    );
+    let mut reindex_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    reindex_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+    assert_eq!(
+        locate("conv")["results"].as_array().expect("results").len(),
+        1
+    );
+}
+
+#[test]
+fn session_refills_result_slots_with_unseen_hits() {
+    let dir = TempDir::new().expect("tempdir");
+    for n in 1..=4 {
+        write_file(
+            &dir.path().join(format!("src/hit_{n}.rs")),
+            &format!("pub fn hit() {{\n    refill_needle({n});\n}}\n"),
+        );
+    }
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let locate = || -> Value {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let assert = cmd
+            .current_dir(dir.path())
+            .args([
+                "agent",
+                "locate",
+                "refill_needle",
+                "--limit",
+                "2",
+                "--session",
+                "conv",
+            ])
+            .assert()
+            .success();
+        serde_json::from_slice(&assert.get_output().stdout).expect("json")
+    };
+    let paths = |payload: &Value| -> Vec<String> {
+        let aliases = &payload["meta"]["path_aliases"];
+        payload["results"]
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|result| {
+                let path = result["path"].as_str().expect("path");
+                aliases[path].as_str().unwrap_or(path).to_string()
+            })
+            .collect()
+    };
+
+    let first = locate();
+    let second = locate();
+    assert_eq!(paths(&first).len(), 2);
+    assert_eq!(second["already_sent"].as_array().expect("sent").len(), 2);
+    let mut all = [paths(&first), paths(&second)].concat();
+    all.sort();
+    assert_eq!(
+        all,
+        vec![
+            "src/hit_1.rs",
+            "src/hit_2.rs",
+            "src/hit_3.rs",
+            "src/hit_4.rs"
+        ]
+    );
+    assert_eq!(second["meta"]["dropped_results"], 0);
+
+    let third = locate();
+    assert!(paths(&third).is_empty());
+    assert_eq!(third["already_sent"].as_array().expect("sent").len(), 4);
+}
+
+#[test]
+fn map_ranks_referenced_symbols_into_budgeted_skeleton() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/store.rs"),
+        "pub struct Store {\n    value: u32,\n}\n\nimpl Store {\n    pub fn fetch_value(&self) -> u32 {\n        self.value\n    }\n}\n",
+    );
+    write_file(
+        &dir.path().join("src/main.rs"),
+        "mod store;\n\nfn main() {\n    let store = store::Store { value: 1 };\n    println!(\"{}\", store.fetch_value());\n}\n",
+    );
+    write_file(
+        &dir.path().join("src/unused.rs"),
+        "pub fn never_called() {}\n",
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let mut map_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let map_assert = map_cmd
+        .current_dir(dir.path())
+        .args(["map", "--format", "json2"])
+        .assert()
+        .success();
+    let map: Value = serde_json::from_slice(&map_assert.get_output().stdout).expect("json");
+    assert_eq!(map["meta"]["budget_tokens"], 1024);
+    assert_eq!(map["meta"]["files_total"], 3);
+    let files = map["results"].as_array().expect("results");
+    assert_eq!(files[0]["path"], "src/store.rs");
+    assert_eq!(files[0]["symbols"][0]["name"], "Store");
+    assert_eq!(files[0]["symbols"][0]["kind"], "struct");
+    assert_eq!(files.last().expect("file")["path"], "src/unused.rs");
+
+    let mut markdown_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let markdown_assert = markdown_cmd
+        .current_dir(dir.path())
+        .args(["map", "--markdown", "--budget", "10", "src/store.rs"])
+        .assert()
+        .success();
+    let markdown = String::from_utf8_lossy(&markdown_assert.get_output().stdout).to_string();
+    assert!(markdown.starts_with("## `src/store.rs`\n"));
+    assert!(markdown.contains("- `pub struct Store` (struct, line 1)"));
+    assert!(!markdown.contains("main.rs"));
+}
+
+#[test]
+fn outline_returns_nested_symbol_tree() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/store.rs"),
+        "pub struct Store {\n    value: u32,\n}\n\nimpl Store {\n    pub fn get(\n        &self,\n    ) -> u32 {\n        self.value\n    }\n}\n",
+    );
+    write_file(
+        &dir.path().join("app/calc.py"),
+        "class Calculator:\n    def add(self, a, b):\n        return a + b\n",
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let assert = cmd
+        .current_dir(dir.path())
+        .args([
+            "outline",
+            "src/store.rs",
+            "app/calc.py",
+            "--format",
+            "json2",
+        ])
+        .assert()
+        .success();
+    let outline: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(outline["meta"]["files"], 2);
+    assert_eq!(outline["meta"]["symbols"], 5);
+
+    let store = &outline["results"][0];
+    assert_eq!(store["path"], "src/store.rs");
+    assert_eq!(store["language"], "rust");
+    assert_eq!(store["symbols"][0]["kind"], "struct");
+    let impl_block = &store["symbols"][1];
+    assert_eq!(impl_block["kind"], "impl");
+    assert_eq!(impl_block["line"], 5);
+    assert_eq!(impl_block["end_line"], 11);
+    let get = &impl_block["children"][0];
+    assert_eq!(get["name"], "get");
+    assert_eq!(get["signature"], "pub fn get(&self) -> u32");
+    assert_eq!(
+        (get["line"].as_u64(), get["end_line"].as_u64()),
+        (Some(6), Some(10))
+    );
+
+    let calc = &outline["results"][1];
+    assert_eq!(calc["symbols"][0]["name"], "Calculator");
+    assert_eq!(calc["symbols"][0]["children"][0]["name"], "add");
+
+    let mut missing_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    missing_cmd
+        .current_dir(dir.path())
+        .args(["outline", "src/missing.rs"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn structured_query_filters_match_across_search_and_agent_locate() {
+    let dir = TempDir::new().expect("tempdir");
+    write_file(
+        &dir.path().join("src/indexer/build.rs"),
+        "pub fn build_index() -> usize {\n    1\n}\n\npub const BUILD_LIMIT: usize = 3;\n",
+    );
+    write_file(
+        &dir.path().join("src/build.py"),
+        "def build_index():\n    return 1\n",
+    );
+    write_file(
+        &dir.path().join("tests/build.rs"),
+        "fn build_index_test() {}\n",
+    );
+
+    let mut index_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    index_cmd
+        .current_dir(dir.path())
+        .args(["index", "--embeddings", "off"])
+        .assert()
+        .success();
+
+    let hits = |query: &str| {
+        let mut search_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let search_assert = search_cmd
+            .current_dir(dir.path())
+            .args(["search", query, "--format", "json"])
+            .assert()
+            .success();
+        let search: Value =
+            serde_json::from_slice(&search_assert.get_output().stdout).expect("json");
+        let mut search_hits: Vec<(String, u64)> = search
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|r| {
+                (
+                    r["path"].as_str().expect("path").to_string(),
+                    r["line"].as_u64().expect("line"),
+                )
+            })
+            .collect();
+        search_hits.sort();
+
+        let mut locate_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+        let locate_assert = locate_cmd
+            .current_dir(dir.path())
+            .args(["agent", "locate", query])
+            .assert()
+            .success();
+        let locate: Value =
+            serde_json::from_slice(&locate_assert.get_output().stdout).expect("json");
+        let aliases = &locate["meta"]["path_aliases"];
+        let mut locate_hits: Vec<(String, u64)> = locate["results"]
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|r| {
+                let path = r["path"].as_str().expect("path");
+                (
+                    aliases[path].as_str().unwrap_or(path).to_string(),
+                    r["line"].as_u64().expect("line"),
+                )
+            })
+            .collect();
+        locate_hits.sort();
+        assert_eq!(locate_hits, search_hits, "{}", query);
+        search_hits
+    };
+
+    assert_eq!(
+        hits("kind:function lang:rust build -test"),
+        vec![("src/indexer/build.rs".to_string(), 1)]
+    );
+    // A leading exclusion is a query, not a flag
+    assert_eq!(
+        hits("-test build"),
+        vec![
+            ("src/build.py".to_string(), 1),
+            ("src/indexer/build.rs".to_string(), 1),
+        ]
+    );
+}
@@ -81,3 +81,39 @@ fn max_total_chars_sets_json2_budget_meta() {
     assert_eq!(json["meta"]["max_total_chars"], 80);
     assert!(json["meta"]["truncated"].is_boolean());
 }
+
+#[test]
+fn max_tokens_replaces_preset_total_and_reports_usage() {
+    let dir = TempDir::new().expect("tempdir");
+    let content = (0..20)
+        .map(|i| format!("needle line {} with some repeated words", i + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+    write_file(&dir.path().join("sample.txt"), &content);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cgrep"));
+    let assert = cmd
+        .current_dir(dir.path())
+        .args([
+            "--format",
+            "json2",
+            "search",
+            "needle",
+            "--no-index",
+            "--budget",
+            "balanced",
+            "--max-tokens",
+            "30",
+        ])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).expect("utf8");
+    let json: Value = serde_json::from_str(&stdout).expect("json2");
+
+    assert_eq!(json["meta"]["max_tokens"], 30);
+    assert!(json["meta"].get("max_total_chars").is_none());
+    let used = json["meta"]["tokens_used"].as_u64().expect("tokens_used");
+    assert!(used > 0 && used <= 30, "{}", used);
+    assert_eq!(json["meta"]["truncated"], true);
+}